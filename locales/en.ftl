@@ -0,0 +1,142 @@
+# English GUI strings. Keys missing from other locales fall back to these.
+
+menu-views = Views
+menu-options = Options
+menu-help = Help
+menu-about = About...
+menu-paste-rom = Paste ROM from clipboard
+menu-reset-rom = Reset ROM
+menu-pause = Pause
+menu-resume = Resume
+menu-step = Step
+
+window-cpu-info = CPU Info
+window-explain = Explain
+window-disassembly = Disassembly
+window-settings = Settings
+window-tutorial = Tutorial
+window-comparison = Quirk Comparison
+window-rom-info = About this ROM
+window-keypad = Keypad
+window-sprite-editor = Sprite Editor
+window-source = Source
+
+settings-palette = Palette
+settings-rom-palette = Use the ROM's own colors
+settings-font = Font (restarts the ROM)
+settings-high-contrast = High contrast GUI
+settings-slow-motion = Slow motion
+settings-slow-motion-speed = Slow-motion speed
+
+cpu-stack = Stack: { $depth }/{ $size } (deepest { $max })
+cpu-display-hash = Display hash: { $hash }
+cpu-platform = Platform: { $platform } ({ $origin })
+cpu-no-platform = none
+cpu-quirks = Quirks: { $quirks } ({ $origin })
+
+origin-command-line = command line
+origin-config = ROM config
+origin-octo-options = Octo options
+origin-rom-database = ROM database
+origin-default = default
+
+disasm-affects = Affects: { $registers }
+disasm-quirks = Quirks: { $notes }
+asm-at = Assemble at { $address }:
+
+comparison-frame = Frame: { $frame } ({ $instructions } instructions)
+comparison-diverged = Displays diverged in frame { $frame }, at instruction { $instruction }
+comparison-identical = Displays identical so far
+
+rom-info-authors = By { $authors }
+rom-info-platform = Platform: { $platform }
+rom-info-untitled = Untitled
+
+sprite-address = Address
+sprite-rows = Rows
+sprite-paused-only = Pause to edit pixels.
+sprite-export = Export
+
+window-memory = Memory
+heat-execute = Executed
+heat-read = Read
+heat-write = Written
+heat-clear = Clear
+heat-counts = { $address }: executed { $executes }, read { $reads }, written { $writes }
+
+window-display = Display
+window-trace = Trace
+trace-fault = Fault: { $message }
+window-breakpoints = Breakpoints
+breakpoints-none = No breakpoints set.
+breakpoints-remove = Remove
+breakpoints-add = Add (hex)
+breakpoints-invalid = Not an address: { $input }
+breakpoints-conditions = Break when:
+breakpoints-add-condition = Add condition
+breakpoints-watchpoints = Watch memory:
+breakpoints-add-watchpoint = Add watchpoint
+watch-hit-read = Last hit: { $range } read by { $opcode } ({ $instruction }) at { $pc }
+watch-hit-write = Last hit: { $range } written by { $opcode } ({ $instruction }) at { $pc }
+window-profiler = Profiler
+profiler-opcodes = Slowest opcode types (total, count, mean)
+profiler-addresses = Slowest instructions (total, count, mean)
+
+window-hotkeys = Keyboard shortcuts
+hotkey-quit = Quit
+hotkey-pause = Pause / resume
+hotkey-dump-ascii = Print the display as block art
+hotkey-help = Show this help
+hotkey-save-section = Practice: start a new section here
+hotkey-retry-section = Practice: retry the current section
+hotkey-reset-run = Practice: restart the run
+hotkey-paste-rom = Paste hex bytes from the clipboard as a ROM
+hotkey-screenshot = Save a screenshot that can be reopened as a savestate
+hotkey-reset-rom = Start the ROM over
+hotkey-fast-forward = Fast-forward while held
+hotkey-slow-motion = Slow motion on / off
+hotkey-frame-advance = While paused: run one frame
+hotkey-step = While paused: run one instruction
+
+status-bar = PC { $pc }  I { $i }  DT { $dt }  ST { $st }{ $beep }  { $ips } IPS  quirks: { $quirks }  { $state }
+state-running = running
+state-booting = booting
+state-tutorial = tutorial
+state-paused = paused ({ $reason })
+
+practice-time = Time { $time }  (frame { $frames })
+practice-section = Section { $section }: { $time }
+practice-keys = Keys: { $keys }
+
+paused = || PAUSED ({ $reason })
+pause-user = user
+pause-breakpoint = breakpoint
+pause-condition = condition
+pause-watchpoint = watchpoint
+pause-minimized = minimized
+pause-unfocused = window not focused
+pause-fault = fault
+pause-finished = program finished
+
+fast-forward = >> { $speed }x
+slow-motion = SLOW { $percent }%
+time-skipped = Skipped { $seconds } s after a stall
+
+tutorial-next = Next
+tutorial-fetch = Fetch: read the two bytes at PC to form the opcode.
+tutorial-decode = Decode: split the opcode into its nibbles to find the instruction.
+tutorial-execute = Execute: perform the instruction and move PC along.
+tutorial-fetched = PC = { $pc }: bytes { $bytes } -> opcode { $opcode }
+tutorial-decoded = { $opcode } is { $instruction }
+tutorial-pc-now = PC is now { $pc }
+tutorial-display-updated = The display was updated.
+tutorial-note-0 = Clear the screen so the program starts from a blank display.
+tutorial-note-1 = Put the x coordinate of our drawing in register VA.
+tutorial-note-2 = Put the y coordinate in register VB.
+tutorial-note-3 = VC holds the digit we want to show on screen.
+tutorial-note-4 = Point I at the built-in font glyph for the digit in VC.
+tutorial-note-5 = Draw the 5-row glyph at I to (VA, VB). Watch the display!
+tutorial-note-6 = Move VA right by 6 pixels so the next sprite sits beside the digit.
+tutorial-note-7 = Point I at the sprite data bundled at the end of the program.
+tutorial-note-8 = Draw the 4-row smiley sprite. VF would be set if it hit lit pixels.
+tutorial-note-9 = Jump to this very instruction: the program is finished and loops here.
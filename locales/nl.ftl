@@ -0,0 +1,142 @@
+# Nederlandse GUI-teksten.
+
+menu-views = Weergave
+menu-options = Opties
+menu-help = Help
+menu-about = Over...
+menu-paste-rom = ROM plakken vanaf klembord
+menu-reset-rom = ROM herstarten
+menu-pause = Pauzeren
+menu-resume = Hervatten
+menu-step = Stap
+
+window-cpu-info = CPU-info
+window-explain = Uitleg
+window-disassembly = Disassembly
+window-settings = Instellingen
+window-tutorial = Rondleiding
+window-comparison = Quirk-vergelijking
+window-rom-info = Over deze ROM
+window-keypad = Toetsenblok
+window-sprite-editor = Sprite-editor
+window-source = Broncode
+
+settings-palette = Kleurenpalet
+settings-rom-palette = Kleuren van de ROM gebruiken
+settings-font = Lettertype (herstart de ROM)
+settings-high-contrast = GUI met hoog contrast
+settings-slow-motion = Slow motion
+settings-slow-motion-speed = Snelheid in slow motion
+
+cpu-stack = Stack: { $depth }/{ $size } (diepste { $max })
+cpu-display-hash = Schermhash: { $hash }
+cpu-platform = Platform: { $platform } ({ $origin })
+cpu-no-platform = geen
+cpu-quirks = Quirks: { $quirks } ({ $origin })
+
+origin-command-line = opdrachtregel
+origin-config = ROM-configuratie
+origin-octo-options = Octo-opties
+origin-rom-database = ROM-database
+origin-default = standaard
+
+disasm-affects = Wijzigt: { $registers }
+disasm-quirks = Quirks: { $notes }
+asm-at = Assembleren op { $address }:
+
+comparison-frame = Frame: { $frame } ({ $instructions } instructies)
+comparison-diverged = Schermen verschillen vanaf frame { $frame }, bij instructie { $instruction }
+comparison-identical = Schermen tot nu toe gelijk
+
+rom-info-authors = Door { $authors }
+rom-info-platform = Platform: { $platform }
+rom-info-untitled = Zonder titel
+
+sprite-address = Adres
+sprite-rows = Rijen
+sprite-paused-only = Pauzeer om pixels te bewerken.
+sprite-export = Exporteren
+
+window-memory = Geheugen
+heat-execute = Uitgevoerd
+heat-read = Gelezen
+heat-write = Geschreven
+heat-clear = Wissen
+heat-counts = { $address }: { $executes }× uitgevoerd, { $reads }× gelezen, { $writes }× geschreven
+
+window-display = Scherm
+window-trace = Spoor
+trace-fault = Fout: { $message }
+window-breakpoints = Breekpunten
+breakpoints-none = Geen breekpunten ingesteld.
+breakpoints-remove = Verwijderen
+breakpoints-add = Toevoegen (hex)
+breakpoints-invalid = Geen adres: { $input }
+breakpoints-conditions = Pauzeren als:
+breakpoints-add-condition = Voorwaarde toevoegen
+breakpoints-watchpoints = Geheugen bewaken:
+breakpoints-add-watchpoint = Bewaakpunt toevoegen
+watch-hit-read = Laatst geraakt: { $range } gelezen door { $opcode } ({ $instruction }) op { $pc }
+watch-hit-write = Laatst geraakt: { $range } geschreven door { $opcode } ({ $instruction }) op { $pc }
+window-profiler = Profiler
+profiler-opcodes = Traagste opcodesoorten (totaal, aantal, gemiddeld)
+profiler-addresses = Traagste instructies (totaal, aantal, gemiddeld)
+
+window-hotkeys = Sneltoetsen
+hotkey-quit = Afsluiten
+hotkey-pause = Pauzeren / hervatten
+hotkey-dump-ascii = Scherm als blokkentekening afdrukken
+hotkey-help = Deze hulp tonen
+hotkey-save-section = Oefenen: begin hier een nieuw deel
+hotkey-retry-section = Oefenen: huidig deel opnieuw
+hotkey-reset-run = Oefenen: run opnieuw beginnen
+hotkey-paste-rom = Hexbytes van het klembord als ROM laden
+hotkey-screenshot = Schermafbeelding opslaan die als savestate te openen is
+hotkey-reset-rom = ROM opnieuw starten
+hotkey-fast-forward = Vooruitspoelen zolang ingedrukt
+hotkey-slow-motion = Slow motion aan / uit
+hotkey-frame-advance = Tijdens pauze: één frame uitvoeren
+hotkey-step = Tijdens pauze: één instructie uitvoeren
+
+status-bar = PC { $pc }  I { $i }  DT { $dt }  ST { $st }{ $beep }  { $ips } IPS  quirks: { $quirks }  { $state }
+state-running = actief
+state-booting = opstarten
+state-tutorial = rondleiding
+state-paused = gepauzeerd ({ $reason })
+
+practice-time = Tijd { $time }  (frame { $frames })
+practice-section = Deel { $section }: { $time }
+practice-keys = Toetsen: { $keys }
+
+paused = || GEPAUZEERD ({ $reason })
+pause-user = gebruiker
+pause-breakpoint = breekpunt
+pause-condition = voorwaarde
+pause-watchpoint = bewaakpunt
+pause-minimized = geminimaliseerd
+pause-unfocused = venster niet actief
+pause-fault = fout
+pause-finished = programma afgelopen
+
+fast-forward = >> { $speed }x
+slow-motion = TRAAG { $percent }%
+time-skipped = { $seconds } s overgeslagen na een hapering
+
+tutorial-next = Volgende
+tutorial-fetch = Ophalen: lees de twee bytes op PC om de opcode te vormen.
+tutorial-decode = Decoderen: splits de opcode in nibbles om de instructie te vinden.
+tutorial-execute = Uitvoeren: voer de instructie uit en schuif PC op.
+tutorial-fetched = PC = { $pc }: bytes { $bytes } -> opcode { $opcode }
+tutorial-decoded = { $opcode } is { $instruction }
+tutorial-pc-now = PC is nu { $pc }
+tutorial-display-updated = Het scherm is bijgewerkt.
+tutorial-note-0 = Wis het scherm zodat het programma met een leeg scherm begint.
+tutorial-note-1 = Zet de x-coördinaat van de tekening in register VA.
+tutorial-note-2 = Zet de y-coördinaat in register VB.
+tutorial-note-3 = VC bevat het cijfer dat we op het scherm willen tonen.
+tutorial-note-4 = Laat I wijzen naar het ingebouwde lettertype-teken voor het cijfer in VC.
+tutorial-note-5 = Teken het teken van 5 rijen op I bij (VA, VB). Let op het scherm!
+tutorial-note-6 = Schuif VA 6 pixels naar rechts zodat de volgende sprite naast het cijfer komt.
+tutorial-note-7 = Laat I wijzen naar de spritedata aan het einde van het programma.
+tutorial-note-8 = Teken de smiley van 4 rijen. VF wordt gezet als hij verlichte pixels raakt.
+tutorial-note-9 = Spring naar deze instructie zelf: het programma is klaar en blijft hier hangen.
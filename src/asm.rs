@@ -0,0 +1,326 @@
+//! A small assembler, used to patch memory from the debugger and to load
+//! assembly sources directly.
+//!
+//! Accepts the mnemonics the disassembler prints (`JP 0x200`, `ADD V3, 5`)
+//! as well as the common Octo statements (`jump 0x200`, `v3 += 5`).
+
+use crate::cpu::PC_START;
+use crate::sourcemap::SourceMap;
+
+/// Assembles a single instruction into its opcode.
+pub fn assemble(line: &str) -> Result<u16, String> {
+    let line = line.to_ascii_lowercase().replace(',', " ");
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.as_slice() {
+        [] => return Err("nothing to assemble".to_string()),
+        [first, ..] if is_octo(&tokens) || first.starts_with('v') || *first == "i" => octo(&tokens),
+        _ => cowgod(&tokens),
+    }
+    .ok_or_else(|| format!("cannot assemble \"{}\"", line.trim()))
+}
+
+fn is_octo(tokens: &[&str]) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "clear",
+        "return",
+        "exit",
+        "lores",
+        "hires",
+        "scroll-down",
+        "scroll-up",
+        "scroll-right",
+        "scroll-left",
+        "jump",
+        "jump0",
+        "delay",
+        "buzzer",
+        "bcd",
+        "save",
+        "load",
+        "saveflags",
+        "loadflags",
+        "sprite",
+        "plane",
+        "audio",
+        "pitch",
+    ];
+    KEYWORDS.contains(&tokens[0])
+}
+
+fn cowgod(tokens: &[&str]) -> Option<u16> {
+    let opcode = match *tokens {
+        ["cls"] => 0x00E0,
+        ["ret"] => 0x00EE,
+        ["scd", n] => operand(n, 0xF)? | 0x00C0,
+        ["scu", n] => operand(n, 0xF)? | 0x00D0,
+        ["scr"] => 0x00FB,
+        ["scl"] => 0x00FC,
+        ["low"] => 0x00FE,
+        ["high"] => 0x00FF,
+        ["megaoff"] => 0x0010,
+        ["megaon"] => 0x0011,
+        ["scru", n] => operand(n, 0xF)? | 0x00B0,
+        ["ldpal", nn] => operand(nn, 0xFF)? | 0x0200,
+        ["sprw", nn] => operand(nn, 0xFF)? | 0x0300,
+        ["sprh", nn] => operand(nn, 0xFF)? | 0x0400,
+        ["alpha", nn] => operand(nn, 0xFF)? | 0x0500,
+        ["digisnd", n] => operand(n, 0xF)? | 0x0600,
+        ["stopsnd"] => 0x0700,
+        ["bmode", n] => operand(n, 0xF)? | 0x0800,
+        ["ccol", nn] => operand(nn, 0xFF)? | 0x0900,
+        ["sys", nnn] => address(0x0000, nnn)?,
+        ["jp", "v0", nnn] => address(0xB000, nnn)?,
+        ["jp", nnn] => address(0x1000, nnn)?,
+        ["call", nnn] => address(0x2000, nnn)?,
+        ["se", x, y] => match register(y) {
+            Some(y) => xy(0x5000, register(x)?, y),
+            None => xnn(0x3000, register(x)?, y)?,
+        },
+        ["sne", x, y] => match register(y) {
+            Some(y) => xy(0x9000, register(x)?, y),
+            None => xnn(0x4000, register(x)?, y)?,
+        },
+        ["ld", "i", nnn] => address(0xA000, nnn)?,
+        ["ld", "dt", x] => x_op(0xF015, register(x)?),
+        ["ld", "st", x] => x_op(0xF018, register(x)?),
+        ["ld", "f", x] => x_op(0xF029, register(x)?),
+        ["ld", "hf", x] => x_op(0xF030, register(x)?),
+        ["ld", "b", x] => x_op(0xF033, register(x)?),
+        ["ld", "[i]", x] => x_op(0xF055, register(x)?),
+        ["ld", "r", x] => x_op(0xF075, register(x)?),
+        ["ld", x, "dt"] => x_op(0xF007, register(x)?),
+        ["ld", x, "k"] => x_op(0xF00A, register(x)?),
+        ["ld", x, "[i]"] => x_op(0xF065, register(x)?),
+        ["ld", x, "r"] => x_op(0xF085, register(x)?),
+        ["ld", x, y] => match register(y) {
+            Some(y) => xy(0x8000, register(x)?, y),
+            None => xnn(0x6000, register(x)?, y)?,
+        },
+        ["add", "i", x] => x_op(0xF01E, register(x)?),
+        ["add", x, y] => match register(y) {
+            Some(y) => xy(0x8004, register(x)?, y),
+            None => xnn(0x7000, register(x)?, y)?,
+        },
+        ["or", x, y] => xy(0x8001, register(x)?, register(y)?),
+        ["and", x, y] => xy(0x8002, register(x)?, register(y)?),
+        ["xor", x, y] => xy(0x8003, register(x)?, register(y)?),
+        ["sub", x, y] => xy(0x8005, register(x)?, register(y)?),
+        ["shr", x, y] => xy(0x8006, register(x)?, register(y)?),
+        ["shr", x] => xy(0x8006, register(x)?, register(x)?),
+        ["subn", x, y] => xy(0x8007, register(x)?, register(y)?),
+        ["shl", x, y] => xy(0x800E, register(x)?, register(y)?),
+        ["shl", x] => xy(0x800E, register(x)?, register(x)?),
+        ["rnd", x, nn] => xnn(0xC000, register(x)?, nn)?,
+        ["drw", x, y, n] => sprite(register(x)?, register(y)?, n)?,
+        ["skp", x] => x_op(0xE09E, register(x)?),
+        ["sknp", x] => x_op(0xE0A1, register(x)?),
+        ["dw", word] => operand(word, 0xFFFF)?,
+        _ => return None,
+    };
+    Some(opcode)
+}
+
+fn octo(tokens: &[&str]) -> Option<u16> {
+    let opcode = match *tokens {
+        ["clear"] => 0x00E0,
+        ["return"] => 0x00EE,
+        ["exit"] => 0x00FD,
+        ["scroll-down", n] => operand(n, 0xF)? | 0x00C0,
+        ["scroll-up", n] => operand(n, 0xF)? | 0x00D0,
+        ["scroll-right"] => 0x00FB,
+        ["scroll-left"] => 0x00FC,
+        ["lores"] => 0x00FE,
+        ["hires"] => 0x00FF,
+        ["jump", nnn] => address(0x1000, nnn)?,
+        ["jump0", nnn] => address(0xB000, nnn)?,
+        ["i", ":=", "hex", x] => x_op(0xF029, register(x)?),
+        ["i", ":=", "bighex", x] => x_op(0xF030, register(x)?),
+        ["i", ":=", nnn] => address(0xA000, nnn)?,
+        ["i", "+=", x] => x_op(0xF01E, register(x)?),
+        ["delay", ":=", x] => x_op(0xF015, register(x)?),
+        ["buzzer", ":=", x] => x_op(0xF018, register(x)?),
+        ["bcd", x] => x_op(0xF033, register(x)?),
+        ["save", x] => x_op(0xF055, register(x)?),
+        ["load", x] => x_op(0xF065, register(x)?),
+        ["save", x, "-", y] => xy(0x5002, register(x)?, register(y)?),
+        ["load", x, "-", y] => xy(0x5003, register(x)?, register(y)?),
+        ["saveflags", x] => x_op(0xF075, register(x)?),
+        ["loadflags", x] => x_op(0xF085, register(x)?),
+        ["sprite", x, y, n] => sprite(register(x)?, register(y)?, n)?,
+        [x, ":=", "delay"] => x_op(0xF007, register(x)?),
+        [x, ":=", "key"] => x_op(0xF00A, register(x)?),
+        ["plane", n] => operand(n, 0x3)? << 8 | 0xF001,
+        ["audio"] => 0xF002,
+        ["pitch", ":=", x] => x_op(0xF03A, register(x)?),
+        [x, ":=", "random", nn] => xnn(0xC000, register(x)?, nn)?,
+        [x, op, y] => {
+            let x = register(x)?;
+            match (op, register(y)) {
+                (":=", Some(y)) => xy(0x8000, x, y),
+                ("|=", Some(y)) => xy(0x8001, x, y),
+                ("&=", Some(y)) => xy(0x8002, x, y),
+                ("^=", Some(y)) => xy(0x8003, x, y),
+                ("+=", Some(y)) => xy(0x8004, x, y),
+                ("-=", Some(y)) => xy(0x8005, x, y),
+                (">>=", Some(y)) => xy(0x8006, x, y),
+                ("=-", Some(y)) => xy(0x8007, x, y),
+                ("<<=", Some(y)) => xy(0x800E, x, y),
+                (":=", None) => xnn(0x6000, x, y)?,
+                ("+=", None) => xnn(0x7000, x, y)?,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(opcode)
+}
+
+fn register(token: &str) -> Option<u16> {
+    let digit = token.strip_prefix('v')?;
+    match u16::from_str_radix(digit, 16) {
+        Ok(x) if digit.len() == 1 => Some(x),
+        _ => None,
+    }
+}
+
+/// Parses `0x1F`, `#1F`, `0b11111` or `31`.
+fn number(token: &str) -> Option<u32> {
+    let (digits, radix) = if let Some(hex) = token.strip_prefix("0x").or(token.strip_prefix('#')) {
+        (hex, 16)
+    } else if let Some(binary) = token.strip_prefix("0b") {
+        (binary, 2)
+    } else if token.starts_with(|c: char| c.is_ascii_digit()) {
+        (token, 10)
+    } else {
+        return None;
+    };
+    u32::from_str_radix(digits, radix).ok()
+}
+
+fn operand(token: &str, max: u32) -> Option<u16> {
+    number(token)
+        .filter(|value| *value <= max)
+        .map(|value| value as u16)
+}
+
+fn address(base: u16, token: &str) -> Option<u16> {
+    operand(token, 0xFFF).map(|nnn| base | nnn)
+}
+
+fn xnn(base: u16, x: u16, token: &str) -> Option<u16> {
+    operand(token, 0xFF).map(|nn| base | x << 8 | nn)
+}
+
+fn sprite(x: u16, y: u16, token: &str) -> Option<u16> {
+    operand(token, 0xF).map(|n| 0xD000 | x << 8 | y << 4 | n)
+}
+
+fn xy(base: u16, x: u16, y: u16) -> u16 {
+    base | x << 8 | y << 4
+}
+
+fn x_op(base: u16, x: u16) -> u16 {
+    base | x << 8
+}
+
+/// An assembled program together with where each instruction came from.
+pub struct Program {
+    pub rom: Vec<u8>,
+    pub source_map: SourceMap,
+}
+
+/// Assembles a whole source file, one instruction or row of data bytes per
+/// line. Labels are declared Octo-style (`: loop`) or as `loop:` and may be
+/// used wherever an address is expected; a line holding just a label calls
+/// it. Comments start with `#` or `;`.
+pub fn assemble_program(source: &str) -> Result<Program, String> {
+    let strip = |line: &str| {
+        line.split(['#', ';'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    // First pass: lay out addresses and collect labels.
+    let mut labels = Vec::new();
+    let mut layout = Vec::new();
+    let mut address = PC_START;
+    for (number, line) in source.lines().enumerate() {
+        let line = strip(line);
+        let label = line
+            .strip_prefix(": ")
+            .or_else(|| line.strip_suffix(':'))
+            .map(str::trim);
+        if let Some(label) = label {
+            labels.push((label.to_ascii_lowercase(), address));
+        } else if !line.is_empty() {
+            let size = match data(&line) {
+                Some(bytes) => bytes.len(),
+                None if long_index(&line.to_ascii_lowercase()).is_some() => 4,
+                None => 2,
+            };
+            layout.push((number, address, line));
+            address += size;
+        }
+    }
+
+    // Second pass: substitute label addresses and assemble.
+    let mut rom = Vec::new();
+    let mut addresses = Vec::new();
+    for (number, address, line) in layout {
+        let lower = line.to_ascii_lowercase();
+        let resolved: Vec<String> = lower
+            .replace(',', " ")
+            .split_whitespace()
+            .map(
+                |token| match labels.iter().find(|(label, _)| label == token) {
+                    Some((_, address)) => format!("{:#05x}", address),
+                    None => token.to_string(),
+                },
+            )
+            .collect();
+        let resolved = match resolved.as_slice() {
+            [target] if labels.iter().any(|(label, _)| *label == lower) => {
+                format!("call {}", target)
+            }
+            _ => resolved.join(" "),
+        };
+        if let Some(target) = long_index(&resolved) {
+            let nnnn = operand(target, 0xFFFF)
+                .ok_or_else(|| format!("line {}: bad address \"{}\"", number + 1, target))?;
+            rom.extend(0xF000u16.to_be_bytes());
+            rom.extend(nnnn.to_be_bytes());
+        } else if let Some(bytes) = data(&resolved) {
+            rom.extend(bytes);
+        } else {
+            let opcode = assemble(&resolved).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            rom.extend(opcode.to_be_bytes());
+        }
+        addresses.push((address as u16, number));
+    }
+
+    Ok(Program {
+        rom,
+        source_map: SourceMap {
+            lines: source.lines().map(str::to_string).collect(),
+            addresses,
+        },
+    })
+}
+
+/// The address in Octo's `i := long NNNN`, the one four-byte instruction,
+/// which [`assemble`] can't produce.
+fn long_index(line: &str) -> Option<&str> {
+    match *line.split_whitespace().collect::<Vec<_>>() {
+        ["i", ":=", "long", target] => Some(target),
+        _ => None,
+    }
+}
+
+/// A row of plain numbers is emitted as data bytes.
+fn data(line: &str) -> Option<Vec<u8>> {
+    line.split_whitespace()
+        .map(|token| operand(&token.to_ascii_lowercase(), 0xFF).map(|byte| byte as u8))
+        .collect()
+}
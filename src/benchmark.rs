@@ -0,0 +1,112 @@
+//! Measures how fast the interpreter runs, so performance regressions show
+//! up as numbers. A program is run flat out for a while to count
+//! instructions per second, then run again timing every instruction, which
+//! slows it down too much to count both in one go.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::cpu::{Cpu, CpuEvent};
+use crate::profiler::{Cost, Profiler};
+
+/// How many instructions run between looks at the clock while counting, so
+/// reading it doesn't weigh on the result.
+const CLOCK_INTERVAL: u32 = 1024;
+
+/// How many opcode types to report.
+const TOP: usize = 16;
+
+pub struct Report {
+    instructions: u64,
+    elapsed: Duration,
+    /// The emulated speed, to compare the host's against.
+    instructions_per_second: f32,
+    opcodes: Vec<(&'static str, Cost)>,
+    /// Why a run ended early, if it did.
+    stopped: Option<String>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rate = self.instructions as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON);
+        writeln!(
+            f,
+            "{} instructions in {:.2} s: {:.0} IPS, {:.0}x the emulated {} IPS",
+            self.instructions,
+            self.elapsed.as_secs_f64(),
+            rate,
+            rate / self.instructions_per_second as f64,
+            self.instructions_per_second
+        )?;
+        if let Some(reason) = &self.stopped {
+            writeln!(f, "stopped early: {}", reason)?;
+        }
+        writeln!(f, "opcode        total ms      count  mean ns")?;
+        for (pattern, cost) in &self.opcodes {
+            writeln!(
+                f,
+                "{:<8} {:>13.2} {:>10} {:>8}",
+                pattern,
+                cost.total.as_secs_f64() * 1000.0,
+                cost.count,
+                cost.mean().as_nanos()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `cpu` as fast as it goes for `duration`, then a copy of it for as
+/// long again timing each instruction.
+pub fn run(cpu: Cpu, duration: Duration, instructions_per_second: f32) -> Report {
+    let mut timed = cpu.clone();
+    let (instructions, elapsed, stopped) = count(cpu, duration);
+    let profiler = profile(&mut timed, duration);
+    Report {
+        instructions,
+        elapsed,
+        instructions_per_second,
+        opcodes: profiler.top_opcodes(TOP),
+        stopped,
+    }
+}
+
+fn count(mut cpu: Cpu, duration: Duration) -> (u64, Duration, Option<String>) {
+    let started = Instant::now();
+    let mut instructions = 0;
+    loop {
+        for _ in 0..CLOCK_INTERVAL {
+            match cpu.tick() {
+                Ok(Some(CpuEvent::Exited)) => {
+                    return (
+                        instructions,
+                        started.elapsed(),
+                        Some("program exited".into()),
+                    )
+                }
+                Ok(_) => instructions += 1,
+                Err(error) => return (instructions, started.elapsed(), Some(error.to_string())),
+            }
+        }
+        let elapsed = started.elapsed();
+        if elapsed >= duration {
+            return (instructions, elapsed, None);
+        }
+    }
+}
+
+fn profile(cpu: &mut Cpu, duration: Duration) -> Profiler {
+    let mut profiler = Profiler::default();
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        let address = cpu.pc;
+        let opcode = cpu.fetch_opcode();
+        let before = Instant::now();
+        let event = cpu.tick();
+        profiler.record(address, opcode, before.elapsed());
+        if matches!(event, Ok(Some(CpuEvent::Exited)) | Err(_)) {
+            break;
+        }
+    }
+    profiler
+}
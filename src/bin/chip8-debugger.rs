@@ -0,0 +1,77 @@
+//! Remote debugger for an emulator started with `--debug-server ADDR`.
+//!
+//! Reads commands from stdin, sends them to the emulator and prints the
+//! answers. Run `help` for the list of commands.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "chip8-debugger",
+    about = "Debug a running chip-8 emulator remotely."
+)]
+struct Opt {
+    /// Where the emulator's --debug-server listens, e.g. 127.0.0.1:7070
+    address: String,
+}
+
+const HELP: &str = "\
+regs                 registers, timers and stack
+pause | resume
+step [N]             execute N instructions (default 1) while paused
+break ADDR           toggle a breakpoint
+mem ADDR [LEN]       hex dump
+poke ADDR BYTE...    write memory
+dis [ADDR] [N]       disassemble, from PC by default
+display              the screen as block art
+quit
+
+All numbers are hex.
+";
+
+fn main() {
+    let opt = Opt::from_args();
+    let stream = match TcpStream::connect(&opt.address) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("cannot connect to {}: {}", opt.address, e);
+            std::process::exit(1);
+        }
+    };
+    let mut writer = stream.try_clone().unwrap();
+    let mut responses = BufReader::new(stream).lines();
+
+    let stdin = io::stdin();
+    let prompt = || {
+        print!("(chip8) ");
+        io::stdout().flush().unwrap();
+    };
+    prompt();
+    for command in stdin.lock().lines() {
+        let command = command.unwrap();
+        match command.trim() {
+            "quit" | "exit" => break,
+            "help" => print!("{}", HELP),
+            _ => {
+                if writeln!(writer, "{}", command).is_err() {
+                    eprintln!("connection closed");
+                    std::process::exit(1);
+                }
+                loop {
+                    match responses.next() {
+                        Some(Ok(line)) if line == "." => break,
+                        Some(Ok(line)) => println!("{}", line),
+                        _ => {
+                            eprintln!("connection closed");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+        prompt();
+    }
+}
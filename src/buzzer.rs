@@ -0,0 +1,174 @@
+//! The buzzer, sounded while the sound timer runs.
+//!
+//! There is no audio library in the dependency tree, so the waveform is
+//! generated here and streamed as raw 8-bit PCM into the platform's own
+//! command-line player. The CPU only exposes [`Cpu::is_beeping`] and the
+//! XO-CHIP audio pattern and pitch; everything audio-related stays in the
+//! frontend.
+//!
+//! [`Cpu::is_beeping`]: crate::cpu::Cpu::is_beeping
+
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::cpu::AUDIO_PATTERN_SIZE;
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// Samples written per chunk (10 ms).
+const CHUNK: usize = SAMPLE_RATE as usize / 100;
+
+/// How far ahead of real time the stream may run; more adds latency, less
+/// risks underruns.
+const LEAD: Duration = Duration::from_millis(40);
+
+const SILENCE: u8 = 0x80;
+const AMPLITUDE: u8 = 0x20;
+
+#[cfg(target_os = "macos")]
+const PLAYERS: &[&[&str]] = &[&[
+    "play", "-q", "-t", "raw", "-r", "8000", "-e", "unsigned", "-b", "8", "-c", "1", "-",
+]];
+#[cfg(not(target_os = "macos"))]
+const PLAYERS: &[&[&str]] = &[
+    &[
+        "pacat",
+        "--raw",
+        "--format=u8",
+        "--rate=8000",
+        "--channels=1",
+        "--latency-msec=40",
+    ],
+    &[
+        "aplay", "-q", "-t", "raw", "-f", "U8", "-r", "8000", "-c", "1", "-B", "40000",
+    ],
+    &[
+        "play", "-q", "-t", "raw", "-r", "8000", "-e", "unsigned", "-b", "8", "-c", "1", "-",
+    ],
+];
+
+/// What the buzzer plays while it sounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tone {
+    /// A square wave at the frequency the buzzer was started with.
+    Square,
+    /// XO-CHIP's loop of 1-bit samples, most significant bit first, played
+    /// at `rate` samples per second.
+    Pattern {
+        bits: [u8; AUDIO_PATTERN_SIZE],
+        rate: f32,
+    },
+}
+
+pub struct Buzzer {
+    on: Arc<AtomicBool>,
+    tone: Arc<Mutex<Tone>>,
+    running: Arc<AtomicBool>,
+}
+
+impl Buzzer {
+    /// Starts streaming silence in the background, ready to sound a tone of
+    /// `frequency` Hz. If no player is available the buzzer stays silent.
+    pub fn start(frequency: f32) -> Self {
+        let on = Arc::new(AtomicBool::new(false));
+        let tone = Arc::new(Mutex::new(Tone::Square));
+        let running = Arc::new(AtomicBool::new(true));
+        let (beep, wave, stop) = (Arc::clone(&on), Arc::clone(&tone), Arc::clone(&running));
+        thread::spawn(move || match spawn_player() {
+            Some(player) => stream(player, frequency, &beep, &wave, &stop),
+            None => {
+                let tried: Vec<&str> = PLAYERS.iter().map(|player| player[0]).collect();
+                warn!(
+                    "No sound: no audio player found (tried {})",
+                    tried.join(", ")
+                );
+            }
+        });
+        Self { on, tone, running }
+    }
+
+    pub fn set(&self, on: bool) {
+        self.on.store(on, Ordering::Relaxed);
+    }
+
+    pub fn set_tone(&self, tone: Tone) {
+        *self.tone.lock().unwrap() = tone;
+    }
+}
+
+impl Drop for Buzzer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn spawn_player() -> Option<Child> {
+    PLAYERS.iter().find_map(|player| {
+        let child = Command::new(player[0])
+            .args(&player[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        info!("Playing sound through {}", player[0]);
+        Some(child)
+    })
+}
+
+fn stream(
+    mut player: Child,
+    frequency: f32,
+    on: &AtomicBool,
+    tone: &Mutex<Tone>,
+    running: &AtomicBool,
+) {
+    let mut stdin = player.stdin.take().unwrap();
+    let half_period = SAMPLE_RATE as f64 / frequency as f64 / 2.0;
+    let started = Instant::now();
+    let mut written = 0u64;
+    // Position in the audio pattern, in samples; kept across chunks so the
+    // loop plays on seamlessly.
+    let mut position = 0f64;
+    let mut chunk = [SILENCE; CHUNK];
+    while running.load(Ordering::Relaxed) {
+        let ahead = Duration::from_secs_f64(written as f64 / SAMPLE_RATE as f64);
+        if ahead > started.elapsed() + LEAD {
+            thread::sleep(Duration::from_millis(5));
+            continue;
+        }
+
+        let beeping = on.load(Ordering::Relaxed);
+        let tone = *tone.lock().unwrap();
+        for (i, sample) in chunk.iter_mut().enumerate() {
+            let high = match tone {
+                Tone::Square => ((written + i as u64) as f64 / half_period) as u64 & 1 == 0,
+                Tone::Pattern { bits, rate } => {
+                    let bit = position as usize % (AUDIO_PATTERN_SIZE * 8);
+                    position = (position + rate as f64 / SAMPLE_RATE as f64)
+                        % (AUDIO_PATTERN_SIZE * 8) as f64;
+                    bits[bit / 8] & (0x80 >> (bit % 8)) != 0
+                }
+            };
+            *sample = match (beeping, high) {
+                (false, _) => SILENCE,
+                (true, true) => SILENCE + AMPLITUDE,
+                (true, false) => SILENCE - AMPLITUDE,
+            };
+        }
+        if let Err(e) = stdin.write_all(&chunk) {
+            warn!("No sound: audio player stopped: {}", e);
+            break;
+        }
+        written += CHUNK as u64;
+    }
+    drop(stdin);
+    let _ = player.kill();
+    let _ = player.wait();
+}
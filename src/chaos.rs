@@ -0,0 +1,88 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cpu::Cpu;
+use crate::rng::Rng;
+
+/// What chaos mode does to the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// Flip a random bit somewhere in memory.
+    BitFlip,
+    /// Skip over the next instruction.
+    Skip,
+}
+
+/// A corruption and how often it strikes, given as `flip:0.001` or
+/// `skip:0.01` (chance per executed instruction).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChaosSpec {
+    corruption: Corruption,
+    rate: f64,
+}
+
+impl FromStr for ChaosSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rate) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected kind:rate, got {}", s))?;
+        let corruption = match kind {
+            "flip" => Corruption::BitFlip,
+            "skip" => Corruption::Skip,
+            _ => return Err(format!("unknown corruption: {}", kind)),
+        };
+        let rate: f64 = rate
+            .parse()
+            .map_err(|_| format!("invalid rate: {}", rate))?;
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("rate must be between 0 and 1, got {}", rate));
+        }
+        Ok(Self { corruption, rate })
+    }
+}
+
+/// Injects random corruption between instructions, reproducibly from a seed.
+pub struct Chaos {
+    spec: ChaosSpec,
+    rng: Rng,
+    injected: u64,
+}
+
+impl Chaos {
+    pub fn new(spec: ChaosSpec, seed: u64) -> Self {
+        Self {
+            spec,
+            rng: Rng::new(seed),
+            injected: 0,
+        }
+    }
+
+    /// Call before every instruction.
+    pub fn strike(&mut self, cpu: &mut Cpu) {
+        if !self.rng.chance(self.spec.rate) {
+            return;
+        }
+        self.injected += 1;
+        match self.spec.corruption {
+            Corruption::BitFlip => {
+                let address = self.rng.below(cpu.memory().len() as u64) as u16;
+                let bit = self.rng.below(8);
+                let byte = cpu.memory()[address as usize] ^ (1 << bit);
+                cpu.write_memory(address, byte);
+            }
+            Corruption::Skip => cpu.pc += 2,
+        }
+    }
+}
+
+impl fmt::Display for Chaos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.spec.corruption {
+            Corruption::BitFlip => "bit flips",
+            Corruption::Skip => "skipped instructions",
+        };
+        write!(f, "{} {}", self.injected, kind)
+    }
+}
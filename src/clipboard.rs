@@ -0,0 +1,61 @@
+//! Loading ROMs pasted from the system clipboard.
+//!
+//! There is no clipboard access in the windowing stack, so the text is read
+//! with the platform's own command-line tool.
+
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+const READERS: &[&[&str]] = &[&["pbpaste"]];
+#[cfg(windows)]
+const READERS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const READERS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// Reads the clipboard as text.
+pub fn read() -> Result<String, String> {
+    for reader in READERS {
+        if let Ok(output) = Command::new(reader[0]).args(&reader[1..]).output() {
+            if output.status.success() {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+        }
+    }
+    let tried: Vec<&str> = READERS.iter().map(|reader| reader[0]).collect();
+    Err(format!(
+        "cannot read the clipboard (tried {})",
+        tried.join(", ")
+    ))
+}
+
+/// Parses hex bytes as posted on forums: `00E0 A22A`, `0x00, 0xE0` and
+/// `00 e0` all work. Comments start with `#`, `;` or `//`.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut rom = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or_default();
+        let line = line.split(['#', ';']).next().unwrap_or_default();
+        for token in line.split(|c: char| c.is_whitespace() || c == ',') {
+            let digits = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .or_else(|| token.strip_prefix('$'))
+                .unwrap_or(token);
+            if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("line {}: not hex bytes: {}", number + 1, token));
+            }
+            for pair in digits.as_bytes().chunks(2) {
+                let pair = std::str::from_utf8(pair).unwrap();
+                rom.push(u8::from_str_radix(pair, 16).unwrap());
+            }
+        }
+    }
+    if rom.is_empty() {
+        return Err("no hex bytes found".to_string());
+    }
+    Ok(rom)
+}
@@ -0,0 +1,48 @@
+use crate::cpu::Cpu;
+
+/// A second core run in lockstep with the main one, used to find where two
+/// quirk profiles make a ROM behave differently.
+#[derive(Clone)]
+pub struct Comparison {
+    pub cpu: Cpu,
+    instructions: u64,
+    diverged_at: Option<(u64, u64)>,
+}
+
+impl Comparison {
+    pub fn new(cpu: Cpu) -> Self {
+        Self {
+            cpu,
+            instructions: 0,
+            diverged_at: None,
+        }
+    }
+
+    /// Advances the second core by one instruction and records whether its
+    /// display now differs from `reference`. Both cores see the same keys.
+    pub fn step(&mut self, reference: &Cpu) {
+        self.cpu.keys = reference.keys;
+        // A second core that faults just stops; the divergence shows it.
+        let _ = self.cpu.tick();
+        self.instructions += 1;
+        if self.diverged_at.is_none() && self.cpu.get_display() != reference.get_display() {
+            self.diverged_at = Some((self.frame(), self.instructions));
+        }
+    }
+
+    /// Instructions run in lockstep so far.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// The 60 Hz frame the second core is in.
+    pub fn frame(&self) -> u64 {
+        self.cpu.frame()
+    }
+
+    /// The frame the two displays first differed in, and the instruction,
+    /// counting from 1, that made them differ.
+    pub fn diverged_at(&self) -> Option<(u64, u64)> {
+        self.diverged_at
+    }
+}
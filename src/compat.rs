@@ -0,0 +1,301 @@
+//! Runs every ROM in a directory headlessly and summarizes how each one
+//! fares as a Markdown compatibility report.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::asm;
+use crate::cpu::{Cpu, CpuError, CpuEvent, MemoryLayout};
+use crate::metadata::RomMetadata;
+use crate::quirks::{Platform, Quirks};
+use crate::romconfig::RomConfig;
+use crate::romdb;
+use crate::trace;
+
+/// Files that are run as ROMs; assembly sources are assembled first.
+const EXTENSIONS: [&str; 4] = ["ch8", "c8", "8o", "asm"];
+
+enum Status {
+    /// The program jumped to itself.
+    Halted {
+        frame: u64,
+    },
+    /// The program quit with 00FD.
+    Exited {
+        frame: u64,
+    },
+    /// Still running after the last frame.
+    Running,
+    /// The program ran into an opcode its platform doesn't have.
+    UnknownOpcode {
+        frame: u64,
+        pc: u16,
+        opcode: u16,
+    },
+    Crashed {
+        frame: u64,
+        reason: String,
+    },
+    /// The file could not be read or assembled.
+    Unloadable(String),
+}
+
+struct Entry {
+    name: String,
+    /// The quirks the ROM ran with.
+    quirks: Quirks,
+    platform: Option<String>,
+    status: Status,
+    /// How many 60 Hz frames changed the display.
+    display_changes: u64,
+    lit_pixels: usize,
+}
+
+pub struct Report {
+    frames: u64,
+    entries: Vec<Entry>,
+}
+
+/// Runs every ROM in `dir` for up to `frames` 60 Hz frames.
+pub fn scan(dir: &Path, frames: u64) -> io::Result<Report> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext))
+        })
+        .collect();
+    paths.sort();
+
+    // Crashes are recorded in the report instead of printed.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let entries = paths.iter().map(|path| check(path, frames)).collect();
+    panic::set_hook(hook);
+
+    Ok(Report { frames, entries })
+}
+
+/// Runs one ROM under the platform the windowed frontend would pick for it:
+/// the one in its config, or else the one the ROM database knows it by.
+fn check(path: &Path, frames: u64) -> Entry {
+    let metadata = RomMetadata::load_sidecar(path);
+    let config = RomConfig::load_sidecar(path).unwrap_or_default();
+    let rom = load(path);
+    let platform: Option<Platform> = config.platform.or_else(|| {
+        let rom = rom.as_deref().ok()?;
+        romdb::lookup(rom).map(|entry| entry.platform)
+    });
+    let quirks = config
+        .quirks
+        .or_else(|| config.platform.map(|platform| platform.quirks()))
+        .or_else(|| {
+            metadata
+                .as_ref()
+                .and_then(|metadata| metadata.options)
+                .map(|options| options.quirks())
+        })
+        .or_else(|| platform.map(|platform| platform.quirks()))
+        .unwrap_or_default();
+    let mut entry = Entry {
+        name: path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+        quirks,
+        platform: platform
+            .map(|platform| platform.name().to_string())
+            .or_else(|| metadata.and_then(|metadata| metadata.platform)),
+        status: Status::Running,
+        display_changes: 0,
+        lit_pixels: 0,
+    };
+
+    let rom = match rom {
+        Ok(rom) => rom,
+        Err(e) => {
+            entry.status = Status::Unloadable(e);
+            return entry;
+        }
+    };
+    let layout = platform.map_or_else(MemoryLayout::default, |platform| platform.memory_layout());
+    let mut cpu = Cpu::with_layout(layout);
+    cpu.quirks = quirks;
+    cpu.platform = platform;
+    cpu.load_rom(&rom);
+
+    let mut frame = 0;
+    let mut previous = cpu.get_display().hash();
+    let finished = panic::catch_unwind(AssertUnwindSafe(|| -> Result<_, CpuError> {
+        while frame < frames {
+            let event = cpu.tick()?;
+            if cpu.frame() != frame {
+                frame = cpu.frame();
+                let hash = cpu.get_display().hash();
+                if hash != previous {
+                    entry.display_changes += 1;
+                    previous = hash;
+                }
+            }
+            // Saving the RPL flags doesn't end the program.
+            if let Some(event @ (CpuEvent::Halted | CpuEvent::Exited)) = event {
+                return Ok(Some((frame, event)));
+            }
+        }
+        Ok(None)
+    }));
+    entry.status = match finished {
+        Ok(Ok(Some((frame, CpuEvent::Exited)))) => Status::Exited { frame },
+        Ok(Ok(Some((frame, _)))) => Status::Halted { frame },
+        Ok(Ok(None)) => Status::Running,
+        Ok(Err(CpuError::UnknownOpcode { pc, opcode })) => {
+            Status::UnknownOpcode { frame, pc, opcode }
+        }
+        Ok(Err(error)) => Status::Crashed {
+            frame,
+            reason: error.to_string(),
+        },
+        Err(payload) => Status::Crashed {
+            frame,
+            reason: trace::panic_message(payload),
+        },
+    };
+    entry.lit_pixels = cpu.get_display().lit_pixels();
+    entry
+}
+
+fn load(path: &Path) -> Result<Vec<u8>, String> {
+    let is_source = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("8o" | "asm")
+    );
+    if is_source {
+        let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        asm::assemble_program(&source).map(|program| program.rom)
+    } else {
+        fs::read(path).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |matches: fn(&Status) -> bool| {
+            self.entries
+                .iter()
+                .filter(|entry| matches(&entry.status))
+                .count()
+        };
+        writeln!(f, "# Compatibility report")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{} ROMs, {} frames each: {} halted, {} still running, {} hit an unknown opcode, {} crashed, {} unloadable.",
+            self.entries.len(),
+            self.frames,
+            count(|status| matches!(status, Status::Halted { .. } | Status::Exited { .. })),
+            count(|status| matches!(status, Status::Running)),
+            count(|status| matches!(status, Status::UnknownOpcode { .. })),
+            count(|status| matches!(status, Status::Crashed { .. })),
+            count(|status| matches!(status, Status::Unloadable(_))),
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "| ROM | Platform | Quirks | Result | Display changes | Lit pixels |"
+        )?;
+        writeln!(f, "|---|---|---|---|---:|---:|")?;
+        for entry in &self.entries {
+            let result = match &entry.status {
+                Status::Halted { frame } => format!("halted at frame {}", frame),
+                Status::Exited { frame } => format!("exited at frame {}", frame),
+                Status::Running => "running".to_string(),
+                Status::UnknownOpcode { frame, pc, opcode } => format!(
+                    "unknown opcode {:#06x} at {:#05x} in frame {}",
+                    opcode, pc, frame
+                ),
+                Status::Crashed { frame, reason } => {
+                    format!("crashed at frame {}: {}", frame, reason)
+                }
+                Status::Unloadable(e) => format!("not loaded: {}", e),
+            };
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} | {} |",
+                escape(&entry.name),
+                escape(entry.platform.as_deref().unwrap_or("-")),
+                entry.quirks,
+                escape(&result),
+                entry.display_changes,
+                entry.lit_pixels
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a cell from breaking the table.
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    /// Writes `rom` to a fresh directory as `name`, with `config` as its
+    /// TOML sidecar if given, and checks it for a second's worth of frames.
+    fn check_rom(name: &str, rom: &[u8], config: Option<&str>) -> Entry {
+        let dir = env::temp_dir().join(format!("chip8-compat-{}-{}", process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, rom).unwrap();
+        if let Some(config) = config {
+            fs::write(path.with_extension("toml"), config).unwrap();
+        }
+        let entry = check(&path, 60);
+        fs::remove_dir_all(&dir).unwrap();
+        entry
+    }
+
+    #[test]
+    fn eti_660_roms_load_at_0x600() {
+        // Jump to itself at 0x600.
+        let entry = check_rom("eti.ch8", &[0x16, 0x00], Some("platform = \"eti660\"\n"));
+        assert_eq!(entry.platform.as_deref(), Some("eti660"));
+        assert!(matches!(entry.status, Status::Halted { frame: 0 }));
+    }
+
+    #[test]
+    fn xo_chip_roms_get_64k_of_memory() {
+        // I = 0xFF00, load V0 from there, then halt.
+        let rom = [0xF0, 0x00, 0xFF, 0x00, 0xF0, 0x65, 0x12, 0x06];
+        let entry = check_rom("xo.ch8", &rom, Some("platform = \"xochip\"\n"));
+        assert!(matches!(entry.status, Status::Halted { frame: 0 }));
+    }
+
+    #[test]
+    fn saving_rpl_flags_keeps_running() {
+        // Save V0 to the RPL flags, then count in V0 forever.
+        let rom = [0xF0, 0x75, 0x70, 0x01, 0x12, 0x02];
+        let entry = check_rom("rpl.ch8", &rom, Some("platform = \"schip\"\n"));
+        assert!(matches!(entry.status, Status::Running));
+    }
+
+    #[test]
+    fn unknown_opcodes_are_told_apart_from_crashes() {
+        let entry = check_rom("unknown.ch8", &[0xFF, 0xFF], None);
+        assert!(matches!(
+            entry.status,
+            Status::UnknownOpcode {
+                pc: 0x200,
+                opcode: 0xFFFF,
+                ..
+            }
+        ));
+    }
+}
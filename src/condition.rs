@@ -0,0 +1,162 @@
+//! Conditions on machine state that pause execution when they come true,
+//! written like `V3 == 0x1F` or `I >= 0x300`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cpu::Cpu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(u8),
+    Index,
+    Pc,
+    DelayTimer,
+    SoundTimer,
+}
+
+impl Operand {
+    fn value(&self, cpu: &Cpu) -> u16 {
+        match self {
+            Operand::Register(x) => cpu.registers[*x as usize] as u16,
+            Operand::Index => cpu.index,
+            Operand::Pc => cpu.pc,
+            Operand::DelayTimer => cpu.delay_timer as u16,
+            Operand::SoundTimer => cpu.sound_timer as u16,
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(x) => write!(f, "V{:X}", x),
+            Operand::Index => write!(f, "I"),
+            Operand::Pc => write!(f, "PC"),
+            Operand::DelayTimer => write!(f, "DT"),
+            Operand::SoundTimer => write!(f, "ST"),
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "I" => Ok(Operand::Index),
+            "PC" => Ok(Operand::Pc),
+            "DT" => Ok(Operand::DelayTimer),
+            "ST" => Ok(Operand::SoundTimer),
+            name => name
+                .strip_prefix('V')
+                .filter(|x| x.len() == 1)
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+                .map(Operand::Register)
+                .ok_or_else(|| format!("expected V0-VF, I, PC, DT or ST, got {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    /// Longer symbols first, so `<=` isn't taken for `<`.
+    const SYMBOLS: [(&'static str, Operator); 6] = [
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+
+    fn symbol(&self) -> &'static str {
+        Self::SYMBOLS
+            .iter()
+            .find(|(_, op)| op == self)
+            .map_or("?", |(symbol, _)| symbol)
+    }
+
+    fn compare(&self, left: u16, right: u16) -> bool {
+        match self {
+            Operator::Equal => left == right,
+            Operator::NotEqual => left != right,
+            Operator::Less => left < right,
+            Operator::LessOrEqual => left <= right,
+            Operator::Greater => left > right,
+            Operator::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+/// A comparison of a register, I, PC or a timer with a constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    operand: Operand,
+    operator: Operator,
+    value: u16,
+    /// Whether it held when last checked, so it fires once each time it
+    /// comes true rather than after every instruction while it stays true.
+    held: bool,
+}
+
+impl Condition {
+    pub fn holds(&self, cpu: &Cpu) -> bool {
+        self.operator.compare(self.operand.value(cpu), self.value)
+    }
+
+    /// Checks the condition against `cpu`, telling whether it just came
+    /// true.
+    pub fn fired(&mut self, cpu: &Cpu) -> bool {
+        let held = self.holds(cpu);
+        let fired = held && !self.held;
+        self.held = held;
+        fired
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {:#X}",
+            self.operand,
+            self.operator.symbol(),
+            self.value
+        )
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (at, symbol, operator) = Operator::SYMBOLS
+            .iter()
+            .filter_map(|(symbol, op)| s.find(symbol).map(|at| (at, *symbol, *op)))
+            .min_by_key(|(at, symbol, _)| (*at, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| format!("expected a comparison like V3 == 0x1F, got {}", s))?;
+        let operand = s[..at].trim().parse()?;
+        let value = s[at + symbol.len()..].trim();
+        let value = match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+            Some(hex) => u16::from_str_radix(hex, 16),
+            None => value.parse(),
+        }
+        .map_err(|_| format!("not a number: {}", value))?;
+        Ok(Condition {
+            operand,
+            operator,
+            value,
+            held: false,
+        })
+    }
+}
@@ -1,174 +1,955 @@
-use crate::display::Display;
-
-const PC_START: usize = 0x200;
-
-const FONT: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use log::warn;
+
+use crate::display::{Display, MegaScreen, Resolution};
+use crate::font::{BigFont, Font, FontSet, BIG_FONT_SIZE, FONT_SIZE};
+use crate::heatmap::{Access, Heatmap};
+use crate::instruction::{self, Instruction};
+use crate::quirks::{Platform, Quirks};
+use crate::rng::Rng;
+use crate::scheduler::FrameClock;
+
+pub const PC_START: usize = 0x200;
+
+/// Identifies a serialized machine state, followed by a format version.
+const STATE_MAGIC: &[u8; 4] = b"CH8S";
+const STATE_VERSION: u8 = 9;
+
+/// Instructions executed per second unless the frontend says otherwise.
+pub const DEFAULT_IPS: f32 = 700.0;
+
+/// How often the delay and sound timers count down, in emulated time.
+pub const TIMER_HZ: f32 = 60.0;
+
+/// RPL user flags SUPER-CHIP can save V0..V7 to with FX75.
+pub const RPL_FLAGS: usize = 8;
+
+/// Bytes in XO-CHIP's audio pattern buffer: 128 one-bit samples.
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+
+/// The FX3A pitch XO-CHIP starts with, which plays the pattern at 4000
+/// samples per second.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Nested calls the original interpreters had room for.
+pub const DEFAULT_STACK_DEPTH: usize = 16;
+
+/// How the address space is split between the interpreter and the program.
+///
+/// Everything below `program_start` is reserved for the interpreter (font
+/// data lives there); ROMs are loaded from `program_start` up to `size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLayout {
+    pub size: usize,
+    pub program_start: usize,
+}
+
+impl MemoryLayout {
+    pub const MAX_SIZE: usize = 0x10000;
+
+    pub fn program_capacity(&self) -> usize {
+        self.size - self.program_start
+    }
+}
+
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        Self {
+            size: 4096,
+            program_start: PC_START,
+        }
+    }
+}
+
+/// What to do when the program runs into an opcode this core can't run:
+/// one that isn't an instruction, or a 0NNN machine-code call.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OpcodePolicy {
+    /// Stop and report a [`CpuError`], leaving PC on the opcode.
+    #[default]
+    Halt,
+    /// Treat it as a two-byte no-op.
+    Skip,
+    /// Like `Skip`, but log a warning each time.
+    Log,
+}
+
+impl OpcodePolicy {
+    pub const NAMES: &'static [&'static str] = &["halt", "skip", "log"];
+}
+
+impl FromStr for OpcodePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "halt" => Ok(OpcodePolicy::Halt),
+            "skip" => Ok(OpcodePolicy::Skip),
+            "log" => Ok(OpcodePolicy::Log),
+            _ => Err(format!("unknown opcode policy: {}", s)),
+        }
+    }
+}
+
+/// Something the program did that the frontend may want to react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuEvent {
+    /// A 1NNN jumped to itself: the program is finished and will spin there
+    /// forever.
+    Halted,
+    /// The program asked the interpreter to quit with 00FD. PC stays on the
+    /// 00FD.
+    Exited,
+    /// FX75 wrote the RPL user flags, which should now be persisted.
+    FlagsSaved,
+}
+
+/// Why an instruction could not be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpuError {
+    UnknownOpcode {
+        pc: u16,
+        opcode: u16,
+    },
+    /// A 0NNN call into COSMAC VIP machine code.
+    MachineCodeCall {
+        pc: u16,
+        nnn: u16,
+    },
+    /// A call with every stack slot in use.
+    StackOverflow {
+        pc: u16,
+        depth: usize,
+    },
+    /// A return with nothing on the stack.
+    StackUnderflow {
+        pc: u16,
+    },
+    /// An instruction touched, or PC reached, an address past the end of
+    /// memory.
+    MemoryOutOfRange {
+        pc: u16,
+        address: usize,
+    },
+}
+
+impl CpuError {
+    /// Address of the instruction that failed.
+    pub fn pc(&self) -> u16 {
+        match self {
+            CpuError::UnknownOpcode { pc, .. }
+            | CpuError::MachineCodeCall { pc, .. }
+            | CpuError::StackOverflow { pc, .. }
+            | CpuError::StackUnderflow { pc }
+            | CpuError::MemoryOutOfRange { pc, .. } => *pc,
+        }
+    }
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpuError::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode {:#06x} at {:#05x}", opcode, pc)
+            }
+            CpuError::MachineCodeCall { pc, nnn } => {
+                write!(f, "machine code call to {:#05x} at {:#05x}", nnn, pc)
+            }
+            CpuError::StackOverflow { pc, depth } => write!(
+                f,
+                "stack overflow at {:#05x}: more than {} nested calls",
+                pc, depth
+            ),
+            CpuError::StackUnderflow { pc } => {
+                write!(f, "stack underflow at {:#05x}: return without a call", pc)
+            }
+            CpuError::MemoryOutOfRange { pc, address } => write!(
+                f,
+                "memory access out of range at {:#05x}: {:#x}",
+                pc, address
+            ),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Cpu {
-    memory: [u8; 4096],
+    memory: Vec<u8>,
+    layout: MemoryLayout,
     display: Display,
+    /// The display planes drawing, clearing and scrolling act on, bit N for
+    /// plane N. Only XO-CHIP's FX01 selects anything but the first.
+    pub planes: u8,
     pub pc: u16,
     pub index: u16,
     pub registers: [u8; 16],
-    pub stack: [u16; 16],
+    pub stack: Vec<u16>,
     pub stack_pointer: usize,
+    /// The deepest the stack has been since the ROM started.
+    pub max_stack_depth: usize,
     pub delay_timer: u8,
+    /// Counts down like the delay timer; the buzzer sounds while it's non-zero.
+    pub sound_timer: u8,
+    /// The 1-bit samples XO-CHIP's F002 loaded, played in a loop while the
+    /// buzzer sounds. Until a program loads one the buzzer plays its tone.
+    pub audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    /// Sets the rate the audio pattern plays at, see [`Cpu::playback_rate`].
+    pub pitch: u8,
+    /// Keys held on the hex keypad, bit N for key N.
+    pub keys: u16,
+    /// The key FX0A saw go down and is waiting to be released.
+    key_wait: Option<u8>,
+    pub font_address: u16,
+    /// SUPER-CHIP's RPL user flags. They live outside the machine proper,
+    /// so [`Cpu::reset`] leaves them alone.
+    pub rpl_flags: [u8; RPL_FLAGS],
+    /// The installed fonts, so [`Cpu::reset`] can put them back.
+    font: Font,
+    big_font: BigFont,
+    pub quirks: Quirks,
+    /// The platform whose extensions to recognise: MegaChip's opcodes and
+    /// the hi-res CHIP-8 entry point. Like the quirks, [`Cpu::reset`] keeps
+    /// it.
+    pub platform: Option<Platform>,
+    /// Width and height of MegaChip color sprites as 03NN and 04NN set
+    /// them, where 0 stands for 256.
+    pub sprite_size: (u8, u8),
+    /// The palette index MegaChip sprites collide with.
+    pub collision_color: u8,
+    pub on_unknown_opcode: OpcodePolicy,
+    pub on_machine_code: OpcodePolicy,
+    /// When the timers count down, see [`Cpu::set_instructions_per_second`].
+    clock: FrameClock,
+    /// The 60 Hz frame running, counted from 0 at boot or reset.
+    frame: u64,
+    /// Source for CXNN; seeded with 0 unless [`Cpu::seed_rng`] says otherwise.
+    rng: Rng,
+    /// What [`Cpu::reset`] seeds the generator with again.
+    seed: u64,
+    /// How often each byte has been executed, read and written. Shared
+    /// with copies of the CPU until either side records an access.
+    pub heatmap: Arc<Heatmap>,
+    /// The bytes the last instruction read and wrote, in order.
+    accesses: Vec<(Access, usize)>,
 }
 
 impl Cpu {
     pub fn new() -> Self {
-        let mut memory = [0; 4096];
-        for (index, pixel) in FONT.iter().enumerate() {
-            memory[index] = *pixel;
-        }
+        Self::with_layout(MemoryLayout::default())
+    }
 
-        Self {
-            memory,
+    pub fn with_layout(layout: MemoryLayout) -> Self {
+        let mut cpu = Self {
+            memory: vec![0; layout.size],
+            layout,
             display: Display::new(),
-            pc: PC_START as u16,
+            planes: 1,
+            pc: layout.program_start as u16,
             index: 0,
             registers: [0; 16],
-            stack: [0; 16],
+            stack: vec![0; DEFAULT_STACK_DEPTH],
             stack_pointer: 0,
+            max_stack_depth: 0,
             delay_timer: 0,
+            sound_timer: 0,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            keys: 0,
+            key_wait: None,
+            font_address: 0,
+            rpl_flags: [0; RPL_FLAGS],
+            font: *FontSet::default().data(),
+            big_font: *FontSet::default().big_data(),
+            quirks: Quirks::default(),
+            platform: None,
+            sprite_size: (0, 0),
+            collision_color: 0,
+            on_unknown_opcode: OpcodePolicy::default(),
+            on_machine_code: OpcodePolicy::default(),
+            clock: FrameClock::default(),
+            frame: 0,
+            rng: Rng::new(0),
+            seed: 0,
+            heatmap: Arc::new(Heatmap::new(layout.size)),
+            accesses: Vec::new(),
+        };
+        cpu.load_font(FontSet::default().data(), FontSet::default().big_data(), 0);
+        cpu
+    }
+
+    /// Sets how many instructions make up a second of emulated time, so
+    /// the timers count down at 60Hz no matter how fast the CPU runs.
+    pub fn set_instructions_per_second(&mut self, ips: f32) {
+        self.clock.set_instructions_per_second(ips);
+    }
+
+    pub fn instructions_per_second(&self) -> f32 {
+        self.clock.instructions_per_second()
+    }
+
+    /// How many instructions run until the timers next count down, counting
+    /// the instruction they count down on.
+    pub fn instructions_to_next_frame(&self) -> u32 {
+        self.clock.instructions_to_next_frame()
+    }
+
+    /// The 60 Hz frame running: how many times the timers have had a chance
+    /// to count down since boot or reset.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Seeds the generator behind CXNN, so runs with the same seed draw the
+    /// same numbers.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+        self.seed = seed;
+    }
+
+    /// Makes room for `depth` nested calls.
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.stack.resize(depth, 0);
+    }
+
+    /// Installs `font` at `address`, with `big_font` right after it,
+    /// clearing the previously installed fonts.
+    pub fn load_font(&mut self, font: &Font, big_font: &BigFont, address: u16) {
+        let old = self.font_address as usize;
+        self.memory[old..old + FONT_SIZE + BIG_FONT_SIZE].fill(0);
+        let new = address as usize;
+        self.memory[new..new + FONT_SIZE].copy_from_slice(font);
+        self.memory[new + FONT_SIZE..new + FONT_SIZE + BIG_FONT_SIZE].copy_from_slice(big_font);
+        self.font_address = address;
+        self.font = *font;
+        self.big_font = *big_font;
+    }
+
+    /// Puts the machine back the way it was before a ROM was loaded: memory
+    /// holds only the font, and registers, timers, stack and display are
+    /// cleared. Quirks, the memory layout and the stack depth are kept. The
+    /// random numbers start over from the seed and the timers from the
+    /// start of a frame, so a reset runs like a fresh boot.
+    pub fn reset(&mut self) {
+        self.memory.fill(0);
+        let (font, big_font) = (self.font, self.big_font);
+        self.load_font(&font, &big_font, self.font_address);
+        self.display = Display::new();
+        self.planes = 1;
+        self.pc = self.layout.program_start as u16;
+        self.index = 0;
+        self.registers = [0; 16];
+        self.stack.fill(0);
+        self.stack_pointer = 0;
+        self.max_stack_depth = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.audio_pattern = None;
+        self.pitch = DEFAULT_PITCH;
+        self.key_wait = None;
+        self.sprite_size = (0, 0);
+        self.collision_color = 0;
+        self.clock.restart();
+        self.frame = 0;
+        self.rng = Rng::new(self.seed);
+        self.clear_heatmap();
+    }
+
+    /// Copies `rom` into memory at the start of the program area, e.g. after
+    /// [`Cpu::reset`].
+    pub fn load_rom(&mut self, rom: &[u8]) {
+        let capacity = self.layout.program_capacity();
+        if rom.len() > capacity {
+            warn!(
+                "ROM is {} bytes but only {} fit in memory, truncating",
+                rom.len(),
+                capacity
+            );
         }
+        let len = rom.len().min(capacity);
+        let start = self.layout.program_start;
+        self.memory[start..start + len].copy_from_slice(&rom[..len]);
     }
 
-    pub fn load(&mut self, rom: &[u8]) {
-        for (i, &byte) in rom.iter().enumerate() {
-            let addr = 0x200 + i;
-            if addr < 4096 {
-                self.memory[addr] = byte;
-            } else {
-                break;
-            }
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// The bytes the last instruction read and wrote, in order. Fetching
+    /// the instruction itself doesn't count.
+    pub fn accesses(&self) -> &[(Access, usize)] {
+        &self.accesses
+    }
+
+    /// Overwrites a byte of memory, e.g. from the debugger.
+    pub fn write_memory(&mut self, address: u16, byte: u8) {
+        self.memory[address as usize] = byte;
+    }
+
+    pub fn clear_heatmap(&mut self) {
+        Arc::make_mut(&mut self.heatmap).clear();
+    }
+
+    /// Serializes everything needed to resume execution later: registers,
+    /// timers, audio, stack, quirks, display, memory and RPL flags. The
+    /// heatmap is left out.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = STATE_MAGIC.to_vec();
+        state.push(STATE_VERSION);
+        state.extend((self.layout.size as u32).to_le_bytes());
+        state.extend((self.layout.program_start as u16).to_le_bytes());
+        state.extend(self.pc.to_le_bytes());
+        state.extend(self.index.to_le_bytes());
+        state.extend(self.registers);
+        state.extend((self.stack.len() as u16).to_le_bytes());
+        for address in &self.stack {
+            state.extend(address.to_le_bytes());
+        }
+        state.extend((self.stack_pointer as u16).to_le_bytes());
+        state.extend((self.max_stack_depth as u16).to_le_bytes());
+        state.push(self.delay_timer);
+        state.push(self.sound_timer);
+        state.push(self.audio_pattern.is_some() as u8);
+        state.extend(self.audio_pattern.unwrap_or_default());
+        state.push(self.pitch);
+        state.extend(self.font_address.to_le_bytes());
+        state.push(self.quirks.bits());
+        state.push(self.platform.map_or(0, |platform| platform as u8 + 1));
+        state.push(self.sprite_size.0);
+        state.push(self.sprite_size.1);
+        state.push(self.collision_color);
+        state.push(self.display.resolution() as u8);
+        state.push(self.planes);
+        state.extend(self.display.packed_planes());
+        if let Some(mega) = &self.display.mega {
+            state.extend(mega.to_bytes());
+        }
+        state.extend(&self.memory);
+        state.extend(self.rng.position().to_le_bytes());
+        state.extend(self.rpl_flags);
+        state
+    }
+
+    /// Restores a machine from [`Cpu::save_state`].
+    pub fn load_state(state: &[u8]) -> Result<Self, String> {
+        let mut reader = StateReader(state);
+        if reader.take(4)? != STATE_MAGIC {
+            return Err("not a machine state".to_string());
+        }
+        let version = reader.u8()?;
+        if version != STATE_VERSION {
+            return Err(format!("unsupported state version {}", version));
+        }
+        let layout = MemoryLayout {
+            size: reader.u32()? as usize,
+            program_start: reader.u16()? as usize,
+        };
+        // The fonts load below the program, at 0 until the saved address
+        // is read, so a layout without room for them is refused up front.
+        let fonts = FONT_SIZE + BIG_FONT_SIZE;
+        if layout.size > MemoryLayout::MAX_SIZE
+            || layout.program_start > layout.size
+            || layout.program_start < fonts
+        {
+            return Err("invalid memory layout".to_string());
         }
+        let mut cpu = Self::with_layout(layout);
+        cpu.pc = reader.u16()?;
+        cpu.index = reader.u16()?;
+        cpu.registers.copy_from_slice(reader.take(16)?);
+        let depth = reader.u16()? as usize;
+        cpu.stack = (0..depth).map(|_| reader.u16()).collect::<Result<_, _>>()?;
+        cpu.stack_pointer = reader.u16()? as usize;
+        cpu.max_stack_depth = reader.u16()? as usize;
+        cpu.delay_timer = reader.u8()?;
+        cpu.sound_timer = reader.u8()?;
+        let has_pattern = reader.u8()? != 0;
+        let mut pattern = [0; AUDIO_PATTERN_SIZE];
+        pattern.copy_from_slice(reader.take(AUDIO_PATTERN_SIZE)?);
+        cpu.audio_pattern = has_pattern.then_some(pattern);
+        cpu.pitch = reader.u8()?;
+        cpu.font_address = reader.u16()?;
+        if cpu.font_address as usize + fonts > layout.program_start {
+            return Err("font address out of range".to_string());
+        }
+        cpu.quirks = Quirks::from_bits(reader.u8()?);
+        cpu.platform = match reader.u8()? {
+            0 => None,
+            n => Some(
+                *Platform::ALL
+                    .get(n as usize - 1)
+                    .ok_or("invalid platform")?,
+            ),
+        };
+        cpu.sprite_size = (reader.u8()?, reader.u8()?);
+        cpu.collision_color = reader.u8()?;
+        let resolution = match reader.u8()? {
+            0 => Resolution::Low,
+            1 => Resolution::Tall,
+            2 => Resolution::High,
+            3 => Resolution::Mega,
+            _ => return Err("invalid resolution".to_string()),
+        };
+        cpu.planes = reader.u8()?;
+        cpu.display = Display::from_packed_planes(
+            resolution,
+            reader.take(Display::packed_planes_len(resolution))?,
+        );
+        if let Some(mega) = &mut cpu.display.mega {
+            **mega = MegaScreen::from_bytes(reader.take(MegaScreen::BYTES)?);
+        }
+        cpu.memory.copy_from_slice(reader.take(layout.size)?);
+        let font = cpu.font_address as usize;
+        cpu.font
+            .copy_from_slice(&cpu.memory[font..font + FONT_SIZE]);
+        cpu.big_font
+            .copy_from_slice(&cpu.memory[font + FONT_SIZE..font + FONT_SIZE + BIG_FONT_SIZE]);
+        // Resetting a loaded state draws from where it was saved.
+        cpu.seed_rng(reader.u64()?);
+        cpu.rpl_flags.copy_from_slice(reader.take(RPL_FLAGS)?);
+        if cpu.stack_pointer > cpu.stack.len() {
+            return Err("stack pointer out of range".to_string());
+        }
+        Ok(cpu)
     }
 
     pub fn get_display(&self) -> &Display {
         &self.display
     }
 
-    pub fn tick(&mut self) {
-        if self.delay_timer > 0 {
-            self.delay_timer -= 1
-        }
+    pub fn is_megachip(&self) -> bool {
+        self.platform == Some(Platform::MegaChip)
+    }
+
+    /// Whether the buzzer should be sounding right now.
+    pub fn is_beeping(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// Samples per second the audio pattern plays at: 4000 at the default
+    /// pitch, doubling every 48 steps above it.
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - DEFAULT_PITCH as f32) / 48.0)
+    }
+
+    /// Executes one instruction. On an error the machine is left as it was
+    /// before the instruction, apart from the timers.
+    pub fn tick(&mut self) -> Result<Option<CpuEvent>, CpuError> {
+        self.accesses.clear();
+        let frames = self.clock.step();
+        let new_frame = frames > 0;
+        self.frame += frames as u64;
+        let frames = frames.min(u8::MAX as u32) as u8;
+        self.delay_timer = self.delay_timer.saturating_sub(frames);
+        self.sound_timer = self.sound_timer.saturating_sub(frames);
 
+        let pc = self.pc as usize;
+        self.check_range(pc, 2)?;
         let opcode = self.fetch_opcode();
+        let decoded = match instruction::decode(opcode) {
+            Ok(instruction) if instruction.is_megachip() && !self.is_megachip() => {
+                Ok(Instruction::MachineCall {
+                    nnn: opcode & 0x0FFF,
+                })
+            }
+            decoded => decoded,
+        };
+        if self.quirks.display_wait && !new_frame && matches!(decoded, Ok(Instruction::Draw { .. }))
+        {
+            // Idle out the rest of the frame; the draw runs at the next one.
+            return Ok(None);
+        }
+        let heatmap = Arc::make_mut(&mut self.heatmap);
+        heatmap.record(Access::Execute, pc);
+        heatmap.record(Access::Execute, pc + 1);
 
-        self.execute_opcode(opcode);
+        match decoded {
+            Ok(instruction) => self.execute(instruction),
+            Err(error) => self.unknown_opcode(error.opcode).map(|()| None),
+        }
     }
 
-    fn fetch_opcode(&self) -> u16 {
-        let hi = self.memory[self.pc as usize] as u16;
-        let lo = self.memory[self.pc as usize + 1] as u16;
-        (hi << 8) | lo
+    /// The opcode at PC. Bytes past the end of memory read as zero.
+    pub fn fetch_opcode(&self) -> u16 {
+        self.opcode_at(self.pc as usize)
     }
 
-    fn execute_opcode(&mut self, opcode: u16) {
-        let nibbles = (
-            ((opcode & 0xF000) >> 12) as u8,
-            ((opcode & 0x0F00) >> 8) as u8,
-            ((opcode & 0x00F0) >> 4) as u8,
-            (opcode & 0x000F) as u8,
-        );
-        let nnn = (opcode & 0x0FFF) as u16;
-        let nn = (opcode & 0x00FF) as u8;
-        let x = nibbles.1 as u8;
-        let y = nibbles.2 as u8;
-        let n = nibbles.3 as u8;
-
-        match nibbles {
-            (0x0, 0x0, 0xE, 0x0) => self.op_00e0(),
-            (0x0, 0x0, 0xE, 0xE) => self.op_00ee(),
-            (0x1, _, _, _) => self.op_1nnn(nnn),
-            (0x2, _, _, _) => self.op_2nnn(nnn),
-            (0x3, _, _, _) => self.op_3xnn(x, nn),
-            (0x4, _, _, _) => self.op_4xnn(x, nn),
-            (0x5, _, _, _) => self.op_5xy0(x, y),
-            (0x6, _, _, _) => self.op_6xnn(x, nn),
-            (0x7, _, _, _) => self.op_7xnn(x, nn),
-            (0x8, _, _, 0x0) => self.op_8xy0(x, y),
-            (0x8, _, _, 0x1) => self.op_8xy1(x, y),
-            (0x8, _, _, 0x2) => self.op_8xy2(x, y),
-            (0x8, _, _, 0x3) => self.op_8xy3(x, y),
-            (0x8, _, _, 0x4) => self.op_8xy4(x, y),
-            (0x8, _, _, 0x5) => self.op_8xy5(x, y),
-            (0x8, _, _, 0x6) => self.op_8xy6(x, y),
-            (0x8, _, _, 0x7) => self.op_8xy7(x, y),
-            (0x8, _, _, 0xE) => self.op_8xye(x, y),
-            (0x9, _, _, 0x0) => self.op_9xy0(x, y),
-            (0xA, _, _, _) => self.op_annn(nnn),
-            (0xB, _, _, _) => self.op_bnnn(nnn),
-            (0xD, _, _, _) => self.op_dxyn(x, y, n),
-            (0xF, _, 0x1, 0x5) => self.op_fx15(x),
-            (0xF, _, 0x3, 0x3) => self.op_fx33(x),
-            (0xF, _, 0x5, 0x5) => self.op_fx55(x),
-            (0xF, _, 0x6, 0x5) => self.op_fx65(x),
-            _ => {
-                panic!("unexpected opcode: {:#06x}", opcode);
-            }
+    fn opcode_at(&self, address: usize) -> u16 {
+        let byte = |address: usize| self.memory.get(address).copied().unwrap_or(0);
+        u16::from_be_bytes([byte(address), byte(address + 1)])
+    }
+
+    /// PC moved on `length` bytes. With 64KB of memory the last instruction
+    /// sits at 0xFFFE, and stepping past it would overflow PC.
+    fn next_pc(&self, length: u16) -> Result<u16, CpuError> {
+        self.pc
+            .checked_add(length)
+            .ok_or(CpuError::MemoryOutOfRange {
+                pc: self.pc,
+                address: self.pc as usize + length as usize,
+            })
+    }
+
+    /// Fails unless `len` bytes from `start` are all in memory. Instructions
+    /// check up front so a failing one doesn't leave a partial write behind.
+    fn check_range(&self, start: usize, len: usize) -> Result<(), CpuError> {
+        match start + len > self.memory.len() {
+            true => Err(CpuError::MemoryOutOfRange {
+                pc: self.pc,
+                address: start.max(self.memory.len()),
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Reads a byte on behalf of the running program.
+    fn read(&mut self, address: usize) -> u8 {
+        Arc::make_mut(&mut self.heatmap).record(Access::Read, address);
+        self.accesses.push((Access::Read, address));
+        self.memory[address]
+    }
+
+    /// Writes a byte on behalf of the running program.
+    fn write(&mut self, address: usize, byte: u8) {
+        Arc::make_mut(&mut self.heatmap).record(Access::Write, address);
+        self.accesses.push((Access::Write, address));
+        self.memory[address] = byte;
+    }
+
+    /// Executes an already decoded instruction at PC.
+    pub fn execute(&mut self, instruction: Instruction) -> Result<Option<CpuEvent>, CpuError> {
+        let event = match instruction {
+            Instruction::Jump { nnn } if nnn == self.pc => Some(CpuEvent::Halted),
+            Instruction::Exit => Some(CpuEvent::Exited),
+            Instruction::SaveFlags { .. } => Some(CpuEvent::FlagsSaved),
+            _ => None,
+        };
+        // Whatever doesn't jump goes on past itself, so make sure it can
+        // before anything changes.
+        let length = match instruction {
+            Instruction::Jump { .. } | Instruction::JumpOffset { .. } | Instruction::Return => 0,
+            Instruction::LongIndex | Instruction::MegaIndex { .. } => 4,
+            _ => 2,
+        };
+        self.next_pc(length)?;
+        match instruction {
+            Instruction::MegaOff => self.op_0010(),
+            Instruction::MegaOn => self.op_0011(),
+            Instruction::MegaScrollUp { n } => self.op_00bn(n),
+            Instruction::ScrollDown { n } => self.op_00cn(n),
+            Instruction::ScrollUp { n } => self.op_00dn(n),
+            Instruction::ClearScreen => self.op_00e0(),
+            Instruction::Return => self.op_00ee()?,
+            Instruction::ScrollRight => self.op_00fb(),
+            Instruction::ScrollLeft => self.op_00fc(),
+            Instruction::Exit => {}
+            Instruction::LowResolution => self.op_00fe(),
+            Instruction::HighResolution => self.op_00ff(),
+            Instruction::MegaIndex { nn } => self.op_01nn(nn)?,
+            Instruction::LoadPalette { nn } => self.op_02nn(nn)?,
+            Instruction::SpriteWidth { nn } => self.op_03nn(nn),
+            Instruction::SpriteHeight { nn } => self.op_04nn(nn),
+            Instruction::ScreenAlpha { nn } => self.op_05nn(nn),
+            Instruction::PlaySample { .. } => self.op_060n(),
+            Instruction::StopSample => self.op_0700(),
+            Instruction::BlendMode { .. } => self.op_080n(),
+            Instruction::CollisionColor { nn } => self.op_09nn(nn),
+            Instruction::MachineCall { nnn } => self.op_0nnn(nnn)?,
+            Instruction::Jump { nnn } => self.op_1nnn(nnn),
+            Instruction::Call { nnn } => self.op_2nnn(nnn)?,
+            Instruction::SkipIfEqual { x, nn } => self.op_3xnn(x, nn)?,
+            Instruction::SkipIfNotEqual { x, nn } => self.op_4xnn(x, nn)?,
+            Instruction::SkipIfRegistersEqual { x, y } => self.op_5xy0(x, y)?,
+            Instruction::StoreRange { x, y } => self.op_5xy2(x, y)?,
+            Instruction::LoadRange { x, y } => self.op_5xy3(x, y)?,
+            Instruction::Set { x, nn } => self.op_6xnn(x, nn),
+            Instruction::AddImmediate { x, nn } => self.op_7xnn(x, nn),
+            Instruction::Move { x, y } => self.op_8xy0(x, y),
+            Instruction::Or { x, y } => self.op_8xy1(x, y),
+            Instruction::And { x, y } => self.op_8xy2(x, y),
+            Instruction::Xor { x, y } => self.op_8xy3(x, y),
+            Instruction::Add { x, y } => self.op_8xy4(x, y),
+            Instruction::Sub { x, y } => self.op_8xy5(x, y),
+            Instruction::ShiftRight { x, y } => self.op_8xy6(x, y),
+            Instruction::SubReverse { x, y } => self.op_8xy7(x, y),
+            Instruction::ShiftLeft { x, y } => self.op_8xye(x, y),
+            Instruction::SkipIfRegistersNotEqual { x, y } => self.op_9xy0(x, y)?,
+            Instruction::SetIndex { nnn } => self.op_annn(nnn),
+            Instruction::JumpOffset { nnn } => self.op_bnnn(nnn),
+            Instruction::Random { x, nn } => self.op_cxnn(x, nn),
+            Instruction::Draw { x, y, n } => self.op_dxyn(x, y, n)?,
+            Instruction::SkipIfKey { x } => self.op_ex9e(x)?,
+            Instruction::SkipIfNotKey { x } => self.op_exa1(x)?,
+            Instruction::LongIndex => self.op_f000()?,
+            Instruction::SelectPlanes { mask } => self.op_fn01(mask),
+            Instruction::LoadAudioPattern => self.op_f002()?,
+            Instruction::WaitKey { x } => self.op_fx0a(x),
+            Instruction::SetDelayTimer { x } => self.op_fx15(x),
+            Instruction::SetSoundTimer { x } => self.op_fx18(x),
+            Instruction::AddIndex { x } => self.op_fx1e(x),
+            Instruction::FontGlyph { x } => self.op_fx29(x),
+            Instruction::BigFontGlyph { x } => self.op_fx30(x),
+            Instruction::SetPitch { x } => self.op_fx3a(x),
+            Instruction::Bcd { x } => self.op_fx33(x)?,
+            Instruction::Store { x } => self.op_fx55(x)?,
+            Instruction::Load { x } => self.op_fx65(x)?,
+            Instruction::SaveFlags { x } => self.op_fx75(x),
+            Instruction::LoadFlags { x } => self.op_fx85(x),
         }
+        Ok(event)
+    }
+
+    fn unknown_opcode(&mut self, opcode: u16) -> Result<(), CpuError> {
+        let error = CpuError::UnknownOpcode {
+            pc: self.pc,
+            opcode,
+        };
+        self.apply(self.on_unknown_opcode, error)
+    }
+
+    /// Runs a 0NNN. There is no 1802 to run the machine code on, so it can
+    /// only be skipped, except for the hi-res CHIP-8 routine at 0x230 that
+    /// clears its screen.
+    fn op_0nnn(&mut self, nnn: u16) -> Result<(), CpuError> {
+        if self.platform == Some(Platform::HiresChip8) && nnn == 0x230 {
+            self.display.clear(self.planes);
+            self.pc += 2;
+            return Ok(());
+        }
+        let error = CpuError::MachineCodeCall { pc: self.pc, nnn };
+        self.apply(self.on_machine_code, error)
+    }
+
+    fn apply(&mut self, policy: OpcodePolicy, error: CpuError) -> Result<(), CpuError> {
+        match policy {
+            OpcodePolicy::Halt => return Err(error),
+            OpcodePolicy::Skip => {}
+            OpcodePolicy::Log => warn!("Skipping {}", error),
+        }
+        self.pc = self.next_pc(2)?;
+        Ok(())
+    }
+
+    fn op_0010(&mut self) {
+        self.display.set_resolution(Resolution::Low);
+        self.pc += 2;
+    }
+
+    /// Switches to MegaChip's color screen, unless it's already showing.
+    fn op_0011(&mut self) {
+        if self.display.resolution() != Resolution::Mega {
+            self.display.set_resolution(Resolution::Mega);
+        }
+        self.pc += 2;
+    }
+
+    fn op_00bn(&mut self, n: u8) {
+        self.display.scroll_up(self.planes, n as usize);
+        self.pc += 2;
+    }
+
+    /// Scrolls by pixels of the current resolution, like modern SUPER-CHIP
+    /// (1.1 scrolled by half as much in low resolution).
+    fn op_00cn(&mut self, n: u8) {
+        self.display.scroll_down(self.planes, n as usize);
+        self.pc += 2;
+    }
+
+    fn op_00dn(&mut self, n: u8) {
+        self.display.scroll_up(self.planes, n as usize);
+        self.pc += 2;
     }
 
     fn op_00e0(&mut self) {
-        self.display.clear();
+        self.display.clear(self.planes);
         self.pc += 2;
     }
 
-    fn op_00ee(&mut self) {
+    fn op_00ee(&mut self) -> Result<(), CpuError> {
+        if self.stack_pointer == 0 {
+            return Err(CpuError::StackUnderflow { pc: self.pc });
+        }
         self.stack_pointer -= 1;
         self.pc = self.stack[self.stack_pointer];
+        Ok(())
+    }
+
+    fn op_00fb(&mut self) {
+        self.display.scroll_right(self.planes, 4);
+        self.pc += 2;
+    }
+
+    fn op_00fc(&mut self) {
+        self.display.scroll_left(self.planes, 4);
+        self.pc += 2;
     }
 
+    fn op_00fe(&mut self) {
+        self.display.set_resolution(Resolution::Low);
+        self.pc += 2;
+    }
+
+    fn op_00ff(&mut self) {
+        self.display.set_resolution(Resolution::High);
+        self.pc += 2;
+    }
+
+    /// Sets I to a 24-bit address: the top byte from the opcode, the rest
+    /// from the word after it. Memory here ends at 64KB, so anything past
+    /// that is out of range.
+    fn op_01nn(&mut self, nn: u8) -> Result<(), CpuError> {
+        let operand = self.pc as usize + 2;
+        self.check_range(operand, 2)?;
+        let low = u16::from_be_bytes([self.read(operand), self.read(operand + 1)]);
+        let address = (nn as usize) << 16 | low as usize;
+        self.check_range(address, 1)?;
+        self.index = address as u16;
+        self.pc += 4;
+        Ok(())
+    }
+
+    /// Loads NN colors from I, four ARGB bytes each, into the palette from
+    /// index 1 on. Outside MegaChip mode there is no palette to load into.
+    fn op_02nn(&mut self, nn: u8) -> Result<(), CpuError> {
+        let index = self.index as usize;
+        let count = nn as usize;
+        self.check_range(index, count * 4)?;
+        let colors: Vec<_> = (0..count)
+            .map(|color| {
+                let address = index + color * 4;
+                let [a, r, g, b] = [0, 1, 2, 3].map(|byte| self.read(address + byte));
+                [r, g, b, a]
+            })
+            .collect();
+        if let Some(mega) = &mut self.display.mega {
+            mega.palette[1..=count].copy_from_slice(&colors);
+        }
+        self.pc += 2;
+        Ok(())
+    }
+
+    fn op_03nn(&mut self, nn: u8) {
+        self.sprite_size.0 = nn;
+        self.pc += 2;
+    }
+
+    fn op_04nn(&mut self, nn: u8) {
+        self.sprite_size.1 = nn;
+        self.pc += 2;
+    }
+
+    fn op_05nn(&mut self, nn: u8) {
+        if let Some(mega) = &mut self.display.mega {
+            mega.alpha = nn;
+        }
+        self.pc += 2;
+    }
+
+    /// Digitized sound isn't supported, so the sample is skipped.
+    fn op_060n(&mut self) {
+        self.pc += 2;
+    }
+
+    fn op_0700(&mut self) {
+        self.pc += 2;
+    }
+
+    /// Only the normal blend mode is supported: sprite pixels always
+    /// replace what's under them.
+    fn op_080n(&mut self) {
+        self.pc += 2;
+    }
+
+    fn op_09nn(&mut self, nn: u8) {
+        self.collision_color = nn;
+        self.pc += 2;
+    }
+
+    /// Hi-res CHIP-8 programs start by jumping from 0x200 into the
+    /// interpreter patch at 0x260, which sets up the 64x64 screen; instead
+    /// the screen is switched here and the program proper starts at 0x2C0.
     fn op_1nnn(&mut self, nnn: u16) {
+        if self.platform == Some(Platform::HiresChip8) && self.pc == 0x200 && nnn == 0x260 {
+            self.display.set_resolution(Resolution::Tall);
+            self.pc = 0x2C0;
+            return;
+        }
         self.pc = nnn;
     }
 
-    fn op_2nnn(&mut self, nnn: u16) {
+    fn op_2nnn(&mut self, nnn: u16) -> Result<(), CpuError> {
+        if self.stack_pointer == self.stack.len() {
+            return Err(CpuError::StackOverflow {
+                pc: self.pc,
+                depth: self.stack.len(),
+            });
+        }
         self.stack[self.stack_pointer] = self.pc + 2;
         self.stack_pointer += 1;
+        if self.stack_pointer > self.max_stack_depth {
+            self.max_stack_depth = self.stack_pointer;
+            if self.max_stack_depth == DEFAULT_STACK_DEPTH + 1 {
+                warn!(
+                    "ROM nests calls deeper than the {} levels most interpreters allow",
+                    DEFAULT_STACK_DEPTH
+                );
+            }
+        }
         self.pc = nnn;
+        Ok(())
     }
 
-    fn op_3xnn(&mut self, x: u8, nn: u8) {
-        if self.registers[x as usize] == nn {
-            self.pc += 2;
+    /// Steps past this instruction and, if `skip`, the next one too, which
+    /// is four bytes long if it's XO-CHIP's F000 NNNN or MegaChip's 01NN
+    /// NNNN.
+    fn skip_if(&mut self, skip: bool) -> Result<(), CpuError> {
+        let mut length = 2;
+        if skip {
+            let opcode = self.opcode_at(self.pc as usize + 2);
+            let long = opcode == 0xF000 || (self.is_megachip() && opcode & 0xFF00 == 0x0100);
+            length += if long { 4 } else { 2 };
         }
-        self.pc += 2;
+        self.pc = self.next_pc(length)?;
+        Ok(())
     }
 
-    fn op_4xnn(&mut self, x: u8, nn: u8) {
-        if self.registers[x as usize] != nn {
-            self.pc += 2;
+    fn op_3xnn(&mut self, x: u8, nn: u8) -> Result<(), CpuError> {
+        self.skip_if(self.registers[x as usize] == nn)
+    }
+
+    fn op_4xnn(&mut self, x: u8, nn: u8) -> Result<(), CpuError> {
+        self.skip_if(self.registers[x as usize] != nn)
+    }
+
+    fn op_5xy0(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        self.skip_if(self.registers[x as usize] == self.registers[y as usize])
+    }
+
+    /// Stores VX..=VY at I, leaving I where it is. With X above Y the
+    /// registers are stored in reverse order, VX first.
+    fn op_5xy2(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        let registers = register_range(x, y);
+        let index = self.index as usize;
+        self.check_range(index, registers.len())?;
+        for (offset, register) in registers.enumerate() {
+            self.write(index + offset, self.registers[register]);
         }
         self.pc += 2;
+        Ok(())
     }
 
-    fn op_5xy0(&mut self, x: u8, y: u8) {
-        let x = self.registers[x as usize];
-        let y = self.registers[y as usize];
-        if x == y {
-            self.pc += 2;
+    /// Loads VX..=VY from I, in the same order 5XY2 stores them.
+    fn op_5xy3(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        let registers = register_range(x, y);
+        let index = self.index as usize;
+        self.check_range(index, registers.len())?;
+        for (offset, register) in registers.enumerate() {
+            self.registers[register] = self.read(index + offset);
         }
         self.pc += 2;
+        Ok(())
     }
 
     fn op_6xnn(&mut self, x: u8, nn: u8) {
@@ -191,19 +972,28 @@ impl Cpu {
 
     fn op_8xy1(&mut self, x: u8, y: u8) {
         self.registers[x as usize] |= self.registers[y as usize];
+        self.reset_vf_after_logic();
         self.pc += 2;
     }
 
     fn op_8xy2(&mut self, x: u8, y: u8) {
         self.registers[x as usize] &= self.registers[y as usize];
+        self.reset_vf_after_logic();
         self.pc += 2;
     }
 
     fn op_8xy3(&mut self, x: u8, y: u8) {
         self.registers[x as usize] ^= self.registers[y as usize];
+        self.reset_vf_after_logic();
         self.pc += 2;
     }
 
+    fn reset_vf_after_logic(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0;
+        }
+    }
+
     fn op_8xy4(&mut self, x: u8, y: u8) {
         let vx = self.registers[x as usize];
         let vy = self.registers[y as usize];
@@ -216,15 +1006,23 @@ impl Cpu {
     fn op_8xy5(&mut self, x: u8, y: u8) {
         let vx = self.registers[x as usize];
         let vy = self.registers[y as usize];
-        let (value, overflowed) = vx.overflowing_add(vy);
-        self.registers[0xF] = overflowed as u8;
+        let (value, borrowed) = vx.overflowing_sub(vy);
         self.registers[x as usize] = value;
+        self.registers[0xF] = !borrowed as u8;
         self.pc += 2;
     }
 
-    fn op_8xy6(&mut self, x: u8, _y: u8) {
-        let mut value = self.registers[x as usize];
-        let shifted_bit = value & 0xF;
+    fn shift_source(&self, x: u8, y: u8) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.registers[y as usize]
+        } else {
+            self.registers[x as usize]
+        }
+    }
+
+    fn op_8xy6(&mut self, x: u8, y: u8) {
+        let mut value = self.shift_source(x, y);
+        let shifted_bit = value & 0x1;
         value >>= 1;
         self.registers[x as usize] = value;
         self.registers[0xF] = shifted_bit;
@@ -234,14 +1032,14 @@ impl Cpu {
     fn op_8xy7(&mut self, x: u8, y: u8) {
         let vx = self.registers[x as usize];
         let vy = self.registers[y as usize];
-        let (value, overflowed) = vy.overflowing_add(vx);
-        self.registers[0xF] = overflowed as u8;
+        let (value, borrowed) = vy.overflowing_sub(vx);
         self.registers[x as usize] = value;
+        self.registers[0xF] = !borrowed as u8;
         self.pc += 2;
     }
 
-    fn op_8xye(&mut self, x: u8, _y: u8) {
-        let mut value = self.registers[x as usize];
+    fn op_8xye(&mut self, x: u8, y: u8) {
+        let mut value = self.shift_source(x, y);
         let shifted_bit = value >> 7;
         value <<= 1;
         self.registers[x as usize] = value;
@@ -249,13 +1047,8 @@ impl Cpu {
         self.pc += 2;
     }
 
-    fn op_9xy0(&mut self, x: u8, y: u8) {
-        let x = self.registers[x as usize];
-        let y = self.registers[y as usize];
-        if x != y {
-            self.pc += 2;
-        }
-        self.pc += 2;
+    fn op_9xy0(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        self.skip_if(self.registers[x as usize] != self.registers[y as usize])
     }
 
     fn op_annn(&mut self, nnn: u16) {
@@ -264,22 +1057,190 @@ impl Cpu {
     }
 
     fn op_bnnn(&mut self, nnn: u16) {
-        self.pc = self.registers[0] as u16 + nnn;
-    }
-
-    fn op_dxyn(&mut self, x: u8, y: u8, n: u8) {
-        self.registers[0x0f] = 0;
-        for byte in 0..n {
-            let y = (self.registers[y as usize] as usize + byte as usize) % Display::HEIGHT;
-            for bit in 0..8 {
-                let x = (self.registers[x as usize] as usize + bit) % Display::WIDTH;
-                let color = (self.memory[self.index as usize + byte as usize] >> (7 - bit)) & 1;
-                let turned_off = color & self.display.pixels[y][x] as u8;
-                self.registers[0x0f] |= turned_off;
-                self.display.pixels[y][x] ^= color != 0;
+        let register = if self.quirks.jump_uses_vx {
+            (nnn >> 8) as usize
+        } else {
+            0
+        };
+        self.pc = self.registers[register] as u16 + nnn;
+    }
+
+    fn op_cxnn(&mut self, x: u8, nn: u8) {
+        self.registers[x as usize] = self.rng.below(256) as u8 & nn;
+        self.pc += 2;
+    }
+
+    /// Draws an 8xN sprite, or in high resolution a 16x16 one for DXY0.
+    /// Each selected plane gets its own copy of the sprite data, one after
+    /// the other from I. VF is set on collision; in high resolution it
+    /// counts the rows that collided or were clipped off the bottom, like
+    /// SUPER-CHIP.
+    fn op_dxyn(&mut self, x: u8, y: u8, n: u8) -> Result<(), CpuError> {
+        if self.display.mega.is_some() {
+            return self.op_dxyn_mega(x, y, n);
+        }
+        let hires = self.display.is_hires();
+        let (rows, columns) = match n {
+            0 if hires => (16, 16),
+            n => (n as usize, 8),
+        };
+        let row_bytes = columns / 8;
+        let selected: Vec<usize> = (0..Display::PLANES)
+            .filter(|plane| self.planes & (1 << plane) != 0)
+            .collect();
+        let sprite_len = rows * row_bytes;
+        self.check_range(self.index as usize, sprite_len * selected.len())?;
+        // The starting position always wraps; only the sprite's overhang
+        // is clipped or wrapped depending on the quirk.
+        let (width, height) = (self.display.width(), self.display.height());
+        let vx = self.registers[x as usize] as usize % width;
+        let vy = self.registers[y as usize] as usize % height;
+        let clip = self.quirks.clip_sprites;
+        let mut collided = vec![false; rows];
+        for (i, &plane) in selected.iter().enumerate() {
+            let sprite = self.index as usize + i * sprite_len;
+            for row in 0..rows {
+                if clip && vy + row >= height {
+                    if hires {
+                        collided[row..].fill(true);
+                    }
+                    break;
+                }
+                let y = (vy + row) % height;
+                let address = sprite + row * row_bytes;
+                let mut pattern = 0u16;
+                for byte in 0..row_bytes {
+                    pattern = pattern << 8 | self.read(address + byte) as u16;
+                }
+                for bit in 0..columns {
+                    if clip && vx + bit >= width {
+                        break;
+                    }
+                    let x = (vx + bit) % width;
+                    let on = (pattern >> (columns - 1 - bit)) & 1 != 0;
+                    let pixel = &mut self.display.planes[plane][y][x];
+                    collided[row] |= on && *pixel;
+                    *pixel ^= on;
+                }
+            }
+        }
+        let collided_rows = collided.iter().filter(|&&row| row).count();
+        self.registers[0x0f] = match hires {
+            true => collided_rows as u8,
+            false => (collided_rows > 0) as u8,
+        };
+        self.pc += 2;
+        Ok(())
+    }
+
+    /// Draws in MegaChip mode, clipping at the edges. Sprites are 03NN by
+    /// 04NN palette indices from I, each non-zero one replacing the pixel
+    /// under it; VF is set if any lands on a pixel of the collision color
+    /// other than the background. The font
+    /// has no colors, so its glyphs are 8xN bit patterns XORed onto the
+    /// screen in color 0xFF, colliding with anything drawn.
+    fn op_dxyn_mega(&mut self, x: u8, y: u8, n: u8) -> Result<(), CpuError> {
+        let index = self.index as usize;
+        let font = self.font_address as usize;
+        let glyph = (font..font + FONT_SIZE + BIG_FONT_SIZE).contains(&index);
+        let dimension = |nn: u8| if nn == 0 { 256 } else { nn as usize };
+        let (columns, rows, len) = if glyph {
+            (8, n as usize, n as usize)
+        } else {
+            let (columns, rows) = (dimension(self.sprite_size.0), dimension(self.sprite_size.1));
+            (columns, rows, columns * rows)
+        };
+        self.check_range(index, len)?;
+        let sprite: Vec<u8> = (index..index + len)
+            .map(|address| self.read(address))
+            .collect();
+        let (width, height) = (self.display.width(), self.display.height());
+        let vx = self.registers[x as usize] as usize % width;
+        let vy = self.registers[y as usize] as usize % height;
+        let collision_color = self.collision_color;
+        let mega = self
+            .display
+            .mega
+            .as_mut()
+            .expect("MegaChip mode has a color screen");
+        let mut collided = false;
+        for row in 0..rows.min(height - vy) {
+            for column in 0..columns.min(width - vx) {
+                let pixel = &mut mega.pixels[vy + row][vx + column];
+                if glyph {
+                    if sprite[row] & (0x80 >> column) != 0 {
+                        collided |= *pixel != 0;
+                        *pixel = if *pixel == 0 { 0xFF } else { 0 };
+                    }
+                } else {
+                    let color = sprite[row * columns + column];
+                    if color != 0 {
+                        collided |= *pixel != 0 && *pixel == collision_color;
+                        *pixel = color;
+                    }
+                }
             }
         }
+        self.registers[0x0f] = collided as u8;
         self.pc += 2;
+        Ok(())
+    }
+
+    fn key_held(&self, x: u8) -> bool {
+        let key = self.registers[x as usize] & 0xF;
+        self.keys & (1 << key) != 0
+    }
+
+    fn op_ex9e(&mut self, x: u8) -> Result<(), CpuError> {
+        self.skip_if(self.key_held(x))
+    }
+
+    fn op_exa1(&mut self, x: u8) -> Result<(), CpuError> {
+        self.skip_if(!self.key_held(x))
+    }
+
+    /// Sets I to the 16-bit address in the word after the opcode, so
+    /// XO-CHIP programs can reach all of memory.
+    fn op_f000(&mut self) -> Result<(), CpuError> {
+        let operand = self.pc as usize + 2;
+        self.check_range(operand, 2)?;
+        self.index = u16::from_be_bytes([self.read(operand), self.read(operand + 1)]);
+        self.pc += 4;
+        Ok(())
+    }
+
+    /// Selects the display planes later instructions draw to. Unlike the
+    /// other FX opcodes the X nibble is a plane mask, not a register.
+    fn op_fn01(&mut self, mask: u8) {
+        self.planes = mask;
+        self.pc += 2;
+    }
+
+    fn op_f002(&mut self) -> Result<(), CpuError> {
+        let index = self.index as usize;
+        self.check_range(index, AUDIO_PATTERN_SIZE)?;
+        let mut pattern = [0; AUDIO_PATTERN_SIZE];
+        for (offset, sample) in pattern.iter_mut().enumerate() {
+            *sample = self.read(index + offset);
+        }
+        self.audio_pattern = Some(pattern);
+        self.pc += 2;
+        Ok(())
+    }
+
+    /// Blocks until a key is pressed and released again, then stores it in
+    /// VX. Like the COSMAC VIP this completes on the release, so the key
+    /// can't also trigger whatever the program does next.
+    fn op_fx0a(&mut self, x: u8) {
+        match self.key_wait {
+            Some(key) if self.keys & (1 << key) == 0 => {
+                self.registers[x as usize] = key;
+                self.key_wait = None;
+                self.pc += 2;
+            }
+            Some(_) => {}
+            None => self.key_wait = (0..16).find(|key| self.keys & (1 << key) != 0),
+        }
     }
 
     fn op_fx15(&mut self, x: u8) {
@@ -287,28 +1248,190 @@ impl Cpu {
         self.pc += 2;
     }
 
-    fn op_fx33(&mut self, x: u8) {
+    fn op_fx18(&mut self, x: u8) {
+        self.sound_timer = self.registers[x as usize];
+        self.pc += 2;
+    }
+
+    fn op_fx1e(&mut self, x: u8) {
+        let index = self.index.wrapping_add(self.registers[x as usize] as u16);
+        self.index = index;
+        if self.quirks.index_overflow_sets_vf {
+            self.registers[0xF] = (index > 0xFFF) as u8;
+        }
+        self.pc += 2;
+    }
+
+    fn op_fx29(&mut self, x: u8) {
+        let digit = (self.registers[x as usize] & 0xF) as u16;
+        self.index = self.font_address + digit * 5;
+        self.pc += 2;
+    }
+
+    fn op_fx30(&mut self, x: u8) {
+        let digit = (self.registers[x as usize] & 0xF) as u16;
+        self.index = self.font_address + FONT_SIZE as u16 + digit * 10;
+        self.pc += 2;
+    }
+
+    fn op_fx3a(&mut self, x: u8) {
+        self.pitch = self.registers[x as usize];
+        self.pc += 2;
+    }
+
+    fn op_fx33(&mut self, x: u8) -> Result<(), CpuError> {
         let idx = self.index as usize;
         let addr = x as usize;
-        self.memory[idx] = self.registers[addr] / 100;
-        self.memory[idx + 1] = (self.registers[addr] % 100) / 10;
-        self.memory[idx + 1] = self.registers[addr] % 10;
+        self.check_range(idx, 3)?;
+        self.write(idx, self.registers[addr] / 100);
+        self.write(idx + 1, (self.registers[addr] % 100) / 10);
+        self.write(idx + 2, self.registers[addr] % 10);
         self.pc += 2;
+        Ok(())
     }
 
-    fn op_fx55(&mut self, x: u8) {
+    fn op_fx55(&mut self, x: u8) -> Result<(), CpuError> {
+        self.check_range(self.index as usize, x as usize + 1)?;
         for offset in 0..=x {
-            let addr = self.index + offset as u16;
-            self.memory[addr as usize] = self.registers[offset as usize];
+            let addr = self.index as usize + offset as usize;
+            self.write(addr, self.registers[offset as usize]);
         }
+        self.index = self.index.wrapping_add(self.quirks.memory_increment(x));
         self.pc += 2;
+        Ok(())
     }
 
-    fn op_fx65(&mut self, x: u8) {
+    fn op_fx65(&mut self, x: u8) -> Result<(), CpuError> {
+        self.check_range(self.index as usize, x as usize + 1)?;
         for offset in 0..=x {
-            let addr = self.index + offset as u16;
-            self.registers[offset as usize] = self.memory[addr as usize];
+            let addr = self.index as usize + offset as usize;
+            self.registers[offset as usize] = self.read(addr);
         }
+        self.index = self.index.wrapping_add(self.quirks.memory_increment(x));
+        self.pc += 2;
+        Ok(())
+    }
+
+    /// Registers past V7 have no flag to go to; like SUPER-CHIP, only
+    /// V0..V7 are saved.
+    fn op_fx75(&mut self, x: u8) {
+        let count = (x as usize).min(RPL_FLAGS - 1) + 1;
+        self.rpl_flags[..count].copy_from_slice(&self.registers[..count]);
+        self.pc += 2;
+    }
+
+    fn op_fx85(&mut self, x: u8) {
+        let count = (x as usize).min(RPL_FLAGS - 1) + 1;
+        self.registers[..count].copy_from_slice(&self.rpl_flags[..count]);
         self.pc += 2;
     }
 }
+
+/// The registers from VX to VY, counting down when X is above Y.
+fn register_range(x: u8, y: u8) -> impl ExactSizeIterator<Item = usize> {
+    let (x, y) = (x as usize, y as usize);
+    (0..x.abs_diff(y) + 1).map(move |i| if x <= y { x + i } else { x - i })
+}
+
+/// Reads a serialized state front to back.
+struct StateReader<'a>(&'a [u8]);
+
+impl<'a> StateReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("machine state is truncated".to_string());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const I: u16 = 0x300;
+
+    /// A CPU about to run `opcode`, with I at [`I`] and VN holding 0x10 + N.
+    fn cpu_running(opcode: u16) -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.load_rom(&opcode.to_be_bytes());
+        cpu.index = I;
+        for (n, register) in cpu.registers.iter_mut().enumerate() {
+            *register = 0x10 + n as u8;
+        }
+        cpu
+    }
+
+    fn memory_at_i(cpu: &Cpu, len: usize) -> &[u8] {
+        &cpu.memory[I as usize..I as usize + len]
+    }
+
+    #[test]
+    fn store_range_forward() {
+        let mut cpu = cpu_running(0x5242);
+        cpu.tick().unwrap();
+        assert_eq!(memory_at_i(&cpu, 4), [0x12, 0x13, 0x14, 0x00]);
+        assert_eq!(cpu.index, I);
+    }
+
+    #[test]
+    fn store_range_reversed() {
+        let mut cpu = cpu_running(0x5422);
+        cpu.tick().unwrap();
+        assert_eq!(memory_at_i(&cpu, 4), [0x14, 0x13, 0x12, 0x00]);
+        assert_eq!(cpu.index, I);
+    }
+
+    #[test]
+    fn store_range_single() {
+        let mut cpu = cpu_running(0x5332);
+        cpu.tick().unwrap();
+        assert_eq!(memory_at_i(&cpu, 2), [0x13, 0x00]);
+        assert_eq!(cpu.index, I);
+    }
+
+    #[test]
+    fn load_range_forward() {
+        let mut cpu = cpu_running(0x5243);
+        cpu.memory[I as usize..I as usize + 3].copy_from_slice(&[0xA0, 0xA1, 0xA2]);
+        cpu.tick().unwrap();
+        assert_eq!(cpu.registers[1..6], [0x11, 0xA0, 0xA1, 0xA2, 0x15]);
+        assert_eq!(cpu.index, I);
+    }
+
+    #[test]
+    fn load_range_reversed() {
+        let mut cpu = cpu_running(0x5423);
+        cpu.memory[I as usize..I as usize + 3].copy_from_slice(&[0xA0, 0xA1, 0xA2]);
+        cpu.tick().unwrap();
+        assert_eq!(cpu.registers[1..6], [0x11, 0xA2, 0xA1, 0xA0, 0x15]);
+        assert_eq!(cpu.index, I);
+    }
+
+    #[test]
+    fn load_range_single() {
+        let mut cpu = cpu_running(0x5333);
+        cpu.memory[I as usize] = 0xA0;
+        cpu.tick().unwrap();
+        assert_eq!(cpu.registers[2..5], [0x12, 0xA0, 0x14]);
+        assert_eq!(cpu.index, I);
+    }
+}
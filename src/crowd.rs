@@ -0,0 +1,115 @@
+//! "Chat plays CHIP-8": key votes arriving over TCP.
+//!
+//! Each line a client sends is one vote, either just a key (`5`) or a sender
+//! name and a key (`alice 5`) so a chat bridge can relay many people over one
+//! connection. Votes are counted over a short window; the winning key is
+//! then held for the next window. Each sender gets one vote per window.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::input::Input;
+
+/// How long votes are collected before the winner is pressed.
+const VOTE_WINDOW: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct Ballot {
+    counts: [u32; 16],
+    last_vote: HashMap<String, Instant>,
+}
+
+impl Ballot {
+    fn vote(&mut self, sender: String, key: u8) {
+        let now = Instant::now();
+        if let Some(last) = self.last_vote.get(&sender) {
+            if now - *last < VOTE_WINDOW {
+                return;
+            }
+        }
+        self.last_vote.insert(sender, now);
+        self.counts[key as usize] += 1;
+    }
+
+    /// The most voted key, ties going to the lowest. Clears the counts.
+    fn close(&mut self) -> Option<u8> {
+        let counts = std::mem::take(&mut self.counts);
+        let (key, votes) = counts
+            .iter()
+            .enumerate()
+            .max_by(|(a, x), (b, y)| x.cmp(y).then(b.cmp(a)))?;
+        (*votes > 0).then_some(key as u8)
+    }
+}
+
+pub struct CrowdInput {
+    ballot: Arc<Mutex<Ballot>>,
+    window_start: Instant,
+    held: u16,
+}
+
+impl CrowdInput {
+    /// Starts accepting voters on `addr` in the background.
+    pub fn listen(addr: SocketAddr) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Accepting crowd input on {}", listener.local_addr()?);
+        let ballot = Arc::new(Mutex::new(Ballot::default()));
+        let shared = Arc::clone(&ballot);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let ballot = Arc::clone(&shared);
+                        thread::spawn(move || read_votes(stream, ballot));
+                    }
+                    Err(e) => warn!("Failed to accept voter: {}", e),
+                }
+            }
+        });
+        Ok(Self {
+            ballot,
+            window_start: Instant::now(),
+            held: 0,
+        })
+    }
+}
+
+impl Input for CrowdInput {
+    fn keys(&mut self) -> u16 {
+        if self.window_start.elapsed() >= VOTE_WINDOW {
+            self.window_start = Instant::now();
+            self.held = match self.ballot.lock().unwrap().close() {
+                Some(key) => 1 << key,
+                None => 0,
+            };
+        }
+        self.held
+    }
+}
+
+fn read_votes(stream: TcpStream, ballot: Arc<Mutex<Ballot>>) {
+    let peer = match stream.peer_addr() {
+        Ok(peer) => peer.ip().to_string(),
+        Err(_) => return,
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let (sender, key) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [key] => (peer.clone(), key),
+            [name, key] => (format!("{}/{}", peer, name), key),
+            _ => continue,
+        };
+        if let Ok(key @ 0..=0xF) = u8::from_str_radix(key, 16) {
+            ballot.lock().unwrap().vote(sender, key);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Remote control of the debugger over TCP, used by the `chip8-debugger`
+//! binary and scriptable with netcat.
+//!
+//! Clients send one command per line and get back the response lines
+//! followed by a line holding a single `.`. Failed commands answer with a
+//! line starting `error:`. All numbers are hex.
+//!
+//! ```text
+//! regs                 registers, timers and stack
+//! pause | resume
+//! step [N]             execute N instructions (default 1, at most a
+//!                      second's worth) while paused
+//! break ADDR           toggle a breakpoint
+//! mem ADDR [LEN]       hex dump
+//! poke ADDR BYTE...    write memory
+//! dis [ADDR] [N]       disassemble, from PC by default
+//! display              the screen as block art
+//! ```
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use log::{info, warn};
+
+use crate::cpu::Cpu;
+use crate::disasm;
+use crate::emulation::{Machine, Step};
+use crate::PauseReason;
+
+/// A command line from a client and where to send the answer.
+struct Request {
+    line: String,
+    reply: Sender<String>,
+}
+
+pub struct DebugServer {
+    requests: Receiver<Request>,
+}
+
+impl DebugServer {
+    /// Starts accepting debugger connections on `addr` in the background.
+    pub fn listen(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Remote debugger can connect to {}", listener.local_addr()?);
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let sender = sender.clone();
+                        thread::spawn(move || serve(stream, sender));
+                    }
+                    Err(e) => warn!("Failed to accept debugger: {}", e),
+                }
+            }
+        });
+        Ok(Self { requests })
+    }
+
+    /// Answers every command that arrived since the last call.
+    pub fn poll(&mut self, machine: &mut Machine) {
+        while let Ok(request) = self.requests.try_recv() {
+            let response = match execute(&request.line, machine) {
+                Ok(response) => response,
+                Err(e) => format!("error: {}\n", e),
+            };
+            let _ = request.reply.send(response);
+        }
+    }
+}
+
+fn serve(stream: TcpStream, requests: Sender<Request>) {
+    info!("Remote debugger connected from {:?}", stream.peer_addr());
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let (reply, response) = mpsc::channel();
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let request = Request {
+            line,
+            reply: reply.clone(),
+        };
+        if requests.send(request).is_err() {
+            break;
+        }
+        let Ok(response) = response.recv() else {
+            break;
+        };
+        if writer
+            .write_all(response.as_bytes())
+            .and_then(|()| writer.write_all(b".\n"))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn execute(line: &str, machine: &mut Machine) -> Result<String, String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let number = |index: usize, default: Option<usize>| -> Result<usize, String> {
+        match tokens.get(index) {
+            Some(token) => {
+                let hex = token.strip_prefix("0x").unwrap_or(token);
+                usize::from_str_radix(hex, 16).map_err(|_| format!("not a hex number: {}", token))
+            }
+            None => default.ok_or_else(|| "missing argument".to_string()),
+        }
+    };
+    let memory_len = machine.cpu.memory().len();
+    let address = |index: usize, default: Option<usize>| {
+        number(index, default).and_then(|address| match address < memory_len {
+            true => Ok(address),
+            false => Err(format!("address {:#x} is out of range", address)),
+        })
+    };
+
+    let response = match tokens.first().copied() {
+        Some("regs") => registers(&machine.cpu),
+        Some("pause") => {
+            machine.paused = Some(PauseReason::User);
+            String::new()
+        }
+        Some("resume") => {
+            machine.paused = None;
+            String::new()
+        }
+        Some("step") => {
+            if machine.paused.is_none() {
+                return Err("pause first".to_string());
+            }
+            if !machine.running {
+                return Err("the program isn't running".to_string());
+            }
+            // The event loop waits on the machine while this runs.
+            let limit = (machine.cpu.instructions_per_second() as usize).max(1);
+            let count = number(1, Some(1))?;
+            if !(1..=limit).contains(&count) {
+                return Err(format!("can step 1 to {:X} instructions", limit));
+            }
+            let executed = machine.executed;
+            machine.run_step(Step::Instructions(count as u32));
+            let ran = (machine.executed - executed) as usize;
+            let mut out = registers(&machine.cpu);
+            if ran < count {
+                let reason = match (machine.paused, &machine.fault) {
+                    _ if machine.exited => "exited".to_string(),
+                    (Some(PauseReason::Fault), Some(fault)) => fault.message.clone(),
+                    (reason, _) => reason
+                        .map_or_else(String::new, |reason| format!("{:?}", reason).to_lowercase()),
+                };
+                out += &format!("stopped after {:X} instructions: {}\n", ran, reason);
+            }
+            out
+        }
+        Some("break") => {
+            let breakpoints = &mut machine.breakpoints;
+            let address = address(1, None)? as u16;
+            match breakpoints.iter().position(|a| *a == address) {
+                Some(i) => {
+                    breakpoints.remove(i);
+                }
+                None => breakpoints.push(address),
+            }
+            let list: Vec<String> = breakpoints.iter().map(|a| format!("{:03X}", a)).collect();
+            format!("breakpoints: {}\n", list.join(" "))
+        }
+        Some("mem") => {
+            let start = address(1, None)?;
+            let end = start.saturating_add(number(2, Some(16))?).min(memory_len);
+            let mut out = String::new();
+            for (row, bytes) in machine.cpu.memory()[start..end].chunks(16).enumerate() {
+                let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+                out += &format!("{:03X}: {}\n", start + row * 16, hex.join(" "));
+            }
+            out
+        }
+        Some("poke") => {
+            let start = address(1, None)?;
+            let bytes = (2..tokens.len())
+                .map(|i| number(i, None))
+                .collect::<Result<Vec<_>, _>>()?;
+            if bytes.is_empty() || bytes.iter().any(|b| *b > 0xFF) {
+                return Err("expected bytes to write".to_string());
+            }
+            if start + bytes.len() > memory_len {
+                return Err("write runs past the end of memory".to_string());
+            }
+            for (offset, byte) in bytes.into_iter().enumerate() {
+                machine
+                    .cpu
+                    .write_memory((start + offset) as u16, byte as u8);
+            }
+            String::new()
+        }
+        Some("dis") => {
+            let cpu = &machine.cpu;
+            let start = address(1, Some(cpu.pc as usize))?;
+            let memory = cpu.memory();
+            let mut out = String::new();
+            for addr in (start..memory.len() - 1)
+                .step_by(2)
+                .take(number(2, Some(16))?)
+            {
+                let opcode = u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+                let marker = if addr == cpu.pc as usize { ">" } else { " " };
+                out += &format!(
+                    "{} {:03X}: {:04X}  {}\n",
+                    marker,
+                    addr,
+                    opcode,
+                    disasm::disassemble(opcode)
+                );
+            }
+            out
+        }
+        Some("display") => machine.cpu.get_display().to_block_art(),
+        Some(command) => return Err(format!("unknown command: {}", command)),
+        None => String::new(),
+    };
+    Ok(response)
+}
+
+fn registers(cpu: &Cpu) -> String {
+    let v: Vec<String> = cpu.registers.iter().map(|r| format!("{:02X}", r)).collect();
+    let stack: Vec<String> = cpu.stack[..cpu.stack_pointer]
+        .iter()
+        .map(|a| format!("{:03X}", a))
+        .collect();
+    format!(
+        "PC={:03X} I={:03X} DT={:02X} ST={:02X} keys={:04X}\nV={}\nstack=[{}]\n",
+        cpu.pc,
+        cpu.index,
+        cpu.delay_timer,
+        cpu.sound_timer,
+        cpu.keys,
+        v.join(" "),
+        stack.join(" ")
+    )
+}
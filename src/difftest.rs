@@ -0,0 +1,128 @@
+use std::fmt;
+
+use crate::cpu::Cpu;
+use crate::disasm;
+use crate::reference::Reference;
+
+/// How a lockstep run against the reference interpreter ended.
+pub enum Outcome {
+    /// Both implementations agreed for every step.
+    Agreed { steps: usize },
+    /// The reference doesn't know the next instruction, so the run stopped.
+    Unsupported { step: usize, pc: u16, opcode: u16 },
+    /// The implementations disagreed after executing an instruction.
+    Mismatch {
+        step: usize,
+        pc: u16,
+        opcode: u16,
+        differences: Vec<String>,
+    },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Agreed { steps } => write!(f, "no mismatches in {} steps", steps),
+            Outcome::Unsupported { step, pc, opcode } => write!(
+                f,
+                "stopped at step {}: reference does not support {:04X} at {:#05x}",
+                step, opcode, pc
+            ),
+            Outcome::Mismatch {
+                step,
+                pc,
+                opcode,
+                differences,
+            } => {
+                writeln!(
+                    f,
+                    "mismatch at step {} after {:04X} ({}) at {:#05x}:",
+                    step,
+                    opcode,
+                    disasm::disassemble(*opcode),
+                    pc
+                )?;
+                for difference in differences {
+                    writeln!(f, "  {}", difference)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Runs `rom` on both the core and the reference interpreter for up to
+/// `steps` instructions, comparing machine state after each one.
+pub fn run(rom: &[u8], steps: usize) -> Outcome {
+    let mut cpu = Cpu::new();
+    cpu.load_rom(rom);
+    let mut reference = Reference::new(rom);
+
+    for step in 0..steps {
+        let pc = reference.pc;
+        let opcode = reference.opcode();
+        if reference.step().is_err() {
+            return Outcome::Unsupported { step, pc, opcode };
+        }
+        let differences = match cpu.tick() {
+            Ok(_) => compare(&cpu, &reference),
+            Err(error) => vec![format!("core: {}", error)],
+        };
+        if !differences.is_empty() {
+            return Outcome::Mismatch {
+                step,
+                pc,
+                opcode,
+                differences,
+            };
+        }
+    }
+    Outcome::Agreed { steps }
+}
+
+fn compare(cpu: &Cpu, reference: &Reference) -> Vec<String> {
+    let mut differences = Vec::new();
+    if cpu.pc != reference.pc {
+        differences.push(format!(
+            "PC: core {:#05x}, reference {:#05x}",
+            cpu.pc, reference.pc
+        ));
+    }
+    if cpu.index != reference.index {
+        differences.push(format!(
+            "I: core {:#05x}, reference {:#05x}",
+            cpu.index, reference.index
+        ));
+    }
+    for (i, (a, b)) in cpu.registers.iter().zip(reference.registers).enumerate() {
+        if *a != b {
+            differences.push(format!("V{:X}: core {:#04x}, reference {:#04x}", i, a, b));
+        }
+    }
+    if cpu.delay_timer != reference.delay_timer {
+        differences.push(format!(
+            "DT: core {}, reference {}",
+            cpu.delay_timer, reference.delay_timer
+        ));
+    }
+    if cpu.sound_timer != reference.sound_timer {
+        differences.push(format!(
+            "ST: core {}, reference {}",
+            cpu.sound_timer, reference.sound_timer
+        ));
+    }
+    if cpu.stack[..cpu.stack_pointer] != reference.stack[..] {
+        differences.push(format!(
+            "stack: core {:04X?}, reference {:04X?}",
+            &cpu.stack[..cpu.stack_pointer],
+            reference.stack
+        ));
+    }
+    if cpu.memory()[..reference.memory.len()] != reference.memory[..] {
+        differences.push("memory contents differ".to_string());
+    }
+    if *cpu.get_display() != reference.display {
+        differences.push("display contents differ".to_string());
+    }
+    differences
+}
@@ -0,0 +1,178 @@
+/// Reference entry describing one CHIP-8 instruction.
+pub struct OpcodeInfo {
+    mask: u16,
+    value: u16,
+    pub pattern: &'static str,
+    pub description: &'static str,
+    pub affects: &'static str,
+    pub quirks: Option<&'static str>,
+}
+
+const fn op(
+    mask: u16,
+    value: u16,
+    pattern: &'static str,
+    description: &'static str,
+    affects: &'static str,
+    quirks: Option<&'static str>,
+) -> OpcodeInfo {
+    OpcodeInfo {
+        mask,
+        value,
+        pattern,
+        description,
+        affects,
+        quirks,
+    }
+}
+
+#[rustfmt::skip]
+pub const REFERENCE: &[OpcodeInfo] = &[
+    op(0xFFFF, 0x0010, "0010", "Leave MegaChip mode for 64x32 low resolution.", "display", Some("MegaChip only; elsewhere a machine code call. Clears the display.")),
+    op(0xFFFF, 0x0011, "0011", "Switch to MegaChip's 256x192 color display.", "display", Some("MegaChip only; elsewhere a machine code call. Clears the display.")),
+    op(0xFFF0, 0x00B0, "00BN", "Scroll the display up N pixels.", "display", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFFF0, 0x00C0, "00CN", "Scroll the display down N pixels.", "display", Some("SCHIP only.")),
+    op(0xFFF0, 0x00D0, "00DN", "Scroll the display up N pixels.", "display", Some("XO-CHIP only.")),
+    op(0xFFFF, 0x00E0, "00E0", "Clear the display.", "display", None),
+    op(0xFFFF, 0x00EE, "00EE", "Return from a subroutine.", "PC, SP", None),
+    op(0xFFFF, 0x00FB, "00FB", "Scroll the display right 4 pixels.", "display", Some("SCHIP only.")),
+    op(0xFFFF, 0x00FC, "00FC", "Scroll the display left 4 pixels.", "display", Some("SCHIP only.")),
+    op(0xFFFF, 0x00FD, "00FD", "Exit the interpreter.", "-", Some("SCHIP only.")),
+    op(0xFFFF, 0x00FE, "00FE", "Switch to 64x32 low resolution.", "display", Some("SCHIP only. Clears the display.")),
+    op(0xFFFF, 0x00FF, "00FF", "Switch to 128x64 high resolution.", "display", Some("SCHIP only. Clears the display.")),
+    op(0xFF00, 0x0100, "01NN", "Set I to the 24-bit address NN in this opcode and NNNN in the next two bytes.", "I", Some("MegaChip only; elsewhere a machine code call. Four bytes long.")),
+    op(0xFF00, 0x0200, "02NN", "Load NN palette colors, four ARGB bytes each, from I into colors 1 and up.", "palette", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFF00, 0x0300, "03NN", "Set the width of color sprites to NN (0 is 256).", "sprite size", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFF00, 0x0400, "04NN", "Set the height of color sprites to NN (0 is 256).", "sprite size", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFF00, 0x0500, "05NN", "Set the opacity of the whole display to NN.", "display", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFFF0, 0x0600, "060N", "Play the digitized sound at I, looping if N is 0.", "-", Some("MegaChip only; elsewhere a machine code call. Not supported here.")),
+    op(0xFFFF, 0x0700, "0700", "Stop the digitized sound.", "-", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xFFF0, 0x0800, "080N", "Set how sprites blend with the display: normal, 25%, 50%, add or multiply.", "-", Some("MegaChip only; elsewhere a machine code call. Only normal blending is supported here.")),
+    op(0xFF00, 0x0900, "09NN", "Set the color index sprites collide with to NN.", "-", Some("MegaChip only; elsewhere a machine code call.")),
+    op(0xF000, 0x0000, "0NNN", "Call machine code routine at NNN.", "-", Some("Only meaningful on the original COSMAC VIP; modern interpreters ignore it.")),
+    op(0xF000, 0x1000, "1NNN", "Jump to address NNN.", "PC", None),
+    op(0xF000, 0x2000, "2NNN", "Call subroutine at NNN.", "PC, SP, stack", None),
+    op(0xF000, 0x3000, "3XNN", "Skip the next instruction if VX == NN.", "PC", None),
+    op(0xF000, 0x4000, "4XNN", "Skip the next instruction if VX != NN.", "PC", None),
+    op(0xF00F, 0x5000, "5XY0", "Skip the next instruction if VX == VY.", "PC", None),
+    op(0xF00F, 0x5002, "5XY2", "Store VX..=VY in memory starting at I.", "memory", Some("XO-CHIP only; I is left unchanged, and X > Y stores the range in reverse.")),
+    op(0xF00F, 0x5003, "5XY3", "Load VX..=VY from memory starting at I.", "VX..VY", Some("XO-CHIP only; I is left unchanged, and X > Y loads the range in reverse.")),
+    op(0xF000, 0x6000, "6XNN", "Set VX to NN.", "VX", None),
+    op(0xF000, 0x7000, "7XNN", "Add NN to VX without carry.", "VX", None),
+    op(0xF00F, 0x8000, "8XY0", "Set VX to VY.", "VX", None),
+    op(0xF00F, 0x8001, "8XY1", "Set VX to VX OR VY.", "VX", Some("COSMAC VIP also resets VF to 0.")),
+    op(0xF00F, 0x8002, "8XY2", "Set VX to VX AND VY.", "VX", Some("COSMAC VIP also resets VF to 0.")),
+    op(0xF00F, 0x8003, "8XY3", "Set VX to VX XOR VY.", "VX", Some("COSMAC VIP also resets VF to 0.")),
+    op(0xF00F, 0x8004, "8XY4", "Add VY to VX; VF is set to the carry.", "VX, VF", None),
+    op(0xF00F, 0x8005, "8XY5", "Subtract VY from VX; VF is set to NOT borrow.", "VX, VF", None),
+    op(0xF00F, 0x8006, "8XY6", "Shift right by one; VF is set to the bit shifted out.", "VX, VF", Some("COSMAC VIP shifts VY into VX; SCHIP shifts VX in place.")),
+    op(0xF00F, 0x8007, "8XY7", "Set VX to VY - VX; VF is set to NOT borrow.", "VX, VF", None),
+    op(0xF00F, 0x800E, "8XYE", "Shift left by one; VF is set to the bit shifted out.", "VX, VF", Some("COSMAC VIP shifts VY into VX; SCHIP shifts VX in place.")),
+    op(0xF00F, 0x9000, "9XY0", "Skip the next instruction if VX != VY.", "PC", None),
+    op(0xF000, 0xA000, "ANNN", "Set I to NNN.", "I", None),
+    op(0xF000, 0xB000, "BNNN", "Jump to NNN + V0.", "PC", Some("CHIP-48/SCHIP jump to XNN + VX instead.")),
+    op(0xF000, 0xC000, "CXNN", "Set VX to a random byte AND NN.", "VX", None),
+    op(0xF00F, 0xD000, "DXY0", "Draw a 16x16 sprite from I at (VX, VY); VF counts the rows that collided.", "display, VF", Some("SCHIP high resolution only; 32 bytes, two per row.")),
+    op(0xF000, 0xD000, "DXYN", "Draw an N-row sprite from I at (VX, VY); VF is set on collision.", "display, VF", Some("Sprites wrap or clip at the screen edge depending on the platform; the VIP waits for vblank.")),
+    op(0xF0FF, 0xE09E, "EX9E", "Skip the next instruction if the key in VX is pressed.", "PC", None),
+    op(0xF0FF, 0xE0A1, "EXA1", "Skip the next instruction if the key in VX is not pressed.", "PC", None),
+    op(0xF0FF, 0xF007, "FX07", "Set VX to the delay timer.", "VX", None),
+    op(0xFFFF, 0xF000, "F000", "Set I to the 16-bit address NNNN in the next two bytes.", "I", Some("XO-CHIP only; the instruction is four bytes long and skips step over all of it.")),
+    op(0xF0FF, 0xF001, "FN01", "Select the display planes drawing, clearing and scrolling act on; N is a plane mask.", "planes", Some("XO-CHIP only.")),
+    op(0xFFFF, 0xF002, "F002", "Load the 16-byte audio pattern from memory starting at I.", "audio", Some("XO-CHIP only; 128 one-bit samples played while the sound timer runs.")),
+    op(0xF0FF, 0xF00A, "FX0A", "Wait for a key press and release, then store it in VX.", "VX", None),
+    op(0xF0FF, 0xF015, "FX15", "Set the delay timer to VX.", "DT", None),
+    op(0xF0FF, 0xF018, "FX18", "Set the sound timer to VX.", "ST", None),
+    op(0xF0FF, 0xF01E, "FX1E", "Add VX to I.", "I", Some("Amiga interpreters set VF when I overflows past 0xFFF.")),
+    op(0xF0FF, 0xF029, "FX29", "Set I to the font glyph for the low nibble of VX.", "I", None),
+    op(0xF0FF, 0xF030, "FX30", "Set I to the large font glyph for the low nibble of VX.", "I", Some("SCHIP only; glyphs are 8x10.")),
+    op(0xF0FF, 0xF03A, "FX3A", "Set the audio pitch to VX.", "pitch", Some("XO-CHIP only; the pattern plays at 4000 * 2^((VX - 64) / 48) samples per second.")),
+    op(0xF0FF, 0xF033, "FX33", "Store the decimal digits of VX at I, I+1 and I+2.", "memory", None),
+    op(0xF0FF, 0xF055, "FX55", "Store V0..=VX in memory starting at I.", "memory", Some("COSMAC VIP increments I by X + 1; SCHIP leaves I unchanged.")),
+    op(0xF0FF, 0xF065, "FX65", "Load V0..=VX from memory starting at I.", "V0..VX", Some("COSMAC VIP increments I by X + 1; SCHIP leaves I unchanged.")),
+    op(0xF0FF, 0xF075, "FX75", "Save V0..=VX to the RPL user flags.", "flags", Some("SCHIP only; X is at most 7. The flags are kept across runs.")),
+    op(0xF0FF, 0xF085, "FX85", "Load V0..=VX from the RPL user flags.", "V0..VX", Some("SCHIP only; X is at most 7.")),
+];
+
+/// Finds the reference entry for `opcode`, if it is a known instruction.
+pub fn lookup(opcode: u16) -> Option<&'static OpcodeInfo> {
+    REFERENCE
+        .iter()
+        .find(|info| opcode & info.mask == info.value)
+}
+
+/// Renders `opcode` as an assembly mnemonic.
+pub fn disassemble(opcode: u16) -> String {
+    let x = (opcode & 0x0F00) >> 8;
+    let y = (opcode & 0x00F0) >> 4;
+    let n = opcode & 0x000F;
+    let nn = opcode & 0x00FF;
+    let nnn = opcode & 0x0FFF;
+
+    match (opcode >> 12, x, y, n) {
+        (0x0, 0x0, 0x1, 0x0) => "MEGAOFF".to_string(),
+        (0x0, 0x0, 0x1, 0x1) => "MEGAON".to_string(),
+        (0x0, 0x0, 0xB, _) => format!("SCRU {}", n),
+        (0x0, 0x0, 0xC, _) => format!("SCD {}", n),
+        (0x0, 0x0, 0xD, _) => format!("SCU {}", n),
+        (0x0, 0x0, 0xE, 0x0) => "CLS".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => "RET".to_string(),
+        (0x0, 0x0, 0xF, 0xB) => "SCR".to_string(),
+        (0x0, 0x0, 0xF, 0xC) => "SCL".to_string(),
+        (0x0, 0x0, 0xF, 0xD) => "EXIT".to_string(),
+        (0x0, 0x0, 0xF, 0xE) => "LOW".to_string(),
+        (0x0, 0x0, 0xF, 0xF) => "HIGH".to_string(),
+        (0x0, 0x1, ..) => format!("LDHI I, {:#04x}....", nn),
+        (0x0, 0x2, ..) => format!("LDPAL {}", nn),
+        (0x0, 0x3, ..) => format!("SPRW {}", nn),
+        (0x0, 0x4, ..) => format!("SPRH {}", nn),
+        (0x0, 0x5, ..) => format!("ALPHA {:#04x}", nn),
+        (0x0, 0x6, 0x0, _) => format!("DIGISND {}", n),
+        (0x0, 0x7, 0x0, 0x0) => "STOPSND".to_string(),
+        (0x0, 0x8, 0x0, _) => format!("BMODE {}", n),
+        (0x0, 0x9, ..) => format!("CCOL {:#04x}", nn),
+        (0x0, ..) => format!("SYS {:#05x}", nnn),
+        (0x1, ..) => format!("JP {:#05x}", nnn),
+        (0x2, ..) => format!("CALL {:#05x}", nnn),
+        (0x3, ..) => format!("SE V{:X}, {:#04x}", x, nn),
+        (0x4, ..) => format!("SNE V{:X}, {:#04x}", x, nn),
+        (0x5, _, _, 0x0) => format!("SE V{:X}, V{:X}", x, y),
+        (0x5, _, _, 0x2) => format!("LD [I], V{:X}-V{:X}", x, y),
+        (0x5, _, _, 0x3) => format!("LD V{:X}-V{:X}, [I]", x, y),
+        (0x6, ..) => format!("LD V{:X}, {:#04x}", x, nn),
+        (0x7, ..) => format!("ADD V{:X}, {:#04x}", x, nn),
+        (0x8, _, _, 0x0) => format!("LD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x1) => format!("OR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x2) => format!("AND V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x3) => format!("XOR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x4) => format!("ADD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x5) => format!("SUB V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x6) => format!("SHR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x7) => format!("SUBN V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0xE) => format!("SHL V{:X}, V{:X}", x, y),
+        (0x9, _, _, 0x0) => format!("SNE V{:X}, V{:X}", x, y),
+        (0xA, ..) => format!("LD I, {:#05x}", nnn),
+        (0xB, ..) => format!("JP V0, {:#05x}", nnn),
+        (0xC, ..) => format!("RND V{:X}, {:#04x}", x, nn),
+        (0xD, ..) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0xE, _, 0x9, 0xE) => format!("SKP V{:X}", x),
+        (0xE, _, 0xA, 0x1) => format!("SKNP V{:X}", x),
+        (0xF, _, 0x0, 0x7) => format!("LD V{:X}, DT", x),
+        (0xF, 0x0, 0x0, 0x0) => "LD I, LONG".to_string(),
+        (0xF, _, 0x0, 0x1) => format!("PLANE {}", x),
+        (0xF, 0x0, 0x0, 0x2) => "AUDIO".to_string(),
+        (0xF, _, 0x0, 0xA) => format!("LD V{:X}, K", x),
+        (0xF, _, 0x1, 0x5) => format!("LD DT, V{:X}", x),
+        (0xF, _, 0x1, 0x8) => format!("LD ST, V{:X}", x),
+        (0xF, _, 0x1, 0xE) => format!("ADD I, V{:X}", x),
+        (0xF, _, 0x2, 0x9) => format!("LD F, V{:X}", x),
+        (0xF, _, 0x3, 0x0) => format!("LD HF, V{:X}", x),
+        (0xF, _, 0x3, 0xA) => format!("PITCH V{:X}", x),
+        (0xF, _, 0x3, 0x3) => format!("LD B, V{:X}", x),
+        (0xF, _, 0x5, 0x5) => format!("LD [I], V{:X}", x),
+        (0xF, _, 0x6, 0x5) => format!("LD V{:X}, [I]", x),
+        (0xF, _, 0x7, 0x5) => format!("LD R, V{:X}", x),
+        (0xF, _, 0x8, 0x5) => format!("LD V{:X}, R", x),
+        _ => format!("DW {:#06x}", opcode),
+    }
+}
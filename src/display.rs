@@ -1,22 +1,396 @@
+use crate::palette::{Color, Palette};
+
+/// The resolutions programs can switch the screen between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// CHIP-8's 64x32.
+    Low,
+    /// The two-page hi-res CHIP-8's 64x64.
+    Tall,
+    /// SUPER-CHIP's 128x64.
+    High,
+    /// MegaChip's 256x192, with a palette index per pixel.
+    Mega,
+}
+
+impl Resolution {
+    /// Width and height in pixels.
+    pub fn size(self) -> (usize, usize) {
+        match self {
+            Resolution::Low => (Display::WIDTH, Display::HEIGHT),
+            Resolution::Tall => (Display::WIDTH, Display::TALL_HEIGHT),
+            Resolution::High => (Display::HIRES_WIDTH, Display::HIRES_HEIGHT),
+            Resolution::Mega => (Display::MEGA_WIDTH, Display::MEGA_HEIGHT),
+        }
+    }
+}
+
+/// The screen, in any of the [`Resolution`]s. Each plane holds one row per
+/// line of the current resolution; CHIP-8 and SUPER-CHIP programs only draw
+/// to the first, XO-CHIP programs can draw to both and get four colors.
+/// MegaChip programs draw in color to [`Display::mega`] instead.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Display {
-    pub pixels: [[bool; Self::WIDTH]; Self::HEIGHT],
+    pub planes: [Vec<Vec<bool>>; Self::PLANES],
+    /// The color screen, while in MegaChip mode.
+    pub mega: Option<Box<MegaScreen>>,
+}
+
+/// MegaChip's screen: a palette index per pixel and the colors the program
+/// loaded for them.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MegaScreen {
+    /// Palette indices, one row per line; 0 is the background.
+    pub pixels: Vec<Vec<u8>>,
+    /// Colors by index. Until the program loads its own, every index but
+    /// the background is white.
+    pub palette: Vec<Color>,
+    /// Opacity of the whole screen over black, out of 0xFF.
+    pub alpha: u8,
+}
+
+impl MegaScreen {
+    pub const COLORS: usize = 256;
+
+    /// Size in bytes of [`MegaScreen::to_bytes`].
+    pub const BYTES: usize = Display::MEGA_WIDTH * Display::MEGA_HEIGHT + Self::COLORS * 4 + 1;
+
+    fn new() -> Self {
+        let mut palette = vec![[0xFF; 4]; Self::COLORS];
+        palette[0] = [0x00, 0x00, 0x00, 0xFF];
+        Self {
+            pixels: vec![vec![0; Display::MEGA_WIDTH]; Display::MEGA_HEIGHT],
+            palette,
+            alpha: 0xFF,
+        }
+    }
+
+    /// The pixels row by row, then the palette as RGBA, then the alpha.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.pixels.concat();
+        bytes.extend(self.palette.concat());
+        bytes.push(self.alpha);
+        bytes
+    }
+
+    /// The inverse of [`MegaScreen::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let (pixels, rest) = bytes.split_at(Display::MEGA_WIDTH * Display::MEGA_HEIGHT);
+        let (palette, alpha) = rest.split_at(Self::COLORS * 4);
+        Self {
+            pixels: pixels
+                .chunks(Display::MEGA_WIDTH)
+                .map(<[u8]>::to_vec)
+                .collect(),
+            palette: palette
+                .chunks(4)
+                .map(|c| [c[0], c[1], c[2], c[3]])
+                .collect(),
+            alpha: alpha[0],
+        }
+    }
 }
 
 impl Display {
     pub const WIDTH: usize = 64;
     pub const HEIGHT: usize = 32;
+    pub const TALL_HEIGHT: usize = 64;
+    pub const HIRES_WIDTH: usize = 128;
+    pub const HIRES_HEIGHT: usize = 64;
+    pub const MEGA_WIDTH: usize = 256;
+    pub const MEGA_HEIGHT: usize = 192;
+    pub const PLANES: usize = 2;
 
+    /// A blank display in the standard resolution.
     pub fn new() -> Self {
+        Self::blank(Resolution::Low)
+    }
+
+    fn blank(resolution: Resolution) -> Self {
+        let (width, height) = resolution.size();
+        let plane = vec![vec![false; width]; height];
         Self {
-            pixels: [[false; Self::WIDTH]; Self::HEIGHT],
+            planes: [plane.clone(), plane],
+            mega: (resolution == Resolution::Mega).then(|| Box::new(MegaScreen::new())),
         }
     }
 
-    pub fn clear(&mut self) {
-        for row in self.pixels.iter_mut() {
-            for pixel in row.iter_mut() {
-                *pixel = false;
+    pub fn width(&self) -> usize {
+        self.planes[0][0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.planes[0].len()
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        match (&self.mega, self.width(), self.height()) {
+            (Some(_), ..) => Resolution::Mega,
+            (None, Self::HIRES_WIDTH, _) => Resolution::High,
+            (None, _, Self::TALL_HEIGHT) => Resolution::Tall,
+            (None, ..) => Resolution::Low,
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.resolution() == Resolution::High
+    }
+
+    /// Switches resolution, which like SUPER-CHIP clears the screen.
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        *self = Self::blank(resolution);
+    }
+
+    /// The planes selected by `mask`, bit N for plane N.
+    fn selected(&mut self, mask: u8) -> impl Iterator<Item = &mut Vec<Vec<bool>>> {
+        self.planes
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| mask & (1 << i) != 0)
+            .map(|(_, plane)| plane)
+    }
+
+    /// The pixel at (`x`, `y`) as a palette index: bit N is set if it is
+    /// lit in plane N, or in MegaChip mode the index the program drew.
+    pub fn color(&self, x: usize, y: usize) -> usize {
+        if let Some(mega) = &self.mega {
+            return mega.pixels[y][x] as usize;
+        }
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |color, (i, plane)| color | (plane[y][x] as usize) << i)
+    }
+
+    /// Returns whether the pixel at (`x`, `y`) is lit in any plane, or
+    /// `None` if the coordinates are off screen at the current resolution.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<bool> {
+        (x < self.width() && y < self.height()).then(|| self.color(x, y) != 0)
+    }
+
+    /// The colors to draw [`Display::color`]s with: `palette`'s, or the
+    /// program's own in MegaChip mode, faded by its screen alpha.
+    pub fn colors(&self, palette: &Palette) -> Vec<Color> {
+        match &self.mega {
+            Some(mega) => {
+                let fade = |c: u8| (c as u16 * mega.alpha as u16 / 0xFF) as u8;
+                mega.palette
+                    .iter()
+                    .map(|&[r, g, b, a]| [fade(r), fade(g), fade(b), a])
+                    .collect()
             }
+            None => palette.colors.to_vec(),
         }
     }
+
+    /// The color at (`x`, `y`) when the display is scaled up to `width`
+    /// columns, e.g. to show a low-resolution display at high resolution.
+    pub fn scaled_color(&self, x: usize, y: usize, width: usize) -> usize {
+        let scale = width / self.width();
+        self.color(x / scale, y / scale)
+    }
+
+    /// Iterates over the rows of the display from top to bottom, each pixel
+    /// given as its composited [`Display::color`] index rather than the
+    /// first plane alone, so XO-CHIP and MegaChip pictures come through
+    /// whole.
+    pub fn rows(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        (0..self.height()).map(move |y| (0..self.width()).map(|x| self.color(x, y)).collect())
+    }
+
+    /// How many pixels are lit in any plane.
+    pub fn lit_pixels(&self) -> usize {
+        self.rows().flatten().filter(|&color| color != 0).count()
+    }
+
+    fn pack(plane: &[Vec<bool>]) -> Vec<u8> {
+        plane
+            .iter()
+            .flat_map(|row| {
+                row.chunks(8).map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u8, |byte, (i, &on)| byte | ((on as u8) << (7 - i)))
+                })
+            })
+            .collect()
+    }
+
+    fn unpack(plane: &mut [Vec<bool>], bytes: &[u8]) {
+        for (y, row) in plane.iter_mut().enumerate() {
+            let width = row.len();
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let byte = bytes[(y * width + x) / 8];
+                *pixel = byte & (0x80 >> (x % 8)) != 0;
+            }
+        }
+    }
+
+    /// The pixels that aren't background, packed MSB-first into bytes, row
+    /// by row.
+    pub fn packed(&self) -> Vec<u8> {
+        let lit: Vec<Vec<bool>> = self
+            .rows()
+            .map(|row| row.into_iter().map(|color| color != 0).collect())
+            .collect();
+        Self::pack(&lit)
+    }
+
+    /// Every plane packed like [`Display::packed`], one after the other.
+    pub fn packed_planes(&self) -> Vec<u8> {
+        self.planes
+            .iter()
+            .flat_map(|plane| Self::pack(plane))
+            .collect()
+    }
+
+    /// Size in bytes of [`Display::packed_planes`] in the given resolution.
+    pub fn packed_planes_len(resolution: Resolution) -> usize {
+        let (width, height) = resolution.size();
+        width * height / 8 * Self::PLANES
+    }
+
+    /// The inverse of [`Display::packed_planes`]. In MegaChip mode the color
+    /// screen starts out blank.
+    pub fn from_packed_planes(resolution: Resolution, bytes: &[u8]) -> Self {
+        let mut display = Self::blank(resolution);
+        let plane_len = Self::packed_planes_len(resolution) / Self::PLANES;
+        for (plane, bytes) in display.planes.iter_mut().zip(bytes.chunks(plane_len)) {
+            Self::unpack(plane, bytes);
+        }
+        display
+    }
+
+    /// A 64-bit FNV-1a hash of the display contents.
+    ///
+    /// The width and height are hashed as little-endian `u32`s followed by the
+    /// first plane packed MSB-first into bytes, row by row, and then the
+    /// second plane the same way if any of its pixels are lit. In MegaChip
+    /// mode the color screen's palette indices follow, a byte per pixel, row
+    /// by row. This encoding is part of the public contract so hashes stay
+    /// comparable across versions.
+    pub fn hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET;
+        let mut feed = |byte: u8| {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        };
+        for dimension in [self.width() as u32, self.height() as u32] {
+            dimension.to_le_bytes().into_iter().for_each(&mut feed);
+        }
+        Self::pack(&self.planes[0]).into_iter().for_each(&mut feed);
+        if self.planes[1].iter().flatten().any(|&on| on) {
+            Self::pack(&self.planes[1]).into_iter().for_each(&mut feed);
+        }
+        if let Some(mega) = &self.mega {
+            mega.pixels.iter().flatten().copied().for_each(feed);
+        }
+        hash
+    }
+
+    /// Renders the display as Unicode block art, two pixel rows per line.
+    /// Pixels lit in any plane are drawn.
+    pub fn to_block_art(&self) -> String {
+        let mut art = String::with_capacity((self.width() + 1) * self.height() / 2 * 3);
+        for y in (0..self.height()).step_by(2) {
+            for x in 0..self.width() {
+                let top = self.get_pixel(x, y) == Some(true);
+                let bottom = self.get_pixel(x, y + 1) == Some(true);
+                art.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            art.push('\n');
+        }
+        art
+    }
+
+    /// Moves the picture in the planes selected by `mask` down `n` rows,
+    /// blanking the rows it uncovers. The color screen always moves.
+    pub fn scroll_down(&mut self, mask: u8, n: usize) {
+        let n = n.min(self.height());
+        self.selected(mask).for_each(|plane| rows_down(plane, n));
+        if let Some(mega) = &mut self.mega {
+            rows_down(&mut mega.pixels, n);
+        }
+    }
+
+    /// Moves the picture in the planes selected by `mask` up `n` rows,
+    /// blanking the rows it uncovers. The color screen always moves.
+    pub fn scroll_up(&mut self, mask: u8, n: usize) {
+        let n = n.min(self.height());
+        self.selected(mask).for_each(|plane| rows_up(plane, n));
+        if let Some(mega) = &mut self.mega {
+            rows_up(&mut mega.pixels, n);
+        }
+    }
+
+    /// Moves the picture in the planes selected by `mask` right `n`
+    /// columns, blanking the columns it uncovers. The color screen always
+    /// moves.
+    pub fn scroll_right(&mut self, mask: u8, n: usize) {
+        let n = n.min(self.width());
+        self.selected(mask)
+            .for_each(|plane| columns_right(plane, n));
+        if let Some(mega) = &mut self.mega {
+            columns_right(&mut mega.pixels, n);
+        }
+    }
+
+    /// Moves the picture in the planes selected by `mask` left `n` columns,
+    /// blanking the columns it uncovers. The color screen always moves.
+    pub fn scroll_left(&mut self, mask: u8, n: usize) {
+        let n = n.min(self.width());
+        self.selected(mask).for_each(|plane| columns_left(plane, n));
+        if let Some(mega) = &mut self.mega {
+            columns_left(&mut mega.pixels, n);
+        }
+    }
+
+    /// Blanks the planes selected by `mask`, and the color screen.
+    pub fn clear(&mut self, mask: u8) {
+        for row in self.selected(mask).flatten() {
+            row.fill(false);
+        }
+        if let Some(mega) = &mut self.mega {
+            mega.pixels.iter_mut().for_each(|row| row.fill(0));
+        }
+    }
+}
+
+fn rows_down<T: Clone + Default>(grid: &mut [Vec<T>], n: usize) {
+    grid.rotate_right(n);
+    for row in &mut grid[..n] {
+        row.fill(T::default());
+    }
+}
+
+fn rows_up<T: Clone + Default>(grid: &mut [Vec<T>], n: usize) {
+    grid.rotate_left(n);
+    let height = grid.len();
+    for row in &mut grid[height - n..] {
+        row.fill(T::default());
+    }
+}
+
+fn columns_right<T: Clone + Default>(grid: &mut [Vec<T>], n: usize) {
+    for row in grid {
+        row.rotate_right(n);
+        row[..n].fill(T::default());
+    }
+}
+
+fn columns_left<T: Clone + Default>(grid: &mut [Vec<T>], n: usize) {
+    for row in grid {
+        row.rotate_left(n);
+        let width = row.len();
+        row[width - n..].fill(T::default());
+    }
 }
@@ -0,0 +1,328 @@
+//! Runs the core on a thread of its own, so window resizes, GUI work and
+//! slow redraws on the event loop don't hold emulation back. The event loop
+//! and the emulation thread share a [`Machine`] behind a mutex.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+
+use crate::chaos::Chaos;
+use crate::compare::Comparison;
+use crate::condition::Condition;
+use crate::cpu::{Cpu, CpuError, CpuEvent, RPL_FLAGS};
+use crate::explain::{Narrator, Snapshot};
+use crate::profiler::Profiler;
+use crate::rawdump::RawDump;
+use crate::recording::Recorder;
+use crate::scheduler::Scheduler;
+use crate::trace::{Fault, Trace};
+use crate::watchpoint::{WatchHit, Watchpoint};
+use crate::PauseReason;
+
+/// How long the emulation thread sleeps between batches of instructions.
+const BATCH_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How far to run when stepping through a paused program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Exactly one instruction.
+    Instruction,
+    /// Up to and including the instruction on which the timers next count
+    /// down.
+    Frame,
+    /// This many instructions.
+    Instructions(u32),
+}
+
+/// The core and everything that follows it instruction by instruction.
+pub struct Machine {
+    pub cpu: Cpu,
+    pub comparison: Option<Comparison>,
+    pub paused: Option<PauseReason>,
+    pub fault: Option<Fault>,
+    pub trace: Trace,
+    /// Addresses at which execution pauses.
+    pub breakpoints: Vec<u16>,
+    /// Conditions that pause execution as they come true.
+    pub conditions: Vec<Condition>,
+    /// Memory ranges whose reads or writes pause execution.
+    pub watchpoints: Vec<Watchpoint>,
+    /// The access that last stopped execution at a watchpoint.
+    pub watch_hit: Option<WatchHit>,
+    pub narrator: Narrator,
+    /// Whether the narrator follows along, i.e. the Explain window is open.
+    pub explain: bool,
+    /// Shared with views until the next instruction is recorded.
+    pub profiler: Option<Arc<Profiler>>,
+    pub recorder: Option<Recorder>,
+    pub raw_dump: Option<RawDump>,
+    pub chaos: Option<Chaos>,
+    /// Whether the program may run at all: not while the boot splash shows
+    /// or the tutorial steps through it.
+    pub running: bool,
+    /// Multiplier on the instruction rate: above 1 while fast-forwarding,
+    /// below 1 in slow motion.
+    pub speed: f32,
+    /// Set to run a step while paused.
+    pub step: Option<Step>,
+    /// Set while paused, so the first instruction after resuming or
+    /// stepping runs even if there's a breakpoint on it.
+    resuming: bool,
+    /// Emulated time let go after stalls too long to catch up on, since the
+    /// event loop last took it.
+    pub skipped: Duration,
+    /// Instructions run since the event loop last took the count.
+    pub executed: u32,
+    /// Set when execution pauses on a fault, until the event loop shows it.
+    pub faulted: bool,
+    /// Set when execution pauses at a breakpoint, on a condition or at a
+    /// watchpoint, until the event loop shows it.
+    pub hit_breakpoint: bool,
+    /// RPL flags the program saved, until the event loop persists them.
+    pub saved_flags: Option<[u8; RPL_FLAGS]>,
+    /// Set when the program exits with 00FD.
+    pub exited: bool,
+}
+
+/// A copy of what the window and the GUI show of a [`Machine`], taken so
+/// drawing doesn't hold the lock while the emulation thread waits.
+pub struct MachineView {
+    /// The CPU, sharing its heatmap only while the Memory window shows it.
+    pub cpu: Cpu,
+    pub comparison: Option<Comparison>,
+    pub paused: Option<PauseReason>,
+    pub fault: Option<Fault>,
+    pub trace: Trace,
+    pub breakpoints: Vec<u16>,
+    pub conditions: Vec<Condition>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub watch_hit: Option<WatchHit>,
+    /// Only copied while the Explain window is open.
+    pub narrator: Option<Narrator>,
+    pub profiler: Option<Arc<Profiler>>,
+    pub speed: f32,
+}
+
+impl Machine {
+    pub fn new(cpu: Cpu) -> Self {
+        Self {
+            cpu,
+            comparison: None,
+            paused: None,
+            fault: None,
+            trace: Trace::default(),
+            breakpoints: Vec::new(),
+            conditions: Vec::new(),
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            narrator: Narrator::new(),
+            explain: false,
+            profiler: None,
+            recorder: None,
+            raw_dump: None,
+            chaos: None,
+            running: false,
+            speed: 1.0,
+            step: None,
+            resuming: false,
+            skipped: Duration::ZERO,
+            executed: 0,
+            faulted: false,
+            hit_breakpoint: false,
+            saved_flags: None,
+            exited: false,
+        }
+    }
+
+    /// Copies what the GUI shows. The heatmap and profiler are shared
+    /// rather than copied, and the heatmap only while `heatmap` says the
+    /// Memory window needs it: the emulation thread copies a shared one
+    /// before it next records to it.
+    pub fn view(&self, heatmap: bool) -> MachineView {
+        let mut cpu = self.cpu.clone();
+        let mut comparison = self.comparison.clone();
+        if !heatmap {
+            cpu.heatmap = Arc::default();
+        }
+        if let Some(comparison) = &mut comparison {
+            comparison.cpu.heatmap = Arc::default();
+        }
+        MachineView {
+            cpu,
+            comparison,
+            paused: self.paused,
+            fault: self.fault.clone(),
+            trace: self.trace.clone(),
+            breakpoints: self.breakpoints.clone(),
+            conditions: self.conditions.clone(),
+            watchpoints: self.watchpoints.clone(),
+            watch_hit: self.watch_hit,
+            narrator: self.explain.then(|| self.narrator.clone()),
+            profiler: self.profiler.clone(),
+            speed: self.speed,
+        }
+    }
+
+    /// Runs what's due by `now`: a step asked for, then the instructions
+    /// `scheduler` says are owed.
+    pub fn advance(&mut self, scheduler: &mut Scheduler, now: Instant) {
+        self.resuming |= self.paused.is_some();
+        if let Some(step) = self.step.take().filter(|_| self.running) {
+            self.run_step(step);
+        }
+        if !self.running || self.paused.is_some() || self.exited {
+            scheduler.idle(now);
+        } else {
+            let (due, skipped) = scheduler.advance(now, self.speed);
+            self.skipped += skipped;
+            self.run(due);
+        }
+    }
+
+    /// Runs `step` right away rather than on the next advance. Like any step
+    /// it gets past a breakpoint it's paused on.
+    pub fn run_step(&mut self, step: Step) {
+        self.resuming |= self.paused.is_some();
+        let count = match step {
+            Step::Instruction => 1,
+            Step::Frame => self.cpu.instructions_to_next_frame(),
+            Step::Instructions(count) => count,
+        };
+        self.run(count);
+    }
+
+    /// Runs up to `count` instructions, stopping early when the program
+    /// pauses or exits. Breakpoints stop execution before the instruction
+    /// they're on.
+    fn run(&mut self, count: u32) {
+        for _ in 0..count {
+            let resuming = std::mem::take(&mut self.resuming);
+            if !resuming && self.breakpoints.contains(&self.cpu.pc) {
+                self.paused = Some(PauseReason::Breakpoint);
+                self.hit_breakpoint = true;
+                self.resuming = true;
+                return;
+            }
+            if let Some(chaos) = &mut self.chaos {
+                chaos.strike(&mut self.cpu);
+            }
+            let address = self.cpu.pc;
+            let frame = self.cpu.frame();
+            let started = Instant::now();
+            let (cpu, narrator, explain) = (&mut self.cpu, &mut self.narrator, self.explain);
+            let ticked = panic::catch_unwind(AssertUnwindSafe(|| -> Result<_, CpuError> {
+                let opcode = cpu.fetch_opcode();
+                let event = if explain {
+                    let before = Snapshot::take(cpu);
+                    let event = cpu.tick()?;
+                    narrator.record(&before, cpu);
+                    event
+                } else {
+                    cpu.tick()?
+                };
+                Ok((opcode, event))
+            }));
+            let ticked = ticked
+                .map_err(|payload| Fault::new(address, payload))
+                .and_then(|result| result.map_err(Fault::from));
+            let (opcode, event) = match ticked {
+                Ok(ticked) => ticked,
+                Err(fault) => {
+                    error!("Paused on fault at {:#05x}: {}", fault.pc, fault.message);
+                    self.fault = Some(fault);
+                    self.paused = Some(PauseReason::Fault);
+                    self.faulted = true;
+                    return;
+                }
+            };
+            self.trace.record(address, opcode);
+            if let Some(profiler) = &mut self.profiler {
+                Arc::make_mut(profiler).record(address, opcode, started.elapsed());
+            }
+            self.executed += 1;
+            if let Some(comparison) = &mut self.comparison {
+                comparison.step(&self.cpu);
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(self.cpu.get_display());
+            }
+            if let Some(dump) = self.raw_dump.as_mut().filter(|_| self.cpu.frame() != frame) {
+                if let Err(e) = dump.append(self.cpu.get_display()) {
+                    error!("failed to write raw dump: {}", e);
+                    self.raw_dump = None;
+                }
+            }
+            match event {
+                Some(CpuEvent::FlagsSaved) => self.saved_flags = Some(self.cpu.rpl_flags),
+                Some(CpuEvent::Exited) => {
+                    info!("Program exited at {:#05x}", self.cpu.pc);
+                    self.exited = true;
+                    return;
+                }
+                Some(CpuEvent::Halted) => {
+                    info!("Program finished at {:#05x}", self.cpu.pc);
+                    self.paused = Some(PauseReason::Finished);
+                    return;
+                }
+                _ => {}
+            }
+            // Every condition is checked, so each knows whether it held.
+            let cpu = &self.cpu;
+            let fired = self
+                .conditions
+                .iter_mut()
+                .fold(false, |fired, condition| condition.fired(cpu) | fired);
+            if fired {
+                self.paused = Some(PauseReason::Condition);
+                self.hit_breakpoint = true;
+                return;
+            }
+            let accesses = self.cpu.accesses();
+            let hit = self.watchpoints.iter().find_map(|watchpoint| {
+                let access = watchpoint.triggered(accesses)?;
+                Some(WatchHit {
+                    watchpoint: *watchpoint,
+                    access,
+                    pc: address,
+                    opcode,
+                })
+            });
+            if let Some(hit) = hit {
+                info!(
+                    "{:?} of {} by {:04X} at {:#05x}",
+                    hit.access, hit.watchpoint, hit.opcode, hit.pc
+                );
+                self.watch_hit = Some(hit);
+                self.paused = Some(PauseReason::Watchpoint);
+                self.hit_breakpoint = true;
+                return;
+            }
+        }
+    }
+}
+
+/// Starts running `machine` at `instructions_per_second` in the background,
+/// for as long as anyone else holds on to it.
+pub fn spawn(machine: &Arc<Mutex<Machine>>, instructions_per_second: f32) {
+    let machine = Arc::downgrade(machine);
+    thread::spawn(move || emulate(machine, instructions_per_second));
+}
+
+/// A fixed-timestep loop: the [`Scheduler`] works out how many instructions
+/// the wall-clock time since the last batch is worth, and exactly that many
+/// run, however late the thread wakes up. The timers count down every so
+/// many instructions rather than every so much time, so they keep step with
+/// the CPU through dropped frames and hiccups alike.
+fn emulate(machine: Weak<Mutex<Machine>>, instructions_per_second: f32) {
+    let mut scheduler = Scheduler::new(instructions_per_second, Instant::now());
+    while let Some(machine) = machine.upgrade() {
+        machine
+            .lock()
+            .unwrap()
+            .advance(&mut scheduler, Instant::now());
+        thread::sleep(BATCH_INTERVAL);
+    }
+}
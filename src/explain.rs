@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::cpu::Cpu;
+
+/// Number of narrated lines kept for the GUI panel.
+const HISTORY: usize = 256;
+
+/// Maximum number of lines narrated per second; the rest are summarized.
+const MAX_PER_SECOND: usize = 20;
+
+/// CPU state captured right before an instruction executes.
+pub struct Snapshot {
+    pc: u16,
+    opcode: u16,
+    registers: [u8; 16],
+    index: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+}
+
+impl Snapshot {
+    pub fn take(cpu: &Cpu) -> Self {
+        Self {
+            pc: cpu.pc,
+            opcode: cpu.fetch_opcode(),
+            registers: cpu.registers,
+            index: cpu.index,
+            delay_timer: cpu.delay_timer,
+            sound_timer: cpu.sound_timer,
+        }
+    }
+}
+
+/// Describes in plain English what the instruction captured in `before` did,
+/// given the machine state `after` it ran.
+pub fn explain(before: &Snapshot, after: &Cpu) -> String {
+    let opcode = before.opcode;
+    let x = ((opcode & 0x0F00) >> 8) as usize;
+    let y = ((opcode & 0x00F0) >> 4) as usize;
+    let n = opcode & 0x000F;
+    let nn = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+    let vx = before.registers[x];
+    let vy = before.registers[y];
+    let result = after.registers[x];
+    let vf = after.registers[0xF];
+    let skipped = |taken: bool| if taken { "skip next" } else { "don't skip" };
+
+    let megachip = after.is_megachip();
+    let text = match (opcode >> 12, x, y, n) {
+        (0x0, 0x0, 0x1, 0x0) if megachip => {
+            "leave MegaChip mode for low resolution and clear the screen".to_string()
+        }
+        (0x0, 0x0, 0x1, 0x1) if megachip => "switch to the MegaChip color screen".to_string(),
+        (0x0, 0x0, 0xB, _) if megachip => format!("scroll the screen up {} pixels", n),
+        (0x0, 0x1, ..) if megachip => format!("I <- {:#08x}", after.index),
+        (0x0, 0x2, ..) if megachip => {
+            format!("load {} palette colors from {:#05x}", nn, before.index)
+        }
+        (0x0, 0x3, ..) if megachip => format!("sprite width <- {}", after.sprite_size.0),
+        (0x0, 0x4, ..) if megachip => format!("sprite height <- {}", after.sprite_size.1),
+        (0x0, 0x5, ..) if megachip => format!("screen alpha <- {:#04x}", nn),
+        (0x0, 0x6, 0x0, _) if megachip => "play digitized sound (not supported)".to_string(),
+        (0x0, 0x7, 0x0, 0x0) if megachip => "stop digitized sound".to_string(),
+        (0x0, 0x8, 0x0, _) if megachip => {
+            format!("blend mode <- {} (only normal is supported)", n)
+        }
+        (0x0, 0x9, ..) if megachip => format!("collision color <- {:#04x}", nn),
+        (0x0, 0x0, 0xC, _) => format!("scroll the screen down {} pixels", n),
+        (0x0, 0x0, 0xD, _) => format!("scroll the screen up {} pixels", n),
+        (0x0, 0x0, 0xE, 0x0) => "clear the screen".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => format!("return from subroutine to {:#05x}", after.pc),
+        (0x0, 0x0, 0xF, 0xB) => "scroll the screen right 4 pixels".to_string(),
+        (0x0, 0x0, 0xF, 0xC) => "scroll the screen left 4 pixels".to_string(),
+        (0x0, 0x0, 0xF, 0xE) => "switch to low resolution and clear the screen".to_string(),
+        (0x0, 0x0, 0xF, 0xF) => "switch to high resolution and clear the screen".to_string(),
+        (0x1, ..) if nnn == before.pc => {
+            format!("jump to {:#05x} (itself: program loops here)", nnn)
+        }
+        (0x1, ..) => format!("jump to {:#05x}", nnn),
+        (0x2, ..) => format!("call subroutine at {:#05x}", nnn),
+        (0x3, ..) => format!(
+            "V{:X} ({:#04x}) == {:#04x}? {}",
+            x,
+            vx,
+            nn,
+            skipped(vx == nn)
+        ),
+        (0x4, ..) => format!(
+            "V{:X} ({:#04x}) != {:#04x}? {}",
+            x,
+            vx,
+            nn,
+            skipped(vx != nn)
+        ),
+        (0x5, _, _, 0x2) => format!("store V{:X}..=V{:X} at {:#05x}", x, y, before.index),
+        (0x5, _, _, 0x3) => format!("load V{:X}..=V{:X} from {:#05x}", x, y, before.index),
+        (0x5, ..) => format!("V{:X} == V{:X}? {}", x, y, skipped(vx == vy)),
+        (0x6, ..) => format!("V{:X} <- {:#04x}", x, nn),
+        (0x7, ..) => format!(
+            "V{:X} <- V{:X} + {:#04x} = {:#04x} (no carry)",
+            x, x, nn, result
+        ),
+        (0x8, _, _, 0x0) => format!("V{:X} <- V{:X} ({:#04x})", x, y, vy),
+        (0x8, _, _, 0x1) => format!("V{:X} <- V{:X} | V{:X} = {:#04x}", x, x, y, result),
+        (0x8, _, _, 0x2) => format!("V{:X} <- V{:X} & V{:X} = {:#04x}", x, x, y, result),
+        (0x8, _, _, 0x3) => format!("V{:X} <- V{:X} ^ V{:X} = {:#04x}", x, x, y, result),
+        (0x8, _, _, 0x4) => format!(
+            "V{:X} <- V{:X} + V{:X} = {:#04x} ({})",
+            x,
+            x,
+            y,
+            result,
+            if vf == 1 { "carry" } else { "no carry" }
+        ),
+        (0x8, _, _, 0x5) => format!(
+            "V{:X} <- V{:X} - V{:X} = {:#04x} (VF = {})",
+            x, x, y, result, vf
+        ),
+        (0x8, _, _, 0x6) => format!("V{:X} <- V{:X} >> 1 = {:#04x} (VF = {})", x, x, result, vf),
+        (0x8, _, _, 0x7) => format!(
+            "V{:X} <- V{:X} - V{:X} = {:#04x} (VF = {})",
+            x, y, x, result, vf
+        ),
+        (0x8, _, _, 0xE) => format!("V{:X} <- V{:X} << 1 = {:#04x} (VF = {})", x, x, result, vf),
+        (0x9, _, _, 0x0) => format!("V{:X} != V{:X}? {}", x, y, skipped(vx != vy)),
+        (0xA, ..) => format!("I <- {:#05x}", nnn),
+        (0xB, ..) => format!("jump to {:#05x} + V0 = {:#05x}", nnn, after.pc),
+        (0xD, ..) => format!(
+            "draw {} sprite from {:#05x} at ({}, {}){}",
+            if n == 0 && after.get_display().is_hires() {
+                "16x16".to_string()
+            } else {
+                format!("{}-row", n)
+            },
+            before.index,
+            vx,
+            vy,
+            if vf != 0 { ", collision!" } else { "" }
+        ),
+        (0xF, 0x0, 0x0, 0x0) => format!("I <- {:#06x}", after.index),
+        (0xF, _, 0x0, 0x1) => format!("draw to planes {:#04b}", x),
+        (0xF, 0x0, 0x0, 0x2) => format!("audio pattern <- 16 bytes from {:#05x}", before.index),
+        (0xF, _, 0x1, 0x5) => format!(
+            "delay timer <- V{:X} ({}), was {}",
+            x, vx, before.delay_timer
+        ),
+        (0xF, _, 0x1, 0x8) => format!(
+            "sound timer <- V{:X} ({}), was {}",
+            x, vx, before.sound_timer
+        ),
+        (0xF, _, 0x1, 0xE) => format!(
+            "I <- I ({:#05x}) + V{:X} ({}) = {:#05x}{}",
+            before.index,
+            x,
+            vx,
+            after.index,
+            if after.quirks.index_overflow_sets_vf {
+                format!(", VF = {}", vf)
+            } else {
+                String::new()
+            }
+        ),
+        (0xF, _, 0x2, 0x9) => format!(
+            "I <- address of glyph {:X} ({:#05x})",
+            vx & 0xF,
+            after.index
+        ),
+        (0xF, _, 0x3, 0x0) => format!(
+            "I <- address of large glyph {:X} ({:#05x})",
+            vx & 0xF,
+            after.index
+        ),
+        (0xF, _, 0x3, 0xA) => format!(
+            "pitch <- V{:X} ({}), pattern plays at {:.0} Hz",
+            x,
+            vx,
+            after.playback_rate()
+        ),
+        (0xF, _, 0x3, 0x3) => format!(
+            "store decimal digits of V{:X} ({}) at {:#05x}",
+            x, vx, before.index
+        ),
+        (0xF, _, 0x5, 0x5) => format!("store V0..=V{:X} at {:#05x}", x, before.index),
+        (0xF, _, 0x6, 0x5) => format!("load V0..=V{:X} from {:#05x}", x, before.index),
+        (0xF, _, 0x7, 0x5) => format!("save V0..=V{:X} to the RPL flags", x.min(7)),
+        (0xF, _, 0x8, 0x5) => format!("load V0..=V{:X} from the RPL flags", x.min(7)),
+        _ => "unknown instruction".to_string(),
+    };
+    format!("{:#05x}: {:04X}  {}", before.pc, opcode, text)
+}
+
+/// Rate-limited history of narrated instructions.
+#[derive(Clone)]
+pub struct Narrator {
+    lines: VecDeque<String>,
+    window_start: Instant,
+    in_window: usize,
+    skipped: usize,
+}
+
+impl Narrator {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::with_capacity(HISTORY),
+            window_start: Instant::now(),
+            in_window: 0,
+            skipped: 0,
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &String> {
+        self.lines.iter()
+    }
+
+    pub fn record(&mut self, before: &Snapshot, after: &Cpu) {
+        let now = Instant::now();
+        if now - self.window_start >= Duration::from_secs(1) {
+            if self.skipped > 0 {
+                self.push(format!("... {} more instructions", self.skipped));
+            }
+            self.window_start = now;
+            self.in_window = 0;
+            self.skipped = 0;
+        }
+
+        if self.in_window < MAX_PER_SECOND {
+            self.in_window += 1;
+            self.push(explain(before, after));
+        } else {
+            self.skipped += 1;
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        info!(target: "explain", "{}", line);
+        if self.lines.len() == HISTORY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+}
@@ -0,0 +1,181 @@
+//! Golden-image tests for the display renderer: known displays are drawn
+//! offscreen with every palette and mode and compared byte for byte with
+//! the reference PNGs in `golden/`. `--golden-update` regenerates them.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::cpu::{Cpu, CpuEvent};
+use crate::display::{Display, Resolution};
+use crate::palette::PalettePreset;
+use crate::png;
+use crate::renderer::DisplayRenderer;
+use crate::tutorial;
+
+/// The display states every configuration is rendered with.
+fn displays() -> Vec<(&'static str, Display)> {
+    let mut checkerboard = Display::new();
+    for (y, row) in checkerboard.planes[0].iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = (x + y) % 2 == 0;
+        }
+    }
+
+    // The tutorial ROM ends in a jump to itself.
+    let mut cpu = Cpu::new();
+    cpu.load_rom(tutorial::ROM);
+    while cpu.tick().expect("tutorial ROM faulted") != Some(CpuEvent::Halted) {}
+
+    let mut hires = Display::new();
+    hires.set_resolution(Resolution::High);
+    for (y, row) in hires.planes[0].iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = (x / 4 + y / 4) % 2 == 0 || x == y;
+        }
+    }
+
+    // One plane striped each way, so all four colors show.
+    let mut xo_chip = Display::new();
+    for (i, plane) in xo_chip.planes.iter_mut().enumerate() {
+        for (y, row) in plane.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = [x, y][i] / 8 % 2 == 0;
+            }
+        }
+    }
+
+    // Every palette index in a 16x16 grid of its own color, faded.
+    let mut mega = Display::new();
+    mega.set_resolution(Resolution::Mega);
+    let screen = mega.mega.as_mut().unwrap();
+    for (y, row) in screen.pixels.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = ((y / 12) * 16 + x / 16) as u8;
+        }
+    }
+    for (i, color) in screen.palette.iter_mut().enumerate().skip(1) {
+        *color = [i as u8, 0xFF - i as u8, (i * 3) as u8, 0xFF];
+    }
+    screen.alpha = 0xC0;
+
+    vec![
+        ("blank", Display::new()),
+        ("checkerboard", checkerboard),
+        ("tutorial", cpu.get_display().clone()),
+        ("hires", hires),
+        ("xo-chip", xo_chip),
+        ("mega", mega),
+    ]
+}
+
+/// Every image the renderer is checked against, by file name.
+fn images() -> Vec<(String, Vec<u8>)> {
+    let displays = displays();
+    let mut images = Vec::new();
+    for preset in PalettePreset::ALL {
+        let renderer = DisplayRenderer {
+            palette: preset.palette(),
+        };
+        for (name, display) in &displays {
+            let (width, height) = DisplayRenderer::frame_size(display);
+            for dimmed in [false, true] {
+                let mut frame = vec![0; width * height * 4];
+                renderer.draw(display, &mut frame, dimmed);
+                let suffix = if dimmed { "-dimmed" } else { "" };
+                images.push((
+                    format!("{}-{}{}.png", name, preset.name(), suffix),
+                    png::encode(width, height, &frame),
+                ));
+            }
+        }
+        // The low-resolution display against a blank one, and scaled up
+        // next to a high-resolution one.
+        let (_, blank) = &displays[0];
+        let (_, tutorial) = &displays[2];
+        let (_, hires) = &displays[3];
+        for (name, left, right) in [
+            ("compare", tutorial, blank),
+            ("compare-hires", tutorial, hires),
+        ] {
+            let (width, height) = DisplayRenderer::compare_frame_size(left, right);
+            let mut frame = vec![0; width * height * 4];
+            renderer.draw_compare(left, right, &mut frame, false);
+            images.push((
+                format!("{}-{}.png", name, preset.name()),
+                png::encode(width, height, &frame),
+            ));
+        }
+    }
+    images
+}
+
+/// Renders every image and writes it to `dir`, replacing the stored ones.
+pub fn update(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (name, image) in images() {
+        fs::write(dir.join(name), image)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the images whose names start with `prefix` and asserts each
+    /// matches the one in `golden/`.
+    fn assert_matches_golden(prefix: &str) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+        let images: Vec<_> = images()
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .collect();
+        assert!(!images.is_empty(), "no images named {}*", prefix);
+        let failures: Vec<_> = images
+            .iter()
+            .filter(|(name, image)| fs::read(dir.join(name)).ok().as_deref() != Some(&image[..]))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert!(
+            failures.is_empty(),
+            "differ from golden/ (regenerate with --golden-update golden): {:?}",
+            failures
+        );
+    }
+
+    #[test]
+    fn blank_display_matches_golden() {
+        assert_matches_golden("blank-");
+    }
+
+    #[test]
+    fn checkerboard_matches_golden() {
+        assert_matches_golden("checkerboard-");
+    }
+
+    #[test]
+    fn tutorial_display_matches_golden() {
+        assert_matches_golden("tutorial-");
+    }
+
+    #[test]
+    fn hires_display_matches_golden() {
+        assert_matches_golden("hires-");
+    }
+
+    #[test]
+    fn xo_chip_planes_match_golden() {
+        assert_matches_golden("xo-chip-");
+    }
+
+    #[test]
+    fn mega_chip_display_matches_golden() {
+        assert_matches_golden("mega-");
+    }
+
+    #[test]
+    fn compare_view_matches_golden() {
+        assert_matches_golden("compare-");
+    }
+}
@@ -1,9 +1,98 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use imgui::Window;
+use imgui::{Condition, Slider, SliderFlags, StyleColor, Window};
 use pixels::{wgpu, PixelsContext};
 
-use crate::cpu::Cpu;
+use crate::asm;
+use crate::compare::Comparison;
+use crate::condition;
+use crate::cpu::{Cpu, PC_START};
+use crate::disasm;
+use crate::explain::Narrator;
+use crate::font::FontSet;
+use crate::heatmap::Access;
+use crate::hotkeys::Bindings;
+use crate::i18n::{Lang, Translations};
+use crate::metadata::RomMetadata;
+use crate::palette::{Palette, PalettePreset};
+use crate::practice::{self, Practice};
+use crate::profiler::Profiler;
+use crate::quirks::Setup;
+use crate::sourcemap::SourceMap;
+use crate::trace::{Fault, Trace};
+use crate::tutorial::{self, Phase, Tutorial};
+use crate::watchpoint::{WatchHit, Watchpoint};
+use crate::widgets;
+use crate::PauseReason;
+
+/// How long passing notices stay on screen.
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// Emulator state shown by the GUI for the current frame.
+pub struct View<'a> {
+    pub cpu: &'a Cpu,
+    pub paused: Option<PauseReason>,
+    /// Set while the Explain window is open.
+    pub narrator: Option<&'a Narrator>,
+    pub tutorial: Option<&'a Tutorial>,
+    pub comparison: Option<&'a Comparison>,
+    pub metadata: Option<&'a RomMetadata>,
+    pub source_map: Option<&'a SourceMap>,
+    /// Addresses at which execution pauses.
+    pub breakpoints: &'a [u16],
+    pub conditions: &'a [condition::Condition],
+    pub watchpoints: &'a [Watchpoint],
+    /// The access that last stopped execution at a watchpoint.
+    pub watch_hit: Option<&'a WatchHit>,
+    /// Keys currently held, bit N for key N.
+    pub keys: u16,
+    pub practice: Option<&'a Practice>,
+    pub profiler: Option<&'a Profiler>,
+    pub trace: &'a Trace,
+    /// Why execution stopped, while paused on a fault.
+    pub fault: Option<&'a Fault>,
+    pub bindings: &'a Bindings,
+    pub ips: u32,
+    /// Multiplier on the instruction rate: above 1 while fast-forwarding,
+    /// below 1 in slow motion.
+    pub speed: f32,
+    pub booting: bool,
+    pub setup: &'a Setup,
+}
+
+/// Requests from the GUI that the main loop carries out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    TutorialNext,
+    /// Overwrite one byte of emulated memory.
+    WriteMemory {
+        address: u16,
+        byte: u8,
+    },
+    /// Print the sprite being edited so it can be pasted into a source file.
+    ExportSprite {
+        address: u16,
+        rows: u8,
+    },
+    ToggleBreakpoint(u16),
+    AddCondition(condition::Condition),
+    /// Drop the conditional breakpoint at this index.
+    RemoveCondition(usize),
+    AddWatchpoint(Watchpoint),
+    /// Drop the watchpoint at this index.
+    RemoveWatchpoint(usize),
+    ClearHeatmap,
+    /// Load hex bytes from the clipboard as the ROM and run it.
+    PasteRom,
+    /// Start the current ROM over.
+    ResetRom,
+    /// Pause a running program, or resume a paused one.
+    TogglePause,
+    /// Run one instruction of a paused program.
+    Step,
+    /// Start the current ROM over with this font set installed.
+    SelectFontSet(FontSet),
+}
 
 pub struct Gui {
     imgui: imgui::Context,
@@ -13,10 +102,57 @@ pub struct Gui {
     last_cursor: Option<imgui::MouseCursor>,
     about_open: bool,
     cpu_info_open: bool,
+    explain_open: bool,
+    disassembly_open: bool,
+    settings_open: bool,
+    rom_info_open: bool,
+    keypad_open: bool,
+    sprite_editor_open: bool,
+    sprite_address: i32,
+    sprite_rows: i32,
+    memory_open: bool,
+    display_open: bool,
+    pointer_keys: u16,
+    trace_open: bool,
+    breakpoints_open: bool,
+    /// The address being typed into the Breakpoints window.
+    breakpoint_source: String,
+    breakpoint_error: Option<String>,
+    /// The condition being typed into the Breakpoints window.
+    condition_source: String,
+    condition_error: Option<String>,
+    /// The watchpoint being typed into the Breakpoints window.
+    watchpoint_source: String,
+    watchpoint_error: Option<String>,
+    heat_access: Access,
+    patch_address: Option<usize>,
+    patch_source: String,
+    patch_error: Option<String>,
+    help_open: bool,
+    palette: PalettePreset,
+    rom_palette: Option<Palette>,
+    use_rom_palette: bool,
+    font_set: FontSet,
+    high_contrast: bool,
+    slow_motion: bool,
+    /// Percentage of normal speed to run at in slow motion.
+    slow_motion_percent: f32,
+    /// Emulated time let go after the last stall, and when it was.
+    time_skipped: Option<(Duration, Instant)>,
+    tr: Translations,
+    actions: Vec<Action>,
 }
 
 impl Gui {
-    pub fn new(window: &winit::window::Window, pixels: &pixels::Pixels) -> Self {
+    pub fn new(
+        window: &winit::window::Window,
+        pixels: &pixels::Pixels,
+        explain: bool,
+        palette: PalettePreset,
+        rom_palette: Option<Palette>,
+        font_set: FontSet,
+        lang: Lang,
+    ) -> Self {
         let mut imgui = imgui::Context::create();
         imgui.set_ini_filename(None);
 
@@ -57,9 +193,158 @@ impl Gui {
             last_cursor: None,
             about_open: true,
             cpu_info_open: true,
+            explain_open: explain,
+            disassembly_open: false,
+            settings_open: false,
+            rom_info_open: true,
+            keypad_open: false,
+            sprite_editor_open: false,
+            sprite_address: PC_START as i32,
+            sprite_rows: 8,
+            memory_open: false,
+            display_open: false,
+            pointer_keys: 0,
+            trace_open: false,
+            breakpoints_open: false,
+            breakpoint_source: String::new(),
+            breakpoint_error: None,
+            condition_source: String::new(),
+            condition_error: None,
+            watchpoint_source: String::new(),
+            watchpoint_error: None,
+            heat_access: Access::Read,
+            patch_address: None,
+            patch_source: String::new(),
+            patch_error: None,
+            help_open: false,
+            palette,
+            rom_palette,
+            use_rom_palette: true,
+            font_set,
+            high_contrast: false,
+            slow_motion: false,
+            slow_motion_percent: 25.0,
+            time_skipped: None,
+            tr: Translations::new(lang),
+            actions: Vec::new(),
+        }
+    }
+
+    /// The display palette: the ROM's own colors if it declares any and they
+    /// are enabled, otherwise the preset picked in the settings panel.
+    pub fn palette(&self) -> Palette {
+        match self.rom_palette {
+            Some(palette) if self.use_rom_palette => palette,
+            _ => self.palette.palette(),
+        }
+    }
+
+    fn apply_style(&mut self) {
+        let style = self.imgui.style_mut();
+        style.use_dark_colors();
+        style.window_border_size = 1.0;
+        style.frame_border_size = 0.0;
+        if self.high_contrast {
+            const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+            const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+            const YELLOW: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+            const BLUE: [f32; 4] = [0.0, 0.0, 0.7, 1.0];
+            style.window_border_size = 2.0;
+            style.frame_border_size = 1.0;
+            for color in [
+                StyleColor::WindowBg,
+                StyleColor::PopupBg,
+                StyleColor::MenuBarBg,
+                StyleColor::FrameBg,
+                StyleColor::TitleBg,
+            ] {
+                style[color] = BLACK;
+            }
+            for color in [StyleColor::Text, StyleColor::CheckMark] {
+                style[color] = WHITE;
+            }
+            for color in [
+                StyleColor::Border,
+                StyleColor::TitleBgActive,
+                StyleColor::Separator,
+            ] {
+                style[color] = YELLOW;
+            }
+            for color in [
+                StyleColor::Button,
+                StyleColor::Header,
+                StyleColor::FrameBgHovered,
+                StyleColor::ButtonHovered,
+                StyleColor::HeaderHovered,
+            ] {
+                style[color] = BLUE;
+            }
         }
     }
 
+    /// Whether executed instructions should be narrated for the Explain panel.
+    pub fn explain_open(&self) -> bool {
+        self.explain_open
+    }
+
+    /// Whether the Memory window, which colors bytes by the heatmap, is open.
+    pub fn memory_open(&self) -> bool {
+        self.memory_open
+    }
+
+    /// Multiplier on the instruction rate picked in the settings panel:
+    /// below 1 in slow motion.
+    pub fn speed(&self) -> f32 {
+        if self.slow_motion {
+            self.slow_motion_percent / 100.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn toggle_slow_motion(&mut self) {
+        self.slow_motion = !self.slow_motion;
+    }
+
+    /// Shows or hides the hotkey cheat sheet.
+    pub fn toggle_help(&mut self) {
+        self.help_open = !self.help_open;
+    }
+
+    /// Brings up the panels needed to debug a fault: the disassembly around
+    /// the faulting PC and the instructions that led there.
+    pub fn show_fault(&mut self) {
+        self.disassembly_open = true;
+        self.trace_open = true;
+    }
+
+    /// Briefly tells the user that `skipped` of emulated time was let go
+    /// rather than caught up on after a stall.
+    pub fn show_time_skipped(&mut self, skipped: Duration) {
+        let total = match self.time_skipped {
+            Some((earlier, at)) if at.elapsed() < NOTICE_DURATION => earlier + skipped,
+            _ => skipped,
+        };
+        self.time_skipped = Some((total, Instant::now()));
+    }
+
+    /// Brings up the panels around a breakpoint that was just hit: the
+    /// disassembly at PC and the list of breakpoints.
+    pub fn show_breakpoint(&mut self) {
+        self.disassembly_open = true;
+        self.breakpoints_open = true;
+    }
+
+    /// Keys held down by clicking the on-screen keypad.
+    pub fn pointer_keys(&self) -> u16 {
+        self.pointer_keys
+    }
+
+    /// Drains the actions requested since the last call.
+    pub fn take_actions(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.actions)
+    }
+
     pub fn prepare(
         &mut self,
         window: &winit::window::Window,
@@ -76,8 +361,36 @@ impl Gui {
         encoder: &mut wgpu::CommandEncoder,
         render_target: &wgpu::TextureView,
         context: &PixelsContext,
-        cpu: &Cpu,
+        view: &View,
     ) -> imgui_wgpu::RendererResult<()> {
+        let View {
+            cpu,
+            paused,
+            narrator,
+            tutorial,
+            comparison,
+            metadata,
+            source_map,
+            breakpoints,
+            conditions,
+            watchpoints,
+            watch_hit,
+            keys,
+            practice,
+            profiler,
+            trace,
+            fault,
+            bindings,
+            ips,
+            speed,
+            booting,
+            setup,
+        } = *view;
+
+        let high_contrast = self.high_contrast;
+        let display_palette = self.palette();
+        let tr = &self.tr;
+
         // Start a new Dear ImGui frame and update the cursor
         let ui = self.imgui.frame();
 
@@ -90,14 +403,58 @@ impl Gui {
         // Draw windows and GUI elements here
         let mut about_open = false;
         let mut cpu_info_open = false;
+        let mut explain_open = false;
+        let mut disassembly_open = false;
+        let mut settings_open = false;
+        let mut rom_info_open = false;
+        let mut keypad_open = false;
+        let mut sprite_editor_open = false;
+        let mut memory_open = false;
+        let mut display_open = false;
+        let mut trace_open = false;
+        let mut breakpoints_open = false;
 
         ui.main_menu_bar(|| {
-            ui.menu("Views", || {
-                cpu_info_open = imgui::MenuItem::new("CPU Info").build(&ui);
+            ui.menu(tr.get("menu-views"), || {
+                cpu_info_open = imgui::MenuItem::new(tr.get("window-cpu-info")).build(&ui);
+                explain_open = imgui::MenuItem::new(tr.get("window-explain")).build(&ui);
+                disassembly_open = imgui::MenuItem::new(tr.get("window-disassembly")).build(&ui);
+                keypad_open = imgui::MenuItem::new(tr.get("window-keypad")).build(&ui);
+                sprite_editor_open =
+                    imgui::MenuItem::new(tr.get("window-sprite-editor")).build(&ui);
+                memory_open = imgui::MenuItem::new(tr.get("window-memory")).build(&ui);
+                display_open = imgui::MenuItem::new(tr.get("window-display")).build(&ui);
+                trace_open = imgui::MenuItem::new(tr.get("window-trace")).build(&ui);
+                breakpoints_open = imgui::MenuItem::new(tr.get("window-breakpoints")).build(&ui);
             });
-            ui.menu("Help", || {
-                about_open = imgui::MenuItem::new("About...").build(&ui);
+            ui.menu(tr.get("menu-options"), || {
+                settings_open = imgui::MenuItem::new(tr.get("window-settings")).build(&ui);
+                if imgui::MenuItem::new(tr.get("menu-paste-rom")).build(&ui) {
+                    self.actions.push(Action::PasteRom);
+                }
+                if imgui::MenuItem::new(tr.get("menu-reset-rom")).build(&ui) {
+                    self.actions.push(Action::ResetRom);
+                }
             });
+            ui.menu(tr.get("menu-help"), || {
+                rom_info_open = imgui::MenuItem::new(tr.get("window-rom-info"))
+                    .enabled(metadata.is_some())
+                    .build(&ui);
+                about_open = imgui::MenuItem::new(tr.get("menu-about")).build(&ui);
+            });
+            let label = match paused {
+                Some(_) => tr.get("menu-resume"),
+                None => tr.get("menu-pause"),
+            };
+            if imgui::MenuItem::new(label).enabled(!booting).build(&ui) {
+                self.actions.push(Action::TogglePause);
+            }
+            if imgui::MenuItem::new(tr.get("menu-step"))
+                .enabled(paused.is_some() && !booting)
+                .build(&ui)
+            {
+                self.actions.push(Action::Step);
+            }
         });
         if about_open {
             self.about_open = true;
@@ -105,18 +462,803 @@ impl Gui {
         if cpu_info_open {
             self.cpu_info_open = true;
         }
+        if explain_open {
+            self.explain_open = true;
+        }
+        if disassembly_open {
+            self.disassembly_open = true;
+        }
+        if settings_open {
+            self.settings_open = true;
+        }
+        if rom_info_open {
+            self.rom_info_open = true;
+        }
+        if keypad_open {
+            self.keypad_open = true;
+        }
+        if sprite_editor_open {
+            self.sprite_editor_open = true;
+        }
+        if memory_open {
+            self.memory_open = true;
+        }
+        if display_open {
+            self.display_open = true;
+        }
+        if trace_open {
+            self.trace_open = true;
+        }
+        if breakpoints_open {
+            self.breakpoints_open = true;
+        }
+
+        if self.settings_open {
+            let palette = &mut self.palette;
+            let has_rom_palette = self.rom_palette.is_some();
+            let use_rom_palette = &mut self.use_rom_palette;
+            let font_set = &mut self.font_set;
+            let actions = &mut self.actions;
+            let high_contrast = &mut self.high_contrast;
+            let slow_motion = &mut self.slow_motion;
+            let slow_motion_percent = &mut self.slow_motion_percent;
+            Window::new(tr.get("window-settings"))
+                .opened(&mut self.settings_open)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    let mut selected = *palette as usize;
+                    if ui.combo_simple_string(
+                        tr.get("settings-palette"),
+                        &mut selected,
+                        PalettePreset::NAMES,
+                    ) {
+                        *palette = PalettePreset::ALL[selected];
+                        *use_rom_palette = false;
+                    }
+                    if has_rom_palette {
+                        ui.checkbox(tr.get("settings-rom-palette"), use_rom_palette);
+                    }
+                    let mut selected = *font_set as usize;
+                    if ui.combo_simple_string(
+                        tr.get("settings-font"),
+                        &mut selected,
+                        FontSet::NAMES,
+                    ) {
+                        *font_set = FontSet::ALL[selected];
+                        actions.push(Action::SelectFontSet(*font_set));
+                    }
+                    ui.checkbox(tr.get("settings-high-contrast"), high_contrast);
+                    ui.checkbox(tr.get("settings-slow-motion"), slow_motion);
+                    Slider::new(tr.get("settings-slow-motion-speed"), 1.0, 100.0)
+                        .display_format("%.0f%%")
+                        .flags(SliderFlags::LOGARITHMIC)
+                        .build(&ui, slow_motion_percent);
+                });
+        }
 
         if self.about_open {
             ui.show_about_window(&mut self.about_open);
         }
 
+        if let Some(metadata) = metadata.filter(|_| self.rom_info_open) {
+            Window::new(tr.get("window-rom-info"))
+                .opened(&mut self.rom_info_open)
+                .size([320.0, 240.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    match &metadata.title {
+                        Some(title) => ui.text(title),
+                        None => ui.text(tr.get("rom-info-untitled")),
+                    }
+                    if !metadata.authors.is_empty() {
+                        ui.text(tr.format(
+                            "rom-info-authors",
+                            &[("authors", &metadata.authors.join(", "))],
+                        ));
+                    }
+                    if let Some(platform) = &metadata.platform {
+                        ui.text(tr.format("rom-info-platform", &[("platform", platform)]));
+                    }
+                    if let Some(description) = &metadata.description {
+                        ui.separator();
+                        ui.text_wrapped(description);
+                    }
+                });
+        }
+
+        let mut pointer_keys = 0;
+        if self.keypad_open {
+            Window::new(tr.get("window-keypad"))
+                .opened(&mut self.keypad_open)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    let hint = |key| metadata?.control(key);
+                    if let Some(key) = widgets::keypad(&ui, keys, hint) {
+                        pointer_keys |= 1 << key;
+                    }
+                });
+        }
+
+        self.pointer_keys = pointer_keys;
+
+        if self.display_open {
+            Window::new(tr.get("window-display"))
+                .opened(&mut self.display_open)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    widgets::display(&ui, cpu.get_display(), display_palette, 4.0);
+                });
+        }
+
+        if self.sprite_editor_open {
+            const CELL: f32 = 16.0;
+            let actions = &mut self.actions;
+            let address = &mut self.sprite_address;
+            let rows = &mut self.sprite_rows;
+            Window::new(tr.get("window-sprite-editor"))
+                .opened(&mut self.sprite_editor_open)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    let memory = cpu.memory();
+                    ui.input_int(tr.get("sprite-address"), address)
+                        .chars_hexadecimal(true)
+                        .build();
+                    ui.input_int(tr.get("sprite-rows"), rows).build();
+                    *rows = (*rows).clamp(1, 15);
+                    *address = (*address).clamp(0, (memory.len() - *rows as usize) as i32);
+                    let start = *address as usize;
+                    let bytes = &memory[start..start + *rows as usize];
+
+                    let origin = ui.cursor_screen_pos();
+                    let clicked =
+                        ui.invisible_button("##sprite", [8.0 * CELL, *rows as f32 * CELL]);
+                    let draw_list = ui.get_window_draw_list();
+                    for (row, byte) in bytes.iter().enumerate() {
+                        for bit in 0..8 {
+                            let lit = byte & (0x80 >> bit) != 0;
+                            let min =
+                                [origin[0] + bit as f32 * CELL, origin[1] + row as f32 * CELL];
+                            let max = [min[0] + CELL - 1.0, min[1] + CELL - 1.0];
+                            let color = if lit {
+                                [1.0, 1.0, 1.0, 1.0]
+                            } else {
+                                [0.15, 0.15, 0.15, 1.0]
+                            };
+                            draw_list.add_rect(min, max, color).filled(true).build();
+                        }
+                    }
+                    if paused.is_none() {
+                        ui.text_disabled(tr.get("sprite-paused-only"));
+                    } else if clicked {
+                        let [x, y] = ui.io().mouse_pos;
+                        let bit = ((x - origin[0]) / CELL) as usize;
+                        let row = ((y - origin[1]) / CELL) as usize;
+                        if bit < 8 && row < bytes.len() {
+                            actions.push(Action::WriteMemory {
+                                address: (start + row) as u16,
+                                byte: bytes[row] ^ (0x80 >> bit),
+                            });
+                        }
+                    }
+
+                    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+                    ui.text(hex.join(" "));
+                    if ui.button(tr.get("sprite-export")) {
+                        actions.push(Action::ExportSprite {
+                            address: start as u16,
+                            rows: *rows as u8,
+                        });
+                    }
+                });
+        }
+
+        if self.memory_open {
+            const COLUMNS: usize = 16;
+            let actions = &mut self.actions;
+            let heat_access = &mut self.heat_access;
+            Window::new(tr.get("window-memory"))
+                .opened(&mut self.memory_open)
+                .size([460.0, 360.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    for (i, access) in Access::ALL.into_iter().enumerate() {
+                        if i > 0 {
+                            ui.same_line();
+                        }
+                        if ui.radio_button_bool(tr.get(access.key()), *heat_access == access) {
+                            *heat_access = access;
+                        }
+                    }
+                    ui.same_line();
+                    if ui.button(tr.get("heat-clear")) {
+                        actions.push(Action::ClearHeatmap);
+                    }
+                    ui.separator();
+
+                    let memory = cpu.memory();
+                    let heatmap = &cpu.heatmap;
+                    // Views only carry the heatmap while this window is
+                    // open, so the frame it opens on has none yet.
+                    let mut colors = heatmap.colors(*heat_access);
+                    colors.resize(memory.len(), [0.45, 0.45, 0.45, 1.0]);
+                    let rows = memory.len() / COLUMNS;
+                    let mut clipper = imgui::ListClipper::new(rows as i32).begin(&ui);
+                    while clipper.step() {
+                        for row in clipper.display_start()..clipper.display_end() {
+                            let start = row as usize * COLUMNS;
+                            ui.text_disabled(format!("{:03X}:", start));
+                            for address in start..start + COLUMNS {
+                                ui.same_line();
+                                ui.text_colored(
+                                    colors[address],
+                                    format!("{:02X}", memory[address]),
+                                );
+                                if ui.is_item_hovered() {
+                                    let count = |access| {
+                                        heatmap.counts(access).get(address).copied().unwrap_or(0)
+                                    };
+                                    ui.tooltip_text(tr.format(
+                                        "heat-counts",
+                                        &[
+                                            ("address", &format!("{:03X}", address)),
+                                            ("executes", &count(Access::Execute)),
+                                            ("reads", &count(Access::Read)),
+                                            ("writes", &count(Access::Write)),
+                                        ],
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                });
+        }
+
+        if self.trace_open {
+            Window::new(tr.get("window-trace"))
+                .opened(&mut self.trace_open)
+                .size([300.0, 360.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    for (address, opcode) in trace.iter() {
+                        ui.text(format!(
+                            "  {:03X}: {:04X}  {}",
+                            address,
+                            opcode,
+                            disasm::disassemble(*opcode)
+                        ));
+                    }
+                    if let Some(fault) = fault {
+                        let memory = cpu.memory();
+                        let pc = fault.pc as usize;
+                        let instruction = match memory.get(pc..pc + 2) {
+                            Some(&[high, low]) => {
+                                let opcode = u16::from_be_bytes([high, low]);
+                                format!("{:04X}  {}", opcode, disasm::disassemble(opcode))
+                            }
+                            _ => "----".to_string(),
+                        };
+                        ui.text_colored(
+                            [1.0, 0.3, 0.3, 1.0],
+                            format!("! {:03X}: {}", pc, instruction),
+                        );
+                        ui.separator();
+                        ui.text_colored(
+                            [1.0, 0.3, 0.3, 1.0],
+                            tr.format("trace-fault", &[("message", &fault.message)]),
+                        );
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+        }
+
+        if self.breakpoints_open {
+            let actions = &mut self.actions;
+            let source = &mut self.breakpoint_source;
+            let error = &mut self.breakpoint_error;
+            let condition_source = &mut self.condition_source;
+            let condition_error = &mut self.condition_error;
+            let watchpoint_source = &mut self.watchpoint_source;
+            let watchpoint_error = &mut self.watchpoint_error;
+            Window::new(tr.get("window-breakpoints"))
+                .opened(&mut self.breakpoints_open)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    if breakpoints.is_empty() {
+                        ui.text_disabled(tr.get("breakpoints-none"));
+                    }
+                    for &address in breakpoints {
+                        let marker = if address == cpu.pc { ">" } else { " " };
+                        ui.text(format!("{} {:#05x}", marker, address));
+                        ui.same_line();
+                        let label = format!("{}##{}", tr.get("breakpoints-remove"), address);
+                        if ui.small_button(label) {
+                            actions.push(Action::ToggleBreakpoint(address));
+                        }
+                    }
+                    ui.separator();
+                    if ui
+                        .input_text(tr.get("breakpoints-add"), source)
+                        .enter_returns_true(true)
+                        .build()
+                    {
+                        let digits = source.trim().trim_start_matches("0x");
+                        match u16::from_str_radix(digits, 16) {
+                            Ok(address) if (address as usize) < cpu.memory().len() => {
+                                if !breakpoints.contains(&address) {
+                                    actions.push(Action::ToggleBreakpoint(address));
+                                }
+                                source.clear();
+                                *error = None;
+                            }
+                            _ => {
+                                *error =
+                                    Some(tr.format("breakpoints-invalid", &[("input", &*source)]))
+                            }
+                        }
+                    }
+                    if let Some(e) = error {
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], &*e);
+                    }
+
+                    ui.separator();
+                    ui.text(tr.get("breakpoints-conditions"));
+                    for (i, condition) in conditions.iter().enumerate() {
+                        let marker = if condition.holds(cpu) { "!" } else { " " };
+                        ui.text(format!("{} {}", marker, condition));
+                        ui.same_line();
+                        let label = format!("{}##condition{}", tr.get("breakpoints-remove"), i);
+                        if ui.small_button(label) {
+                            actions.push(Action::RemoveCondition(i));
+                        }
+                    }
+                    if ui
+                        .input_text(tr.get("breakpoints-add-condition"), condition_source)
+                        .hint("V3 == 0x1F")
+                        .enter_returns_true(true)
+                        .build()
+                    {
+                        match condition_source.parse() {
+                            Ok(condition) => {
+                                actions.push(Action::AddCondition(condition));
+                                condition_source.clear();
+                                *condition_error = None;
+                            }
+                            Err(e) => *condition_error = Some(e),
+                        }
+                    }
+                    if let Some(e) = condition_error {
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], &*e);
+                    }
+
+                    ui.separator();
+                    ui.text(tr.get("breakpoints-watchpoints"));
+                    for (i, watchpoint) in watchpoints.iter().enumerate() {
+                        ui.text(format!("  {}", watchpoint));
+                        ui.same_line();
+                        let label = format!("{}##watchpoint{}", tr.get("breakpoints-remove"), i);
+                        if ui.small_button(label) {
+                            actions.push(Action::RemoveWatchpoint(i));
+                        }
+                    }
+                    if ui
+                        .input_text(tr.get("breakpoints-add-watchpoint"), watchpoint_source)
+                        .hint("0x300-0x302:w")
+                        .enter_returns_true(true)
+                        .build()
+                    {
+                        match watchpoint_source.parse::<Watchpoint>() {
+                            Ok(watchpoint) if watchpoint.fits(cpu.memory().len()) => {
+                                actions.push(Action::AddWatchpoint(watchpoint));
+                                watchpoint_source.clear();
+                                *watchpoint_error = None;
+                            }
+                            Ok(watchpoint) => {
+                                *watchpoint_error = Some(tr.format(
+                                    "breakpoints-invalid",
+                                    &[("input", &watchpoint.to_string())],
+                                ))
+                            }
+                            Err(e) => *watchpoint_error = Some(e),
+                        }
+                    }
+                    if let Some(e) = watchpoint_error {
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], &*e);
+                    }
+                    if let Some(hit) = watch_hit {
+                        let key = match hit.access {
+                            Access::Write => "watch-hit-write",
+                            _ => "watch-hit-read",
+                        };
+                        ui.text_wrapped(tr.format(
+                            key,
+                            &[
+                                ("range", &hit.watchpoint.to_string()),
+                                ("opcode", &format!("{:04X}", hit.opcode)),
+                                ("instruction", &disasm::disassemble(hit.opcode)),
+                                ("pc", &format!("{:#05x}", hit.pc)),
+                            ],
+                        ));
+                    }
+                });
+        }
+
         if self.cpu_info_open {
-            Window::new("CPU Info").build(&ui, || {
-                ui.text(format!("PC: {}", cpu.pc));
-                ui.text(format!("I: {}", cpu.index))
+            Window::new(tr.get("window-cpu-info")).build(&ui, || {
+                widgets::registers(&ui, tr, cpu);
+                ui.separator();
+                widgets::setup(&ui, tr, setup);
             });
         }
 
+        if self.explain_open {
+            Window::new(tr.get("window-explain"))
+                .opened(&mut self.explain_open)
+                .size([360.0, 200.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    for line in narrator.iter().flat_map(|narrator| narrator.lines()) {
+                        ui.text(line);
+                    }
+                    if ui.scroll_y() >= ui.scroll_max_y() {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+        }
+
+        if self.disassembly_open {
+            let actions = &mut self.actions;
+            let patch_address = &mut self.patch_address;
+            let patch_source = &mut self.patch_source;
+            let patch_error = &mut self.patch_error;
+            Window::new(tr.get("window-disassembly"))
+                .opened(&mut self.disassembly_open)
+                .size([260.0, 320.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    let memory = cpu.memory();
+                    if let Some(addr) =
+                        widgets::disassembly(&ui, tr, cpu, breakpoints, 24, *patch_address)
+                    {
+                        *patch_address = Some(addr);
+                        *patch_error = None;
+                    }
+
+                    if let Some(addr) = *patch_address {
+                        ui.separator();
+                        ui.text(tr.format("asm-at", &[("address", &format!("{:#05x}", addr))]));
+                        if ui
+                            .input_text("##asm", patch_source)
+                            .enter_returns_true(true)
+                            .build()
+                        {
+                            match asm::assemble(patch_source) {
+                                Ok(opcode) => {
+                                    let [high, low] = opcode.to_be_bytes();
+                                    actions.push(Action::WriteMemory {
+                                        address: addr as u16,
+                                        byte: high,
+                                    });
+                                    actions.push(Action::WriteMemory {
+                                        address: addr as u16 + 1,
+                                        byte: low,
+                                    });
+                                    patch_source.clear();
+                                    *patch_error = None;
+                                    *patch_address =
+                                        Some(addr + 2).filter(|a| a + 1 < memory.len());
+                                }
+                                Err(e) => *patch_error = Some(e),
+                            }
+                        }
+                        if let Some(e) = patch_error {
+                            ui.text_colored([1.0, 0.3, 0.3, 1.0], &*e);
+                        }
+                    }
+                });
+        }
+
+        if let Some(source_map) = source_map {
+            let actions = &mut self.actions;
+            Window::new(tr.get("window-source"))
+                .size([360.0, 320.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    let current = source_map.line_at(cpu.pc);
+                    for (number, line) in source_map.lines.iter().enumerate() {
+                        let address = source_map.address_of(number);
+                        let marker = if current == Some(number) { ">" } else { " " };
+                        let breakpoint = match address {
+                            Some(address) if breakpoints.contains(&address) => "*",
+                            _ => " ",
+                        };
+                        let label = format!(
+                            "{}{} {:4}  {}##{}",
+                            marker,
+                            breakpoint,
+                            number + 1,
+                            line,
+                            number
+                        );
+                        if imgui::Selectable::new(label)
+                            .selected(current == Some(number))
+                            .build(&ui)
+                        {
+                            if let Some(address) = address {
+                                actions.push(Action::ToggleBreakpoint(address));
+                            }
+                        }
+                        if current == Some(number) && paused.is_none() {
+                            ui.set_scroll_here_y();
+                        }
+                    }
+                });
+        }
+
+        if let Some(tutorial) = tutorial {
+            let actions = &mut self.actions;
+            Window::new(tr.get("window-tutorial"))
+                .size([320.0, 360.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    let memory = cpu.memory();
+                    let addr = tutorial.instruction() as usize;
+                    let opcode = u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+
+                    ui.text_wrapped(tr.get(tutorial.phase().key()));
+                    ui.separator();
+                    for a in (PC_START..tutorial::DATA_START as usize).step_by(2) {
+                        let color = if a == addr {
+                            [1.0, 0.85, 0.2, 1.0]
+                        } else {
+                            [0.6, 0.6, 0.6, 1.0]
+                        };
+                        ui.text_colored(
+                            color,
+                            format!("{:#05x}: {:02X} {:02X}", a, memory[a], memory[a + 1]),
+                        );
+                    }
+                    ui.separator();
+                    match tutorial.phase() {
+                        Phase::Fetch => ui.text(tr.format(
+                            "tutorial-fetched",
+                            &[
+                                ("pc", &format!("{:#05x}", addr)),
+                                (
+                                    "bytes",
+                                    &format!("{:02X} {:02X}", memory[addr], memory[addr + 1]),
+                                ),
+                                ("opcode", &format!("{:04X}", opcode)),
+                            ],
+                        )),
+                        Phase::Decode => {
+                            ui.text(tr.format(
+                                "tutorial-decoded",
+                                &[
+                                    ("opcode", &format!("{:04X}", opcode)),
+                                    ("instruction", &disasm::disassemble(opcode)),
+                                ],
+                            ));
+                            if let Some(info) = disasm::lookup(opcode) {
+                                ui.text_wrapped(info.description);
+                            }
+                        }
+                        Phase::Execute => {
+                            ui.text(
+                                tr.format(
+                                    "tutorial-pc-now",
+                                    &[("pc", &format!("{:#05x}", cpu.pc))],
+                                ),
+                            );
+                            ui.text(format!(
+                                "VA = {}  VB = {}  VC = {}  VF = {}  I = {:#05x}",
+                                cpu.registers[0xA],
+                                cpu.registers[0xB],
+                                cpu.registers[0xC],
+                                cpu.registers[0xF],
+                                cpu.index
+                            ));
+                            if opcode & 0xF000 == 0xD000 {
+                                ui.text(tr.get("tutorial-display-updated"));
+                            }
+                        }
+                    }
+                    ui.separator();
+                    ui.text_wrapped(tr.get(&tutorial.note_key()));
+                    if ui.button(tr.get("tutorial-next")) {
+                        actions.push(Action::TutorialNext);
+                    }
+                });
+        }
+
+        if let Some(comparison) = comparison {
+            Window::new(tr.get("window-comparison"))
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    ui.text(tr.format(
+                        "comparison-frame",
+                        &[
+                            ("frame", &comparison.frame()),
+                            ("instructions", &comparison.instructions()),
+                        ],
+                    ));
+                    match comparison.diverged_at() {
+                        Some((frame, instruction)) => ui.text_colored(
+                            [1.0, 0.3, 0.3, 1.0],
+                            tr.format(
+                                "comparison-diverged",
+                                &[("frame", &frame), ("instruction", &instruction)],
+                            ),
+                        ),
+                        None => ui.text(tr.get("comparison-identical")),
+                    }
+                });
+        }
+
+        if let Some(profiler) = profiler {
+            const TOP: usize = 10;
+            Window::new(tr.get("window-profiler"))
+                .size([320.0, 420.0], Condition::FirstUseEver)
+                .build(&ui, || {
+                    let row = |name: String, total: std::time::Duration, count: u64, mean| {
+                        ui.text(format!(
+                            "{:<8} {:>9.2} ms {:>10} {:>6} ns",
+                            name,
+                            total.as_secs_f64() * 1000.0,
+                            count,
+                            mean
+                        ));
+                    };
+                    ui.text(tr.get("profiler-opcodes"));
+                    for (pattern, cost) in profiler.top_opcodes(TOP) {
+                        row(
+                            pattern.to_string(),
+                            cost.total,
+                            cost.count,
+                            cost.mean().as_nanos(),
+                        );
+                    }
+                    ui.separator();
+                    ui.text(tr.get("profiler-addresses"));
+                    for ((pattern, address), cost) in profiler.top_addresses(TOP) {
+                        row(
+                            format!("{:03X} {}", address, pattern),
+                            cost.total,
+                            cost.count,
+                            cost.mean().as_nanos(),
+                        );
+                    }
+                });
+        }
+
+        if self.help_open {
+            let [width, height] = ui.io().display_size;
+            Window::new(tr.get("window-hotkeys"))
+                .opened(&mut self.help_open)
+                .collapsible(false)
+                .always_auto_resize(true)
+                .position([width / 2.0, height / 2.0], Condition::Always)
+                .position_pivot([0.5, 0.5])
+                .build(&ui, || {
+                    for (hotkey, key) in bindings.iter() {
+                        ui.text(format!("{:>8?}", key));
+                        ui.same_line_with_pos(80.0);
+                        ui.text(tr.get(hotkey.key()));
+                    }
+                });
+        }
+
+        let [width, height] = ui.io().display_size;
+        Window::new("##status")
+            .title_bar(false)
+            .resizable(false)
+            .movable(false)
+            .scroll_bar(false)
+            .position([0.0, height], Condition::Always)
+            .position_pivot([0.0, 1.0])
+            .size([width, 0.0], Condition::Always)
+            .build(&ui, || {
+                let state = if booting {
+                    tr.get("state-booting").to_string()
+                } else if let Some(reason) = paused {
+                    tr.format("state-paused", &[("reason", &tr.get(reason.key()))])
+                } else if tutorial.is_some() {
+                    tr.get("state-tutorial").to_string()
+                } else {
+                    tr.get("state-running").to_string()
+                };
+                ui.text(tr.format(
+                    "status-bar",
+                    &[
+                        ("pc", &format!("{:03X}", cpu.pc)),
+                        ("i", &format!("{:03X}", cpu.index)),
+                        ("dt", &cpu.delay_timer),
+                        ("st", &cpu.sound_timer),
+                        ("beep", &if cpu.is_beeping() { "♪" } else { "" }),
+                        ("ips", &ips),
+                        ("quirks", &cpu.quirks),
+                        ("state", &state),
+                    ],
+                ));
+            });
+
+        if let Some(practice) = practice {
+            Window::new("##practice")
+                .title_bar(false)
+                .resizable(false)
+                .movable(false)
+                .always_auto_resize(true)
+                .position([8.0, 24.0], Condition::Always)
+                .build(&ui, || {
+                    ui.text(tr.format(
+                        "practice-time",
+                        &[
+                            ("time", &practice::format_time(practice.elapsed())),
+                            ("frames", &practice.frames()),
+                        ],
+                    ));
+                    ui.text(tr.format(
+                        "practice-section",
+                        &[
+                            ("section", &practice.section()),
+                            ("time", &practice::format_time(practice.section_elapsed())),
+                        ],
+                    ));
+                    let held: Vec<String> = (0..16)
+                        .filter(|key| keys & (1 << key) != 0)
+                        .map(|key| format!("{:X}", key))
+                        .collect();
+                    let held = if held.is_empty() {
+                        "-".to_string()
+                    } else {
+                        held.join(" ")
+                    };
+                    ui.text(tr.format("practice-keys", &[("keys", &held)]));
+                });
+        }
+
+        if let Some(reason) = paused {
+            let [width, _] = ui.io().display_size;
+            Window::new("Paused")
+                .title_bar(false)
+                .resizable(false)
+                .movable(false)
+                .always_auto_resize(true)
+                .position([width - 8.0, 24.0], Condition::Always)
+                .position_pivot([1.0, 0.0])
+                .build(&ui, || {
+                    ui.text(tr.format("paused", &[("reason", &tr.get(reason.key()))]));
+                });
+        } else if speed != 1.0 && !booting {
+            let [width, _] = ui.io().display_size;
+            Window::new("##speed")
+                .title_bar(false)
+                .resizable(false)
+                .movable(false)
+                .always_auto_resize(true)
+                .position([width - 8.0, 24.0], Condition::Always)
+                .position_pivot([1.0, 0.0])
+                .build(&ui, || {
+                    if speed > 1.0 {
+                        ui.text(tr.format("fast-forward", &[("speed", &speed)]));
+                    } else {
+                        let percent = format!("{:.0}", speed * 100.0);
+                        ui.text(tr.format("slow-motion", &[("percent", &percent)]));
+                    }
+                });
+        }
+
+        if let Some((skipped, at)) = self.time_skipped {
+            if at.elapsed() < NOTICE_DURATION {
+                let [width, _] = ui.io().display_size;
+                Window::new("##time-skipped")
+                    .title_bar(false)
+                    .resizable(false)
+                    .movable(false)
+                    .always_auto_resize(true)
+                    .position([width / 2.0, 24.0], Condition::Always)
+                    .position_pivot([0.5, 0.0])
+                    .build(&ui, || {
+                        let seconds = format!("{:.1}", skipped.as_secs_f32());
+                        ui.text(tr.format("time-skipped", &[("seconds", &seconds)]));
+                    });
+            } else {
+                self.time_skipped = None;
+            }
+        }
+
         // Render Dear ImGui with WGPU
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("imgui"),
@@ -131,8 +1273,15 @@ impl Gui {
             depth_stencil_attachment: None,
         });
 
-        self.renderer
-            .render(ui.render(), &context.queue, &context.device, &mut rpass)
+        let result = self
+            .renderer
+            .render(ui.render(), &context.queue, &context.device, &mut rpass);
+        drop(rpass);
+
+        if self.high_contrast != high_contrast {
+            self.apply_style();
+        }
+        result
     }
 
     /// Handle any outstanding events.
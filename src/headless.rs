@@ -0,0 +1,193 @@
+//! Scripted runs without a window, for driving compatibility sweeps from
+//! shell scripts. The process exit code says how the run ended.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+
+use crate::chaos::Chaos;
+use crate::cpu::{Cpu, CpuError, CpuEvent};
+use crate::rawdump::RawDump;
+use crate::trace;
+
+/// Keypad changes to replay, one per line as `FRAME KEY...`: from 60 Hz
+/// frame `FRAME` on, counting from 0, exactly the listed hex keys are held. A frame with no keys
+/// releases everything. Comments start with `#`.
+///
+/// ```text
+/// 60 5      # hold 5 from frame 60
+/// 90 5 6    # add 6
+/// 120       # release both
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct InputScript {
+    changes: Vec<(u64, u16)>,
+}
+
+impl InputScript {
+    /// The keys held during `frame`.
+    fn keys_at(&self, frame: u64) -> u16 {
+        self.changes
+            .iter()
+            .take_while(|(at, _)| *at <= frame)
+            .last()
+            .map_or(0, |(_, keys)| *keys)
+    }
+}
+
+impl FromStr for InputScript {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut changes = Vec::new();
+        for (number, line) in s.lines().enumerate() {
+            let error = |what: &str| format!("line {}: {}", number + 1, what);
+            let mut tokens = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            let Some(frame) = tokens.next() else {
+                continue;
+            };
+            let frame: u64 = frame
+                .parse()
+                .map_err(|_| error("expected a frame number"))?;
+            let mut keys = 0;
+            for key in tokens {
+                match u8::from_str_radix(key, 16) {
+                    Ok(key) if key < 16 => keys |= 1 << key,
+                    _ => return Err(error(&format!("not a key: {}", key))),
+                }
+            }
+            changes.push((frame, keys));
+        }
+        changes.sort_by_key(|(frame, _)| *frame);
+        Ok(Self { changes })
+    }
+}
+
+/// When a headless run stops and what it checks.
+pub struct Run {
+    /// How many 60 Hz frames to run at most, however many instructions
+    /// that takes at the CPU's speed.
+    pub max_frames: Option<u64>,
+    /// Stop once the program jumps to itself. Programs that exit with 00FD
+    /// always stop the run.
+    pub until_halt: bool,
+    pub expect_display_hash: Option<u64>,
+    pub script: InputScript,
+    /// Gets a record for every frame of the run.
+    pub raw_dump: Option<RawDump>,
+    pub chaos: Option<Chaos>,
+}
+
+pub enum Outcome {
+    /// The run ended as asked and the display matched, if it was checked.
+    Pass { frames: u64, hash: u64 },
+    /// The display hash differs from the expected one.
+    Fail {
+        frames: u64,
+        hash: u64,
+        expected: u64,
+    },
+    /// `--max-frames` ran out before the program halted.
+    Timeout { frames: u64, hash: u64 },
+    /// An instruction failed, e.g. on an unknown opcode, or the emulator
+    /// panicked.
+    Crashed { frame: u64, reason: String },
+}
+
+impl Outcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Pass { .. } => 0,
+            Outcome::Fail { .. } => 1,
+            Outcome::Timeout { .. } => 2,
+            Outcome::Crashed { .. } => 3,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass { frames, hash } => {
+                write!(f, "pass after {} frames, display {:016x}", frames, hash)
+            }
+            Outcome::Fail {
+                frames,
+                hash,
+                expected,
+            } => write!(
+                f,
+                "fail after {} frames: display {:016x}, expected {:016x}",
+                frames, hash, expected
+            ),
+            Outcome::Timeout { frames, hash } => write!(
+                f,
+                "timeout: no halt within {} frames, display {:016x}",
+                frames, hash
+            ),
+            Outcome::Crashed { frame, reason } => {
+                write!(f, "crashed at frame {}: {}", frame, reason)
+            }
+        }
+    }
+}
+
+impl Run {
+    pub fn execute(&mut self, cpu: &mut Cpu) -> Outcome {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let start = cpu.frame();
+        let mut frames = 0;
+        let mut halted = false;
+        let finished = panic::catch_unwind(AssertUnwindSafe(|| -> Result<_, CpuError> {
+            while self.max_frames.is_none_or(|max| frames < max) {
+                cpu.keys = self.script.keys_at(frames);
+                if let Some(chaos) = &mut self.chaos {
+                    chaos.strike(cpu);
+                }
+                let event = cpu.tick()?;
+                if let Some(dump) = self
+                    .raw_dump
+                    .as_mut()
+                    .filter(|_| cpu.frame() - start != frames)
+                {
+                    if let Err(e) = dump.append(cpu.get_display()) {
+                        eprintln!("failed to write raw dump: {}", e);
+                        self.raw_dump = None;
+                    }
+                }
+                frames = cpu.frame() - start;
+                let exited = event == Some(CpuEvent::Exited);
+                if exited || self.until_halt && event == Some(CpuEvent::Halted) {
+                    halted = true;
+                    break;
+                }
+            }
+            Ok(())
+        }));
+        panic::set_hook(hook);
+
+        let hash = cpu.get_display().hash();
+        match (finished, self.expect_display_hash) {
+            (Err(payload), _) => Outcome::Crashed {
+                frame: frames,
+                reason: trace::panic_message(payload),
+            },
+            (Ok(Err(error)), _) => Outcome::Crashed {
+                frame: frames,
+                reason: error.to_string(),
+            },
+            (Ok(Ok(())), _) if self.until_halt && !halted => Outcome::Timeout { frames, hash },
+            (Ok(Ok(())), Some(expected)) if expected != hash => Outcome::Fail {
+                frames,
+                hash,
+                expected,
+            },
+            (Ok(Ok(())), _) => Outcome::Pass { frames, hash },
+        }
+    }
+}
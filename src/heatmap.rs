@@ -0,0 +1,101 @@
+//! Counts how often each byte of memory is executed, read and written, so
+//! the memory view can show which addresses a ROM actually uses.
+
+/// Which kind of access the memory view colors by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Execute,
+    Read,
+    Write,
+}
+
+impl Access {
+    pub const ALL: [Access; 3] = [Access::Execute, Access::Read, Access::Write];
+
+    /// The translation key for this access kind.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Access::Execute => "heat-execute",
+            Access::Read => "heat-read",
+            Access::Write => "heat-write",
+        }
+    }
+
+    /// The color of the hottest byte; colder bytes fade towards grey.
+    fn color(&self) -> [f32; 3] {
+        match self {
+            Access::Execute => [0.4, 0.6, 1.0],
+            Access::Read => [0.3, 1.0, 0.4],
+            Access::Write => [1.0, 0.35, 0.25],
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Heatmap {
+    executes: Vec<u32>,
+    reads: Vec<u32>,
+    writes: Vec<u32>,
+}
+
+impl Heatmap {
+    pub fn new(size: usize) -> Self {
+        Self {
+            executes: vec![0; size],
+            reads: vec![0; size],
+            writes: vec![0; size],
+        }
+    }
+
+    pub fn record(&mut self, access: Access, address: usize) {
+        let counts = self.counts_mut(access);
+        if let Some(count) = counts.get_mut(address) {
+            *count = count.saturating_add(1);
+        }
+    }
+
+    pub fn counts(&self, access: Access) -> &[u32] {
+        match access {
+            Access::Execute => &self.executes,
+            Access::Read => &self.reads,
+            Access::Write => &self.writes,
+        }
+    }
+
+    fn counts_mut(&mut self, access: Access) -> &mut [u32] {
+        match access {
+            Access::Execute => &mut self.executes,
+            Access::Read => &mut self.reads,
+            Access::Write => &mut self.writes,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for access in Access::ALL {
+            self.counts_mut(access).fill(0);
+        }
+    }
+
+    /// Text colors for every byte, on a log scale relative to the hottest
+    /// byte so a frame counter doesn't drown out everything else.
+    pub fn colors(&self, access: Access) -> Vec<[f32; 4]> {
+        const COLD: [f32; 3] = [0.45, 0.45, 0.45];
+        let counts = self.counts(access);
+        let max = counts.iter().copied().max().unwrap_or(0);
+        let scale = ((max as f32) + 1.0).ln();
+        let hot = access.color();
+        counts
+            .iter()
+            .map(|&count| {
+                let heat = if count == 0 {
+                    0.0
+                } else {
+                    // Anything touched at all stands out from untouched bytes.
+                    0.35 + 0.65 * ((count as f32) + 1.0).ln() / scale
+                };
+                let mix = |i: usize| COLD[i] + (hot[i] - COLD[i]) * heat;
+                [mix(0), mix(1), mix(2), 1.0]
+            })
+            .collect()
+    }
+}
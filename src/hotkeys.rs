@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// Emulator actions that can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    Quit,
+    Pause,
+    DumpAscii,
+    Help,
+    SaveSection,
+    RetrySection,
+    ResetRun,
+    PasteRom,
+    Screenshot,
+    ResetRom,
+    FastForward,
+    SlowMotion,
+    FrameAdvance,
+    Step,
+}
+
+impl Hotkey {
+    pub const ALL: [Hotkey; 14] = [
+        Hotkey::Quit,
+        Hotkey::Pause,
+        Hotkey::DumpAscii,
+        Hotkey::Help,
+        Hotkey::SaveSection,
+        Hotkey::RetrySection,
+        Hotkey::ResetRun,
+        Hotkey::PasteRom,
+        Hotkey::Screenshot,
+        Hotkey::ResetRom,
+        Hotkey::FastForward,
+        Hotkey::SlowMotion,
+        Hotkey::FrameAdvance,
+        Hotkey::Step,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hotkey::Quit => "quit",
+            Hotkey::Pause => "pause",
+            Hotkey::DumpAscii => "dump-ascii",
+            Hotkey::Help => "help",
+            Hotkey::SaveSection => "save-section",
+            Hotkey::RetrySection => "retry-section",
+            Hotkey::ResetRun => "reset-run",
+            Hotkey::PasteRom => "paste-rom",
+            Hotkey::Screenshot => "screenshot",
+            Hotkey::ResetRom => "reset-rom",
+            Hotkey::FastForward => "fast-forward",
+            Hotkey::SlowMotion => "slow-motion",
+            Hotkey::FrameAdvance => "frame-advance",
+            Hotkey::Step => "step",
+        }
+    }
+
+    /// Translation key describing the action.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Hotkey::Quit => "hotkey-quit",
+            Hotkey::Pause => "hotkey-pause",
+            Hotkey::DumpAscii => "hotkey-dump-ascii",
+            Hotkey::Help => "hotkey-help",
+            Hotkey::SaveSection => "hotkey-save-section",
+            Hotkey::RetrySection => "hotkey-retry-section",
+            Hotkey::ResetRun => "hotkey-reset-run",
+            Hotkey::PasteRom => "hotkey-paste-rom",
+            Hotkey::Screenshot => "hotkey-screenshot",
+            Hotkey::ResetRom => "hotkey-reset-rom",
+            Hotkey::FastForward => "hotkey-fast-forward",
+            Hotkey::SlowMotion => "hotkey-slow-motion",
+            Hotkey::FrameAdvance => "hotkey-frame-advance",
+            Hotkey::Step => "hotkey-step",
+        }
+    }
+
+    fn default_key(&self) -> VirtualKeyCode {
+        match self {
+            Hotkey::Quit => VirtualKeyCode::Escape,
+            Hotkey::Pause => VirtualKeyCode::P,
+            Hotkey::DumpAscii => VirtualKeyCode::F2,
+            Hotkey::Help => VirtualKeyCode::F1,
+            Hotkey::SaveSection => VirtualKeyCode::F5,
+            Hotkey::RetrySection => VirtualKeyCode::F6,
+            Hotkey::ResetRun => VirtualKeyCode::F7,
+            Hotkey::PasteRom => VirtualKeyCode::F8,
+            Hotkey::Screenshot => VirtualKeyCode::F9,
+            Hotkey::ResetRom => VirtualKeyCode::F3,
+            Hotkey::FastForward => VirtualKeyCode::Tab,
+            Hotkey::SlowMotion => VirtualKeyCode::F4,
+            Hotkey::FrameAdvance => VirtualKeyCode::F10,
+            Hotkey::Step => VirtualKeyCode::F11,
+        }
+    }
+}
+
+/// A user remap given on the command line as `action=Key`, e.g. `pause=Space`.
+#[derive(Debug, Clone, Copy)]
+pub struct Binding {
+    hotkey: Hotkey,
+    key: VirtualKeyCode,
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (action, key) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected action=Key, got {}", s))?;
+        let hotkey = Hotkey::ALL
+            .into_iter()
+            .find(|hotkey| hotkey.name() == action.trim())
+            .ok_or_else(|| format!("unknown action: {}", action))?;
+        let key = parse_key(key.trim()).ok_or_else(|| format!("unknown key: {}", key))?;
+        Ok(Binding { hotkey, key })
+    }
+}
+
+/// The active key for every hotkey.
+pub struct Bindings {
+    keys: Vec<(Hotkey, VirtualKeyCode)>,
+}
+
+impl Bindings {
+    pub fn new(remaps: &[Binding]) -> Self {
+        let keys = Hotkey::ALL
+            .into_iter()
+            .map(|hotkey| {
+                let key = remaps
+                    .iter()
+                    .rev()
+                    .find(|binding| binding.hotkey == hotkey)
+                    .map_or(hotkey.default_key(), |binding| binding.key);
+                (hotkey, key)
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn key(&self, hotkey: Hotkey) -> VirtualKeyCode {
+        self.keys
+            .iter()
+            .find(|(h, _)| *h == hotkey)
+            .map_or(hotkey.default_key(), |(_, key)| *key)
+    }
+
+    pub fn pressed(&self, input: &WinitInputHelper, hotkey: Hotkey) -> bool {
+        input.key_pressed(self.key(hotkey))
+    }
+
+    /// Whether the key for `hotkey` is being held down.
+    pub fn held(&self, input: &WinitInputHelper, hotkey: Hotkey) -> bool {
+        input.key_held(self.key(hotkey))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Hotkey, VirtualKeyCode)> {
+        self.keys.iter()
+    }
+}
+
+const KEYS: &[VirtualKeyCode] = {
+    use VirtualKeyCode::*;
+    &[
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9,
+        F10, F11, F12, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back,
+        Return, Space, Tab, Minus, Equals, Comma, Period, Slash, Backslash, Grave,
+    ]
+};
+
+/// Parses a key by its winit name, ignoring case (e.g. `space`, `F3`, `Key1`).
+pub fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+    KEYS.iter()
+        .copied()
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
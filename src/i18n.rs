@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// GUI languages with a bundled translation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Nl,
+}
+
+impl Lang {
+    pub const NAMES: &'static [&'static str] = &["en", "nl"];
+
+    fn source(&self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.ftl"),
+            Lang::Nl => include_str!("../locales/nl.ftl"),
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "nl" => Ok(Lang::Nl),
+            _ => Err(format!("unsupported language: {}", s)),
+        }
+    }
+}
+
+/// Translated GUI strings, read from the Fluent-style `key = value` files in
+/// `locales/`. Lookups fall back to English, then to the key itself.
+pub struct Translations {
+    messages: HashMap<&'static str, &'static str>,
+    fallback: HashMap<&'static str, &'static str>,
+}
+
+impl Translations {
+    pub fn new(lang: Lang) -> Self {
+        Self {
+            messages: parse(lang.source()),
+            fallback: parse(Lang::En.source()),
+        }
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .copied()
+            .unwrap_or(key)
+    }
+
+    /// Looks up `key` and substitutes each `{ $name }` placeholder.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut message = self.get(key).to_string();
+        for (name, value) in args {
+            message = message.replace(&format!("{{ ${} }}", name), &value.to_string());
+        }
+        message
+    }
+}
+
+fn parse(source: &'static str) -> HashMap<&'static str, &'static str> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
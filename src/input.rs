@@ -0,0 +1,22 @@
+use winit_input_helper::WinitInputHelper;
+
+/// A source of CHIP-8 key presses: the keyboard, a network crowd, ...
+pub trait Input {
+    /// The keys currently held, bit N for key N.
+    fn keys(&mut self) -> u16;
+
+    /// Called whenever winit delivered new window events.
+    fn update(&mut self, _input: &WinitInputHelper) {}
+}
+
+/// Passes new window events on to every input.
+pub fn update(inputs: &mut [Box<dyn Input>], input: &WinitInputHelper) {
+    for source in inputs {
+        source.update(input);
+    }
+}
+
+/// The keys held on any of `inputs`.
+pub fn held(inputs: &mut [Box<dyn Input>]) -> u16 {
+    inputs.iter_mut().fold(0, |keys, input| keys | input.keys())
+}
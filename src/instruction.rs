@@ -0,0 +1,250 @@
+//! Decoding opcodes into the instructions the core executes, separately from
+//! executing them.
+
+use std::fmt;
+
+/// A decoded instruction. `x` and `y` name registers, `nn`/`nnn` are
+/// immediates and `n` is a sprite height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0010 (MegaChip)
+    MegaOff,
+    /// 0011 (MegaChip)
+    MegaOn,
+    /// 00BN (MegaChip)
+    MegaScrollUp { n: u8 },
+    /// 00CN (SCHIP)
+    ScrollDown { n: u8 },
+    /// 00DN (XO-CHIP)
+    ScrollUp { n: u8 },
+    /// 00E0
+    ClearScreen,
+    /// 00EE
+    Return,
+    /// 00FB (SCHIP)
+    ScrollRight,
+    /// 00FC (SCHIP)
+    ScrollLeft,
+    /// 00FD (SCHIP)
+    Exit,
+    /// 00FE (SCHIP)
+    LowResolution,
+    /// 00FF (SCHIP)
+    HighResolution,
+    /// 01NN NNNN (MegaChip), whose low 16 bits are the word after the
+    /// opcode
+    MegaIndex { nn: u8 },
+    /// 02NN (MegaChip)
+    LoadPalette { nn: u8 },
+    /// 03NN (MegaChip)
+    SpriteWidth { nn: u8 },
+    /// 04NN (MegaChip)
+    SpriteHeight { nn: u8 },
+    /// 05NN (MegaChip)
+    ScreenAlpha { nn: u8 },
+    /// 060N (MegaChip)
+    PlaySample { n: u8 },
+    /// 0700 (MegaChip)
+    StopSample,
+    /// 080N (MegaChip)
+    BlendMode { n: u8 },
+    /// 09NN (MegaChip)
+    CollisionColor { nn: u8 },
+    /// 0NNN
+    MachineCall { nnn: u16 },
+    /// 1NNN
+    Jump { nnn: u16 },
+    /// 2NNN
+    Call { nnn: u16 },
+    /// 3XNN
+    SkipIfEqual { x: u8, nn: u8 },
+    /// 4XNN
+    SkipIfNotEqual { x: u8, nn: u8 },
+    /// 5XY0
+    SkipIfRegistersEqual { x: u8, y: u8 },
+    /// 5XY2 (XO-CHIP)
+    StoreRange { x: u8, y: u8 },
+    /// 5XY3 (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    /// 6XNN
+    Set { x: u8, nn: u8 },
+    /// 7XNN
+    AddImmediate { x: u8, nn: u8 },
+    /// 8XY0
+    Move { x: u8, y: u8 },
+    /// 8XY1
+    Or { x: u8, y: u8 },
+    /// 8XY2
+    And { x: u8, y: u8 },
+    /// 8XY3
+    Xor { x: u8, y: u8 },
+    /// 8XY4
+    Add { x: u8, y: u8 },
+    /// 8XY5
+    Sub { x: u8, y: u8 },
+    /// 8XY6
+    ShiftRight { x: u8, y: u8 },
+    /// 8XY7
+    SubReverse { x: u8, y: u8 },
+    /// 8XYE
+    ShiftLeft { x: u8, y: u8 },
+    /// 9XY0
+    SkipIfRegistersNotEqual { x: u8, y: u8 },
+    /// ANNN
+    SetIndex { nnn: u16 },
+    /// BNNN
+    JumpOffset { nnn: u16 },
+    /// CXNN
+    Random { x: u8, nn: u8 },
+    /// DXYN
+    Draw { x: u8, y: u8, n: u8 },
+    /// EX9E
+    SkipIfKey { x: u8 },
+    /// EXA1
+    SkipIfNotKey { x: u8 },
+    /// F000 NNNN (XO-CHIP), whose address is the word after the opcode
+    LongIndex,
+    /// FN01 (XO-CHIP), where N is a plane mask rather than a register
+    SelectPlanes { mask: u8 },
+    /// F002 (XO-CHIP)
+    LoadAudioPattern,
+    /// FX0A
+    WaitKey { x: u8 },
+    /// FX15
+    SetDelayTimer { x: u8 },
+    /// FX18
+    SetSoundTimer { x: u8 },
+    /// FX1E
+    AddIndex { x: u8 },
+    /// FX29
+    FontGlyph { x: u8 },
+    /// FX30 (SCHIP)
+    BigFontGlyph { x: u8 },
+    /// FX3A (XO-CHIP)
+    SetPitch { x: u8 },
+    /// FX33
+    Bcd { x: u8 },
+    /// FX55
+    Store { x: u8 },
+    /// FX65
+    Load { x: u8 },
+    /// FX75 (SCHIP)
+    SaveFlags { x: u8 },
+    /// FX85 (SCHIP)
+    LoadFlags { x: u8 },
+}
+
+impl Instruction {
+    /// Whether this is one of MegaChip's additions, which on every other
+    /// platform are 0NNN machine-code calls.
+    pub fn is_megachip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::MegaOff
+                | Instruction::MegaOn
+                | Instruction::MegaScrollUp { .. }
+                | Instruction::MegaIndex { .. }
+                | Instruction::LoadPalette { .. }
+                | Instruction::SpriteWidth { .. }
+                | Instruction::SpriteHeight { .. }
+                | Instruction::ScreenAlpha { .. }
+                | Instruction::PlaySample { .. }
+                | Instruction::StopSample
+                | Instruction::BlendMode { .. }
+                | Instruction::CollisionColor { .. }
+        )
+    }
+}
+
+/// An opcode that isn't an instruction this core knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    pub opcode: u16,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown opcode {:#06x}", self.opcode)
+    }
+}
+
+pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+    let nibbles = (
+        ((opcode & 0xF000) >> 12) as u8,
+        ((opcode & 0x0F00) >> 8) as u8,
+        ((opcode & 0x00F0) >> 4) as u8,
+        (opcode & 0x000F) as u8,
+    );
+    let nnn = opcode & 0x0FFF;
+    let nn = (opcode & 0x00FF) as u8;
+    let x = nibbles.1;
+    let y = nibbles.2;
+    let n = nibbles.3;
+
+    let instruction = match nibbles {
+        (0x0, 0x0, 0x1, 0x0) => Instruction::MegaOff,
+        (0x0, 0x0, 0x1, 0x1) => Instruction::MegaOn,
+        (0x0, 0x0, 0xB, _) => Instruction::MegaScrollUp { n },
+        (0x0, 0x0, 0xC, _) => Instruction::ScrollDown { n },
+        (0x0, 0x0, 0xD, _) => Instruction::ScrollUp { n },
+        (0x0, 0x0, 0xE, 0x0) => Instruction::ClearScreen,
+        (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+        (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+        (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+        (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+        (0x0, 0x0, 0xF, 0xE) => Instruction::LowResolution,
+        (0x0, 0x0, 0xF, 0xF) => Instruction::HighResolution,
+        (0x0, 0x1, _, _) => Instruction::MegaIndex { nn },
+        (0x0, 0x2, _, _) => Instruction::LoadPalette { nn },
+        (0x0, 0x3, _, _) => Instruction::SpriteWidth { nn },
+        (0x0, 0x4, _, _) => Instruction::SpriteHeight { nn },
+        (0x0, 0x5, _, _) => Instruction::ScreenAlpha { nn },
+        (0x0, 0x6, 0x0, _) => Instruction::PlaySample { n },
+        (0x0, 0x7, 0x0, 0x0) => Instruction::StopSample,
+        (0x0, 0x8, 0x0, _) => Instruction::BlendMode { n },
+        (0x0, 0x9, _, _) => Instruction::CollisionColor { nn },
+        (0x0, _, _, _) => Instruction::MachineCall { nnn },
+        (0x1, _, _, _) => Instruction::Jump { nnn },
+        (0x2, _, _, _) => Instruction::Call { nnn },
+        (0x3, _, _, _) => Instruction::SkipIfEqual { x, nn },
+        (0x4, _, _, _) => Instruction::SkipIfNotEqual { x, nn },
+        (0x5, _, _, 0x2) => Instruction::StoreRange { x, y },
+        (0x5, _, _, 0x3) => Instruction::LoadRange { x, y },
+        (0x5, _, _, _) => Instruction::SkipIfRegistersEqual { x, y },
+        (0x6, _, _, _) => Instruction::Set { x, nn },
+        (0x7, _, _, _) => Instruction::AddImmediate { x, nn },
+        (0x8, _, _, 0x0) => Instruction::Move { x, y },
+        (0x8, _, _, 0x1) => Instruction::Or { x, y },
+        (0x8, _, _, 0x2) => Instruction::And { x, y },
+        (0x8, _, _, 0x3) => Instruction::Xor { x, y },
+        (0x8, _, _, 0x4) => Instruction::Add { x, y },
+        (0x8, _, _, 0x5) => Instruction::Sub { x, y },
+        (0x8, _, _, 0x6) => Instruction::ShiftRight { x, y },
+        (0x8, _, _, 0x7) => Instruction::SubReverse { x, y },
+        (0x8, _, _, 0xE) => Instruction::ShiftLeft { x, y },
+        (0x9, _, _, 0x0) => Instruction::SkipIfRegistersNotEqual { x, y },
+        (0xA, _, _, _) => Instruction::SetIndex { nnn },
+        (0xB, _, _, _) => Instruction::JumpOffset { nnn },
+        (0xC, _, _, _) => Instruction::Random { x, nn },
+        (0xD, _, _, _) => Instruction::Draw { x, y, n },
+        (0xE, _, 0x9, 0xE) => Instruction::SkipIfKey { x },
+        (0xE, _, 0xA, 0x1) => Instruction::SkipIfNotKey { x },
+        (0xF, 0x0, 0x0, 0x0) => Instruction::LongIndex,
+        (0xF, _, 0x0, 0x1) => Instruction::SelectPlanes { mask: x },
+        (0xF, 0x0, 0x0, 0x2) => Instruction::LoadAudioPattern,
+        (0xF, _, 0x0, 0xA) => Instruction::WaitKey { x },
+        (0xF, _, 0x1, 0x5) => Instruction::SetDelayTimer { x },
+        (0xF, _, 0x1, 0x8) => Instruction::SetSoundTimer { x },
+        (0xF, _, 0x1, 0xE) => Instruction::AddIndex { x },
+        (0xF, _, 0x2, 0x9) => Instruction::FontGlyph { x },
+        (0xF, _, 0x3, 0x0) => Instruction::BigFontGlyph { x },
+        (0xF, _, 0x3, 0xA) => Instruction::SetPitch { x },
+        (0xF, _, 0x3, 0x3) => Instruction::Bcd { x },
+        (0xF, _, 0x5, 0x5) => Instruction::Store { x },
+        (0xF, _, 0x6, 0x5) => Instruction::Load { x },
+        (0xF, _, 0x7, 0x5) => Instruction::SaveFlags { x },
+        (0xF, _, 0x8, 0x5) => Instruction::LoadFlags { x },
+        _ => return Err(DecodeError { opcode }),
+    };
+    Ok(instruction)
+}
@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+use crate::hotkeys::parse_key;
+use crate::input::Input;
+use crate::metadata::KEYPAD;
+
+/// Which keyboard key stands in for each keypad button, in keypad order
+/// (`1 2 3 C / 4 5 6 D / 7 8 9 E / A 0 B F`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeypadLayout {
+    keys: [VirtualKeyCode; 16],
+}
+
+impl Default for KeypadLayout {
+    /// The left-hand block starting at `1`, as most emulators map it.
+    fn default() -> Self {
+        use VirtualKeyCode::*;
+        Self {
+            keys: [Key1, Key2, Key3, Key4, Q, W, E, R, A, S, D, F, Z, X, C, V],
+        }
+    }
+}
+
+impl KeypadLayout {
+    /// The keyboard key for CHIP-8 key `key`.
+    pub fn key(&self, key: u8) -> VirtualKeyCode {
+        let position = KEYPAD.iter().flatten().position(|k| *k == key).unwrap();
+        self.keys[position]
+    }
+}
+
+impl FromStr for KeypadLayout {
+    type Err = String;
+
+    /// Parses 16 comma-separated key names in keypad order.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split(',')
+            .map(|name| parse_key(name.trim()).ok_or_else(|| format!("unknown key: {}", name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let keys = keys
+            .try_into()
+            .map_err(|keys: Vec<_>| format!("expected 16 keys, got {}", keys.len()))?;
+        Ok(Self { keys })
+    }
+}
+
+/// The keypad mapped onto the keyboard.
+pub struct Keyboard {
+    layout: KeypadLayout,
+    held: u16,
+}
+
+impl Keyboard {
+    pub fn new(layout: KeypadLayout) -> Self {
+        Self { layout, held: 0 }
+    }
+}
+
+impl Input for Keyboard {
+    fn keys(&mut self) -> u16 {
+        self.held
+    }
+
+    fn update(&mut self, input: &WinitInputHelper) {
+        self.held = (0..16)
+            .filter(|key| input.key_held(self.layout.key(*key)))
+            .fold(0, |held, key| held | 1 << key);
+    }
+}
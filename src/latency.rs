@@ -0,0 +1,92 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Probes are dropped if the display doesn't react within this long.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Probe {
+    pressed_at: Instant,
+    display_hash: u64,
+    executed_at: Option<Instant>,
+    changed_at: Option<Instant>,
+}
+
+/// Points on the way from a key press to the screen: the core executing
+/// its first instruction after the press, the display contents changing,
+/// and the changed frame being presented.
+const STAGES: [&str; 3] = ["key -> core", "key -> display change", "key -> presented"];
+
+/// Time from a key press to each of the `STAGES`.
+type Sample = [Duration; 3];
+
+/// Measures end-to-end input latency, one key press at a time.
+#[derive(Default)]
+pub struct LatencyProbe {
+    pending: Option<Probe>,
+    samples: Vec<Sample>,
+}
+
+impl LatencyProbe {
+    /// Starts timing a key press, unless one is already being timed.
+    pub fn key_pressed(&mut self, display_hash: u64) {
+        if self.pending.is_none() {
+            self.pending = Some(Probe {
+                pressed_at: Instant::now(),
+                display_hash,
+                executed_at: None,
+                changed_at: None,
+            });
+        }
+    }
+
+    /// Call after the core executed instructions.
+    pub fn executed(&mut self, display_hash: u64) {
+        let now = Instant::now();
+        if let Some(probe) = &mut self.pending {
+            probe.executed_at.get_or_insert(now);
+            if probe.changed_at.is_none() && display_hash != probe.display_hash {
+                probe.changed_at = Some(now);
+            }
+            if now - probe.pressed_at > TIMEOUT && probe.changed_at.is_none() {
+                self.pending = None;
+            }
+        }
+    }
+
+    /// Call once a frame has been handed to the GPU.
+    pub fn presented(&mut self) {
+        let probe = match &self.pending {
+            Some(probe) if probe.changed_at.is_some() => self.pending.take().unwrap(),
+            _ => return,
+        };
+        let sample = [
+            probe.executed_at.unwrap() - probe.pressed_at,
+            probe.changed_at.unwrap() - probe.pressed_at,
+            probe.pressed_at.elapsed(),
+        ];
+        log::info!(target: "latency", "{:?}", sample);
+        self.samples.push(sample);
+    }
+}
+
+impl fmt::Display for LatencyProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.samples.is_empty() {
+            return writeln!(f, "latency: no key press changed the display");
+        }
+        writeln!(f, "latency over {} key presses:", self.samples.len())?;
+        for (stage, name) in STAGES.iter().enumerate() {
+            let times: Vec<Duration> = self.samples.iter().map(|sample| sample[stage]).collect();
+            let mean = times.iter().sum::<Duration>() / times.len() as u32;
+            writeln!(
+                f,
+                "  {:<24} mean {:>7.2} ms  min {:>7.2} ms  max {:>7.2} ms",
+                name,
+                mean.as_secs_f64() * 1000.0,
+                times.iter().min().unwrap().as_secs_f64() * 1000.0,
+                times.iter().max().unwrap().as_secs_f64() * 1000.0,
+            )?;
+        }
+        Ok(())
+    }
+}
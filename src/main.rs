@@ -1,81 +1,1077 @@
-use cpu::Cpu;
+use buzzer::{Buzzer, Tone};
+use chaos::{Chaos, ChaosSpec};
+use compare::Comparison;
+use condition::Condition;
+use cpu::{Cpu, MemoryLayout, OpcodePolicy, DEFAULT_IPS, TIMER_HZ};
+use crowd::CrowdInput;
+use debugserver::DebugServer;
 use display::Display;
-use gui::Gui;
-use log::error;
-use pixels::{Pixels, SurfaceTexture};
+use emulation::{Machine, Step};
+use font::FontSet;
+use gui::{Action, Gui, View};
+use headless::InputScript;
+use hotkeys::{Binding, Bindings, Hotkey};
+use i18n::Lang;
+use input::Input;
+use keyboard::{Keyboard, KeypadLayout};
+use latency::LatencyProbe;
+use log::{error, info};
+use metadata::RomMetadata;
+use palette::PalettePreset;
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
+use practice::Practice;
+use quirks::{Origin, Platform, Quirks, Setup};
+use rawdump::RawDump;
+use recording::Recorder;
 use renderer::DisplayRenderer;
+use rng::Rng;
+use romconfig::RomConfig;
+use scheduler::Scheduler;
+use spectate::Spectators;
+use speed::SpeedMeter;
 use std::{
-    path::PathBuf,
+    cell::Cell,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 use structopt::StructOpt;
+use tutorial::Tutorial;
+use watchpoint::Watchpoint;
 use winit::{
     dpi::LogicalSize,
-    event::{Event, VirtualKeyCode},
+    event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{Window, WindowBuilder},
 };
 use winit_input_helper::WinitInputHelper;
 
+mod asm;
+mod benchmark;
+mod buzzer;
+mod chaos;
+mod clipboard;
+mod compare;
+mod compat;
+mod condition;
 mod cpu;
+mod crowd;
+mod debugserver;
+mod difftest;
+mod disasm;
 mod display;
+mod emulation;
+mod explain;
+mod font;
+mod golden;
 mod gui;
+mod headless;
+mod heatmap;
+mod hotkeys;
+mod i18n;
+mod input;
+mod instruction;
+mod json;
+mod keyboard;
+mod latency;
+mod metadata;
+mod octo;
+mod palette;
+mod png;
+mod practice;
+mod profiler;
+mod quirks;
+mod quirkscan;
+mod rawdump;
+mod recording;
+mod reference;
 mod renderer;
+mod rng;
+mod romconfig;
+mod romdb;
+mod rpl;
+mod scheduler;
+mod screenshot;
+mod sourcemap;
+mod spectate;
+mod speed;
+mod splash;
+mod toml;
+mod trace;
+mod tutorial;
+mod watchpoint;
+mod widgets;
+
+/// How long --compat runs each ROM unless --max-frames says otherwise: 15
+/// seconds of emulated time.
+const COMPAT_FRAMES: u64 = 900;
+
+/// How often the event loop wakes up while the window is minimized.
+const MINIMIZED_WAKEUP: Duration = Duration::from_millis(50);
+
+/// Why emulation is currently paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    User,
+    Breakpoint,
+    /// A conditional breakpoint came true.
+    Condition,
+    /// The program read or wrote a watched range of memory.
+    Watchpoint,
+    Minimized,
+    /// Another application has focus.
+    Unfocused,
+    /// The last instruction failed or made the emulator panic.
+    Fault,
+    /// The program jumped to itself.
+    Finished,
+}
+
+impl PauseReason {
+    /// Translation key of the reason shown in the paused badge.
+    pub fn key(&self) -> &'static str {
+        match self {
+            PauseReason::User => "pause-user",
+            PauseReason::Breakpoint => "pause-breakpoint",
+            PauseReason::Condition => "pause-condition",
+            PauseReason::Watchpoint => "pause-watchpoint",
+            PauseReason::Minimized => "pause-minimized",
+            PauseReason::Unfocused => "pause-unfocused",
+            PauseReason::Fault => "pause-fault",
+            PauseReason::Finished => "pause-finished",
+        }
+    }
+}
+
+/// Pauses a running program, or resumes a paused one whatever paused it.
+fn toggle_pause(paused: &mut Option<PauseReason>) {
+    *paused = match paused {
+        Some(_) => None,
+        None => Some(PauseReason::User),
+    };
+}
+
+/// The window the GUI moves to with --detach-debugger.
+struct DebuggerWindow {
+    window: Window,
+    pixels: Pixels,
+}
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "chip-8", about = "A chip-8 emulator.")]
 struct Opt {
-    #[structopt(parse(from_os_str))]
-    input: PathBuf,
+    /// A ROM, an assembly source (.8o, .asm) or a screenshot (.png) to
+    /// resume from
+    #[structopt(
+        parse(from_os_str),
+        required_unless_one = &["tutorial", "golden-update", "compat"]
+    )]
+    input: Option<PathBuf>,
+
+    /// Walk through a bundled ROM one fetch/decode/execute step at a time
+    #[structopt(long)]
+    tutorial: bool,
+
+    /// Instructions executed per second, independent of the frame rate.
+    /// Defaults to the tickrate in the ROM's config or Octo sidecar if it
+    /// has one, else 700
+    #[structopt(long)]
+    ips: Option<f32>,
+
+    /// How many times faster the program runs while the fast-forward key
+    /// (Tab unless rebound) is held
+    #[structopt(long, default_value = "4")]
+    fast_forward: f32,
+
+    /// Skip the boot splash and start the ROM immediately
+    #[structopt(long)]
+    no_splash: bool,
+
+    /// Start paused before the program's first instruction, to set
+    /// breakpoints and look around memory first
+    #[structopt(long)]
+    start_paused: bool,
+
+    /// Pause before the instruction at this address runs, e.g.
+    /// --breakpoint 0x2A4 (repeatable)
+    #[structopt(long = "breakpoint", number_of_values = 1, parse(try_from_str = parse_number))]
+    breakpoints: Vec<usize>,
+
+    /// Pause when a condition on a register, I, PC or a timer comes true,
+    /// e.g. --break-if "V3 == 0x1F" (repeatable)
+    #[structopt(long, number_of_values = 1)]
+    break_if: Vec<Condition>,
+
+    /// Pause when the program reads or writes memory in a range, given as
+    /// START[-END][:r|w|rw], e.g. --watch 0x300-0x302:w (repeatable)
+    #[structopt(long = "watch", number_of_values = 1)]
+    watchpoints: Vec<Watchpoint>,
+
+    /// Don't sound the buzzer
+    #[structopt(long)]
+    mute: bool,
+
+    /// Pitch of the buzzer in Hz, unless an XO-CHIP program loads its own
+    /// audio pattern
+    #[structopt(long, default_value = "440")]
+    tone: f32,
+
+    /// Built-in hex font to install, with its large font. Defaults to the
+    /// ROM's config, else octo; can be switched in the settings panel
+    #[structopt(long, possible_values = FontSet::NAMES)]
+    font_set: Option<FontSet>,
+
+    /// Raw hex font file, overrides --font-set: 80 bytes of small glyphs,
+    /// optionally followed by 100 (0-9) or 160 (0-F) bytes of large ones
+    #[structopt(long, alias = "font-file", parse(from_os_str))]
+    font: Option<PathBuf>,
+
+    /// Address the font is installed at (e.g. 0x050), followed by the large
+    /// SUPER-CHIP font
+    #[structopt(long, default_value = "0x000", parse(try_from_str = parse_font_address))]
+    font_address: u16,
+
+    /// Size of emulated memory in bytes. Defaults to what the --platform
+    /// has (65536 for XO-CHIP), or 4096
+    #[structopt(long, parse(try_from_str = parse_memory_size))]
+    memory_size: Option<usize>,
+
+    /// Address the ROM is loaded and starts running at. Defaults to what
+    /// the --platform uses (0x600 for the ETI-660), or 0x200
+    #[structopt(long, parse(try_from_str = parse_number))]
+    load_address: Option<usize>,
+
+    /// How many nested subroutine calls the stack holds
+    #[structopt(long, default_value = "16")]
+    stack_depth: usize,
+
+    /// Show a speedrun practice overlay with a run timer and section
+    /// savestates (F5 to mark a section, F6 to retry it, F7 to restart)
+    #[structopt(long)]
+    practice: bool,
+
+    /// Time how long key presses take to reach the core, change the display
+    /// and be presented, and print a summary on exit
+    #[structopt(long)]
+    measure_latency: bool,
+
+    /// Pause emulation while the window is minimized
+    #[structopt(long)]
+    pause_when_minimized: bool,
+
+    /// Pause emulation, and the buzzer with it, while none of the
+    /// emulator's windows has focus
+    #[structopt(long)]
+    pause_when_unfocused: bool,
+
+    /// Narrate every executed instruction in plain English
+    #[structopt(long)]
+    explain: bool,
+
+    /// What to do on an opcode that isn't an instruction: stop, skip it,
+    /// or skip it with a warning
+    #[structopt(long, default_value = "halt", possible_values = OpcodePolicy::NAMES)]
+    unknown_opcode: OpcodePolicy,
+
+    /// What to do on a 0NNN call into COSMAC VIP machine code, which can't
+    /// be run: stop, skip it, or skip it with a warning
+    #[structopt(long, default_value = "halt", possible_values = OpcodePolicy::NAMES)]
+    machine_code: OpcodePolicy,
+
+    /// Comma-separated quirks to enable (shift, memory-increment, jump,
+    /// index-overflow, display-wait, vf-reset, clip, memory-increment-x).
+    /// Defaults to the --platform preset, then to the ROM's config
+    /// (game.ch8.toml), then to its Octo options if its sidecar has any,
+    /// then to the platform the ROM database knows it by
+    #[structopt(long)]
+    quirks: Option<Quirks>,
+
+    /// Use the quirks, memory size, load address and extra opcodes of this
+    /// interpreter family
+    #[structopt(long, possible_values = Platform::NAMES)]
+    platform: Option<Platform>,
+
+    /// Don't pick the platform of ROMs found in the built-in hash database
+    #[structopt(long)]
+    no_rom_db: bool,
+
+    /// Run a second core with these quirks side by side and report where the
+    /// displays diverge
+    #[structopt(long)]
+    compare_quirks: Option<Quirks>,
+
+    /// Run up to this many instructions in lockstep with the bundled
+    /// reference interpreter, report the first mismatch and exit
+    #[structopt(long)]
+    diff_test: Option<usize>,
+
+    /// Run every ROM in this directory headlessly (for --max-frames, by
+    /// default 900) and print a Markdown compatibility report, then exit
+    #[structopt(long, parse(from_os_str))]
+    compat: Option<PathBuf>,
+
+    /// Accept remote debugger connections (see the chip8-debugger binary)
+    /// on this address, e.g. 127.0.0.1:7070
+    #[structopt(long)]
+    debug_server: Option<SocketAddr>,
+
+    /// Show the debugger panels in a window of their own, leaving the game
+    /// window with just the display
+    #[structopt(long)]
+    detach_debugger: bool,
+
+    /// Present frames as soon as they're drawn instead of waiting for the
+    /// monitor's refresh. Emulation speed doesn't depend on either
+    #[structopt(long)]
+    no_vsync: bool,
+
+    /// Render known displays with every palette into this directory as the
+    /// reference PNGs `cargo test` compares the renderer with, then exit
+    #[structopt(long, parse(from_os_str))]
+    golden_update: Option<PathBuf>,
+
+    /// Run the ROM for this many 60 Hz frames under every quirk combination
+    /// and report the frame in which each one first changes the display,
+    /// then exit
+    #[structopt(long)]
+    quirk_scan: Option<u64>,
+
+    /// Run this many instructions without opening a window, then print the
+    /// display as block art and exit
+    #[structopt(long)]
+    dump_ascii: Option<usize>,
+
+    /// Run the ROM flat out without a window for this many seconds, print
+    /// the instructions per second and what each opcode type costs, then
+    /// exit. Timing each opcode takes a second run as long
+    #[structopt(long)]
+    benchmark: Option<f32>,
+
+    /// Run headless for at most this many 60 Hz frames. Exits with 0 on
+    /// pass, 1 if the display hash doesn't match, 2 on timeout and 3 on a
+    /// crash
+    #[structopt(long)]
+    max_frames: Option<u64>,
+
+    /// Run headless for at most this many seconds of emulated time, i.e.
+    /// 60 frames a second; runs like --max-frames
+    #[structopt(long, conflicts_with = "max-frames")]
+    max_seconds: Option<f32>,
+
+    /// With a headless run, print the final display as block art after the
+    /// outcome
+    #[structopt(long)]
+    print_display: bool,
+
+    /// Run headless until the program jumps to itself; running out of
+    /// --max-frames first is a timeout
+    #[structopt(long)]
+    until_halt: bool,
+
+    /// With a headless run, fail unless the final display has this hash (as
+    /// shown in CPU Info)
+    #[structopt(long, parse(try_from_str = parse_hash))]
+    expect_display_hash: Option<u64>,
+
+    /// With a headless run, replay keypad input from this file: one
+    /// `FRAME KEY...` line per change in held keys, frames counting from 0
+    #[structopt(long, parse(from_os_str))]
+    input_script: Option<PathBuf>,
+
+    /// Record the run and save it as an animated SVG on exit
+    #[structopt(long, parse(from_os_str))]
+    record_svg: Option<PathBuf>,
+
+    /// Write every emulated frame to this file as packed 1bpp data, for
+    /// byte-comparing runs
+    #[structopt(long, parse(from_os_str))]
+    dump_raw: Option<PathBuf>,
+
+    /// Time every instruction, show the most expensive ones in a Profiler
+    /// window and write a collapsed-stack profile for inferno/flamegraph to
+    /// this file on exit
+    #[structopt(long, parse(from_os_str))]
+    profile: Option<PathBuf>,
+
+    /// Record the run and save it as an asciinema cast on exit
+    #[structopt(long, parse(from_os_str))]
+    record_cast: Option<PathBuf>,
+
+    /// Inject random corruption at a chance per instruction: `flip:RATE`
+    /// flips bits in memory, `skip:RATE` skips instructions
+    #[structopt(long)]
+    chaos: Option<ChaosSpec>,
+
+    /// Seed for the random numbers CXNN draws; by default a new one every
+    /// run. The same seed replays the same numbers
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Seed for --chaos; the same seed replays the same corruption
+    #[structopt(long)]
+    chaos_seed: Option<u64>,
+
+    /// Make runs reproducible: seeds default to 0, and emulation advances
+    /// exactly one 60 Hz frame per redraw instead of following the wall
+    /// clock, so the same ROM and input give the same frames every time
+    #[structopt(long, conflicts_with = "crowd")]
+    deterministic: bool,
+
+    /// Stream the display to spectators connecting to this address, e.g.
+    /// 0.0.0.0:8086
+    #[structopt(long)]
+    spectate: Option<SocketAddr>,
+
+    /// Let a crowd vote on key presses by sending lines of hex keys to this
+    /// address
+    #[structopt(long)]
+    crowd: Option<SocketAddr>,
+
+    /// Keyboard keys for the keypad, 16 comma-separated names in keypad
+    /// order (1 2 3 C / 4 5 6 D / 7 8 9 E / A 0 B F). The ROM's config can
+    /// set this too
+    #[structopt(long)]
+    keypad_layout: Option<KeypadLayout>,
+
+    /// Display palette; classic unless the ROM's config picks one
+    #[structopt(long, possible_values = PalettePreset::NAMES)]
+    palette: Option<PalettePreset>,
+
+    /// GUI language
+    #[structopt(long, default_value = "en", possible_values = Lang::NAMES)]
+    lang: Lang,
+
+    /// Remap a hotkey, e.g. --bind pause=Space (repeatable)
+    #[structopt(long = "bind", number_of_values = 1)]
+    bindings: Vec<Binding>,
+}
+
+fn parse_number(s: &str) -> Result<usize, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn parse_hash(s: &str) -> Result<u64, String> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    u64::from_str_radix(hex, 16).map_err(|e| e.to_string())
+}
+
+fn parse_font_address(s: &str) -> Result<u16, String> {
+    let address = parse_number(s)?;
+    if address + font::FONT_SIZE + font::BIG_FONT_SIZE > cpu::PC_START {
+        return Err(format!(
+            "font at {:#05x} would overlap the program",
+            address
+        ));
+    }
+    Ok(address as u16)
+}
+
+fn parse_memory_size(s: &str) -> Result<usize, String> {
+    let size = parse_number(s)?;
+    if size <= cpu::PC_START || size > MemoryLayout::MAX_SIZE {
+        return Err(format!(
+            "memory size must be between {:#x} and {:#x}",
+            cpu::PC_START + 1,
+            MemoryLayout::MAX_SIZE
+        ));
+    }
+    Ok(size)
+}
+
+/// The game window's title, naming the platform and quirks in effect.
+fn window_title(setup: &Setup) -> String {
+    match setup.platform {
+        Some(platform) => format!("Chip 8 - {}, quirks: {}", platform.name(), setup.quirks),
+        None => format!("Chip 8 - quirks: {}", setup.quirks),
+    }
+}
+
+/// Whether `path` is assembly source rather than a binary ROM.
+fn is_source(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("8o" | "asm")
+    )
 }
 
 fn main() {
     env_logger::init();
     let opt = Opt::from_args();
+    let mut source_map = None;
+    let mut resume = None;
+    if let Some(dir) = &opt.golden_update {
+        if let Err(e) = golden::update(dir) {
+            eprintln!("{}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(dir) = &opt.compat {
+        match compat::scan(dir, opt.max_frames.unwrap_or(COMPAT_FRAMES)) {
+            Ok(report) => print!("{}", report),
+            Err(e) => {
+                eprintln!("{}: {}", dir.display(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut rom = match &opt.input {
+        Some(path) if !opt.tutorial && is_source(path) => {
+            let source = std::fs::read_to_string(path).unwrap();
+            match asm::assemble_program(&source) {
+                Ok(program) => {
+                    source_map = Some(program.source_map);
+                    program.rom
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(path) if !opt.tutorial && screenshot::is_screenshot(path) => {
+            match screenshot::load(path) {
+                Ok((rom, mut cpu)) => {
+                    cpu.on_unknown_opcode = opt.unknown_opcode;
+                    cpu.on_machine_code = opt.machine_code;
+                    resume = Some(cpu);
+                    rom
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(path) if !opt.tutorial => std::fs::read(path).unwrap(),
+        _ => tutorial::ROM.to_vec(),
+    };
+    let mut metadata = opt
+        .input
+        .as_deref()
+        .filter(|_| !opt.tutorial)
+        .and_then(RomMetadata::load_sidecar);
+    let options = metadata.as_ref().and_then(|metadata| metadata.options);
+    let config = opt
+        .input
+        .as_deref()
+        .filter(|_| !opt.tutorial)
+        .and_then(RomConfig::load_sidecar)
+        .unwrap_or_default();
+    let known = Some(&rom)
+        .filter(|_| !opt.no_rom_db)
+        .and_then(|rom| romdb::lookup(rom));
+    if let Some(entry) = known {
+        info!(
+            "Recognised {} as a {} ROM",
+            entry.title,
+            entry.platform.name()
+        );
+    }
+    let (quirks, quirks_origin) = opt
+        .quirks
+        .or_else(|| opt.platform.map(|platform| platform.quirks()))
+        .map(|quirks| (quirks, Origin::CommandLine))
+        .or_else(|| {
+            config
+                .quirks
+                .or_else(|| config.platform.map(|platform| platform.quirks()))
+                .map(|quirks| (quirks, Origin::Config))
+        })
+        .or_else(|| options.map(|options| (options.quirks(), Origin::OctoOptions)))
+        .or_else(|| known.map(|entry| (entry.platform.quirks(), Origin::RomDatabase)))
+        .unwrap_or_default();
+    let (platform, platform_origin) = match (opt.platform, config.platform, known) {
+        (Some(platform), _, _) => (Some(platform), Origin::CommandLine),
+        (None, Some(platform), _) => (Some(platform), Origin::Config),
+        (None, None, Some(entry)) => (Some(entry.platform), Origin::RomDatabase),
+        (None, None, None) => (None, Origin::Default),
+    };
+    let setup = Setup {
+        platform,
+        platform_origin,
+        quirks,
+        quirks_origin,
+    };
+    let layout = MemoryLayout {
+        size: opt
+            .memory_size
+            .or_else(|| platform.map(|platform| platform.memory_size()))
+            .unwrap_or(MemoryLayout::default().size),
+        program_start: opt
+            .load_address
+            .or_else(|| platform.map(|platform| platform.load_address()))
+            .unwrap_or(MemoryLayout::default().program_start),
+    };
+    let font_end = opt.font_address as usize + font::FONT_SIZE + font::BIG_FONT_SIZE;
+    if layout.program_start < font_end || layout.program_start >= layout.size {
+        eprintln!(
+            "load address must be between {:#x} and {:#x}",
+            font_end,
+            layout.size - 1
+        );
+        std::process::exit(1);
+    }
+    if let Some(address) = opt.breakpoints.iter().find(|&&a| a >= layout.size) {
+        eprintln!("breakpoint {:#x} is past the end of memory", address);
+        std::process::exit(1);
+    }
+    if let Some(watchpoint) = opt.watchpoints.iter().find(|w| !w.fits(layout.size)) {
+        eprintln!("watchpoint {} is past the end of memory", watchpoint);
+        std::process::exit(1);
+    }
+    let font_set = opt.font_set.or(config.font_set).unwrap_or_default();
+    let (font, big_font) = match &opt.font {
+        Some(path) => match font::load(path, font_set.big_data()) {
+            Ok(fonts) => fonts,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => (*font_set.data(), *font_set.big_data()),
+    };
+    let instructions_per_second = opt
+        .ips
+        .or_else(|| {
+            config
+                .tickrate
+                .or_else(|| options.and_then(|options| options.tickrate))
+                .map(|tickrate| tickrate as f32 * 60.0)
+        })
+        .unwrap_or(DEFAULT_IPS);
+    if instructions_per_second.is_nan() || instructions_per_second <= 0.0 {
+        eprintln!("--ips must be a positive number");
+        std::process::exit(1);
+    }
+    if opt.fast_forward.is_nan() || opt.fast_forward <= 0.0 {
+        eprintln!("--fast-forward must be a positive number");
+        std::process::exit(1);
+    }
+    if let Some(cpu) = &mut resume {
+        cpu.set_instructions_per_second(instructions_per_second);
+    }
+
+    let time_seed = || {
+        if opt.deterministic {
+            0
+        } else {
+            Rng::time_seed()
+        }
+    };
+    let seed = opt.seed.unwrap_or_else(time_seed);
+    info!("Random seed {}", seed);
+
+    let mut chaos = opt.chaos.map(|spec| {
+        let seed = opt.chaos_seed.unwrap_or_else(time_seed);
+        eprintln!("chaos: seed {}", seed);
+        Chaos::new(spec, seed)
+    });
+
+    let mut raw_dump = opt
+        .dump_raw
+        .as_deref()
+        .map(|path| match RawDump::create(path) {
+            Ok(dump) => dump,
+            Err(e) => {
+                eprintln!("cannot create {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        });
+
+    if let Some(steps) = opt.diff_test {
+        let outcome = difftest::run(&rom, steps);
+        println!("{}", outcome);
+        let failed = matches!(outcome, difftest::Outcome::Mismatch { .. });
+        std::process::exit(failed as i32);
+    }
+
+    let headless_cpu = || {
+        let mut cpu = Cpu::with_layout(layout);
+        cpu.quirks = quirks;
+        cpu.platform = platform;
+        cpu.set_stack_depth(opt.stack_depth);
+        cpu.load_font(&font, &big_font, opt.font_address);
+        cpu.load_rom(&rom);
+        cpu.seed_rng(seed);
+        cpu.on_unknown_opcode = opt.unknown_opcode;
+        cpu.on_machine_code = opt.machine_code;
+        cpu.set_instructions_per_second(instructions_per_second);
+        cpu
+    };
+
+    if let Some(frames) = opt.quirk_scan {
+        let boot = |quirks| {
+            let mut cpu = headless_cpu();
+            cpu.quirks = quirks;
+            cpu
+        };
+        print!("{}", quirkscan::scan(frames, boot));
+        return;
+    }
+
+    if let Some(seconds) = opt.benchmark {
+        if seconds.is_nan() || seconds <= 0.0 {
+            eprintln!("--benchmark must be a positive number of seconds");
+            std::process::exit(1);
+        }
+        let cpu = resume.take().unwrap_or_else(headless_cpu);
+        let duration = Duration::from_secs_f32(seconds);
+        print!("{}", benchmark::run(cpu, duration, instructions_per_second));
+        return;
+    }
+
+    if let Some(steps) = opt.dump_ascii {
+        let mut cpu = headless_cpu();
+        for _ in 0..steps {
+            if let Some(chaos) = &mut chaos {
+                chaos.strike(&mut cpu);
+            }
+            let frame = cpu.frame();
+            if let Err(error) = cpu.tick() {
+                eprintln!("stopped: {}", error);
+                break;
+            }
+            if let Some(dump) = raw_dump.as_mut().filter(|_| cpu.frame() != frame) {
+                dump.append(cpu.get_display()).unwrap();
+            }
+        }
+        if let Some(dump) = raw_dump {
+            dump.finish().unwrap();
+        }
+        print!("{}", cpu.get_display().to_block_art());
+        if let Some(chaos) = &chaos {
+            eprintln!("chaos: injected {}", chaos);
+        }
+        return;
+    }
+
+    if let Some(seconds) = opt.max_seconds {
+        if seconds.is_nan() || seconds < 0.0 {
+            eprintln!("--max-seconds must not be negative");
+            std::process::exit(1);
+        }
+    }
+    let max_frames = opt.max_frames.or_else(|| {
+        opt.max_seconds
+            .map(|seconds| (seconds * TIMER_HZ).round() as u64)
+    });
+    if max_frames.is_some() || opt.until_halt {
+        let script = match &opt.input_script {
+            Some(path) => match std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|script| script.parse())
+            {
+                Ok(script) => script,
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            },
+            None => InputScript::default(),
+        };
+        let mut run = headless::Run {
+            max_frames,
+            until_halt: opt.until_halt,
+            expect_display_hash: opt.expect_display_hash,
+            script,
+            raw_dump,
+            chaos,
+        };
+        let mut cpu = resume.take().unwrap_or_else(headless_cpu);
+        let outcome = run.execute(&mut cpu);
+        println!("{}", outcome);
+        if let Some(chaos) = &run.chaos {
+            eprintln!("chaos: injected {}", chaos);
+        }
+        if let Some(Err(e)) = run.raw_dump.take().map(RawDump::finish) {
+            eprintln!("failed to finish raw dump: {}", e);
+            std::process::exit(1);
+        }
+        if opt.print_display {
+            print!("{}", cpu.get_display().to_block_art());
+        }
+        std::process::exit(outcome.exit_code());
+    }
+    if opt.expect_display_hash.is_some() || opt.input_script.is_some() || opt.print_display {
+        eprintln!(
+            "--expect-display-hash, --input-script and --print-display need --max-frames, \
+             --max-seconds or --until-halt"
+        );
+        std::process::exit(1);
+    }
+
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
+    // The window is sized for the standard resolution; the pixel buffer
+    // follows the display when a program switches to high resolution.
+    let blank = Display::new();
+    let mut frame_size = if opt.compare_quirks.is_some() {
+        DisplayRenderer::compare_frame_size(&blank, &blank)
+    } else {
+        DisplayRenderer::frame_size(&blank)
+    };
     let window = {
-        let size = LogicalSize::new(Display::WIDTH as u32, Display::HEIGHT as u32);
+        let size = LogicalSize::new(frame_size.0 as u32, frame_size.1 as u32);
         WindowBuilder::new()
-            .with_title("Chip 8")
+            .with_title(window_title(&setup))
             .with_inner_size(size)
             .with_min_inner_size(size)
             .build(&event_loop)
             .unwrap()
     };
 
+    let vsync = !opt.no_vsync;
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(
-            Display::WIDTH as u32,
-            Display::HEIGHT as u32,
-            surface_texture,
-        )
-        .unwrap()
+        PixelsBuilder::new(frame_size.0 as u32, frame_size.1 as u32, surface_texture)
+            .enable_vsync(vsync)
+            .build()
+            .unwrap()
     };
 
-    let rom = std::fs::read(opt.input).unwrap();
-    let mut cpu = Cpu::new();
-    cpu.load(&rom);
-    let renderer = DisplayRenderer;
+    let mut debugger = opt.detach_debugger.then(|| {
+        let window = WindowBuilder::new()
+            .with_title("Chip 8 Debugger")
+            .with_inner_size(LogicalSize::new(800, 600))
+            .build(&event_loop)
+            .unwrap();
+        // The debugger draws nothing of its own; the pixel buffer only
+        // provides the surface the GUI renders to.
+        let size = window.inner_size();
+        let surface_texture = SurfaceTexture::new(size.width, size.height, &window);
+        let pixels = PixelsBuilder::new(1, 1, surface_texture)
+            .enable_vsync(vsync)
+            .build()
+            .unwrap();
+        DebuggerWindow { window, pixels }
+    });
 
-    let mut gui = Gui::new(&window, &pixels);
+    // Shared with the boot closure so a font set picked in the settings
+    // panel outlives loading another ROM.
+    let fonts = Rc::new(Cell::new((font, big_font)));
+    let boot_fonts = Rc::clone(&fonts);
+    let font_address = opt.font_address;
+    let stack_depth = opt.stack_depth;
+    let unknown_opcode = opt.unknown_opcode;
+    let machine_code = opt.machine_code;
+    // Flags are kept for ROMs loaded from a file; the boot closure picks up
+    // whatever the program last saved.
+    let mut rpl_path = opt
+        .input
+        .as_deref()
+        .filter(|_| !opt.tutorial)
+        .map(rpl::path);
+    let rpl_flags = Rc::new(Cell::new(
+        rpl_path.as_deref().map(rpl::load).unwrap_or_default(),
+    ));
+    let boot_flags = Rc::clone(&rpl_flags);
+    let boot = move |rom: &[u8], quirks: Quirks| {
+        let mut cpu = Cpu::with_layout(layout);
+        let (font, big_font) = boot_fonts.get();
+        cpu.load_font(&font, &big_font, font_address);
+        cpu.load_rom(rom);
+        cpu.quirks = quirks;
+        cpu.platform = platform;
+        cpu.set_stack_depth(stack_depth);
+        cpu.seed_rng(seed);
+        cpu.on_unknown_opcode = unknown_opcode;
+        cpu.on_machine_code = machine_code;
+        cpu.set_instructions_per_second(instructions_per_second);
+        cpu.rpl_flags = boot_flags.get();
+        cpu
+    };
+    let compare_quirks = opt.compare_quirks;
 
-    let mut last_render = Instant::now();
+    let mut machine;
+    let record_svg = opt.record_svg.clone();
+    let record_cast = opt.record_cast.clone();
+    let recording = record_svg.is_some() || record_cast.is_some();
+    let mut splash_until = None;
+    if opt.no_splash {
+        machine = Machine::new(resume.take().unwrap_or_else(|| boot(&rom, quirks)));
+        machine.comparison = compare_quirks.map(|quirks| Comparison::new(boot(&rom, quirks)));
+        machine.recorder = recording.then(Recorder::new);
+    } else {
+        let mut cpu = Cpu::new();
+        splash::show(&mut cpu);
+        machine = Machine::new(cpu);
+        splash_until = Some(Instant::now() + Duration::from_secs_f32(splash::DURATION_SECS));
+    }
+    machine.chaos = chaos;
+    machine.raw_dump = raw_dump;
+    machine.breakpoints = opt.breakpoints.iter().map(|&a| a as u16).collect();
+    machine.conditions = opt.break_if.clone();
+    machine.watchpoints = opt.watchpoints.clone();
+    if opt.start_paused {
+        machine.paused = Some(PauseReason::User);
+    }
+    let palette = opt.palette.or(config.palette).unwrap_or_default();
+    let mut renderer = DisplayRenderer {
+        palette: palette.palette(),
+    };
 
-    event_loop.run(move |event, _, control_flow| {
-        if let Event::RedrawRequested(_) = event {
-            renderer.draw(cpu.get_display(), pixels.get_frame());
+    let (gui_window, gui_pixels) = match &debugger {
+        Some(debugger) => (&debugger.window, &debugger.pixels),
+        None => (&window, &pixels),
+    };
+    let mut gui = Gui::new(
+        gui_window,
+        gui_pixels,
+        opt.explain,
+        palette,
+        options.and_then(|options| options.palette()),
+        font_set,
+        opt.lang,
+    );
+    let mut minimized = false;
+    let mut practice = opt.practice.then(|| Practice::new(instructions_per_second));
+    let pause_when_minimized = opt.pause_when_minimized;
+    let pause_when_unfocused = opt.pause_when_unfocused;
+    let fast_forward = opt.fast_forward;
+    let mut tutorial = opt.tutorial.then(Tutorial::new);
+    let bindings = Bindings::new(&opt.bindings);
+    let mut speed = SpeedMeter::new();
+    let mut inputs: Vec<Box<dyn Input>> = vec![Box::new(Keyboard::new(
+        opt.keypad_layout
+            .or(config.keypad_layout)
+            .unwrap_or_default(),
+    ))];
+    if let Some(addr) = opt.crowd {
+        match CrowdInput::listen(addr) {
+            Ok(crowd) => inputs.push(Box::new(crowd)),
+            Err(e) => {
+                eprintln!("cannot listen on {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let mut keys = 0;
+    let mut latency = opt.measure_latency.then(LatencyProbe::default);
+    let profile = opt.profile.clone();
+    machine.profiler = profile.is_some().then(Arc::default);
+    let mut debug_server = opt
+        .debug_server
+        .map(|addr| match DebugServer::listen(addr) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("cannot listen on {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        });
+    let buzzer = (!opt.mute).then(|| Buzzer::start(opt.tone));
+    let mut spectators = opt.spectate.map(|addr| match Spectators::listen(addr) {
+        Ok(spectators) => spectators,
+        Err(e) => {
+            eprintln!("cannot listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    });
 
-            gui.prepare(&window).expect("gui.prepare() failed");
+    let machine = Arc::new(Mutex::new(machine));
+    // With --deterministic the event loop drives emulation itself, by a
+    // clock that moves on one frame per pass.
+    let mut virtual_clock = if opt.deterministic {
+        let now = Instant::now();
+        Some((Scheduler::new(instructions_per_second, now), now))
+    } else {
+        emulation::spawn(&machine, instructions_per_second);
+        None
+    };
 
-            let render_result = pixels.render_with(|encoder, render_target, context| {
-                context.scaling_renderer.render(encoder, render_target);
-                gui.render(&window, encoder, render_target, context, &cpu)?;
+    event_loop.run(move |event, _, control_flow| {
+        if let Event::RedrawRequested(id) = event {
+            // Draw from a copy, so the emulation thread isn't kept waiting
+            // on the GPU and vsync.
+            let shown = machine.lock().unwrap().view(gui.memory_open());
+            let (cpu, comparison, paused) = (&shown.cpu, &shown.comparison, shown.paused);
+            renderer.palette = gui.palette();
+            let view = View {
+                cpu,
+                paused,
+                narrator: shown.narrator.as_ref(),
+                tutorial: tutorial.as_ref(),
+                comparison: comparison.as_ref(),
+                metadata: metadata.as_ref(),
+                source_map: source_map.as_ref(),
+                breakpoints: &shown.breakpoints,
+                conditions: &shown.conditions,
+                watchpoints: &shown.watchpoints,
+                watch_hit: shown.watch_hit.as_ref(),
+                keys,
+                practice: practice.as_ref(),
+                profiler: shown.profiler.as_deref(),
+                trace: &shown.trace,
+                fault: shown
+                    .fault
+                    .as_ref()
+                    .filter(|_| paused == Some(PauseReason::Fault)),
+                bindings: &bindings,
+                ips: speed.rate(),
+                speed: shown.speed,
+                booting: splash_until.is_some(),
+                setup: &setup,
+            };
 
+            let render_result = if id == window.id() && minimized {
+                // The OS may still ask to redraw a minimized window, which
+                // has no surface worth drawing to.
                 Ok(())
-            });
+            } else if id == window.id() {
+                let size = match &comparison {
+                    Some(comparison) => DisplayRenderer::compare_frame_size(
+                        cpu.get_display(),
+                        comparison.cpu.get_display(),
+                    ),
+                    None => DisplayRenderer::frame_size(cpu.get_display()),
+                };
+                if size != frame_size {
+                    pixels.resize_buffer(size.0 as u32, size.1 as u32);
+                    frame_size = size;
+                }
+                match &comparison {
+                    Some(comparison) => renderer.draw_compare(
+                        cpu.get_display(),
+                        comparison.cpu.get_display(),
+                        pixels.get_frame(),
+                        paused.is_some(),
+                    ),
+                    None => renderer.draw(cpu.get_display(), pixels.get_frame(), paused.is_some()),
+                }
+                if debugger.is_some() {
+                    pixels.render()
+                } else {
+                    gui.prepare(&window).expect("gui.prepare() failed");
+                    pixels.render_with(|encoder, render_target, context| {
+                        context.scaling_renderer.render(encoder, render_target);
+                        gui.render(&window, encoder, render_target, context, &view)?;
+                        Ok(())
+                    })
+                }
+            } else if let Some(DebuggerWindow { window, pixels }) = debugger
+                .as_mut()
+                .filter(|debugger| debugger.window.id() == id)
+            {
+                gui.prepare(window).expect("gui.prepare() failed");
+                pixels.render_with(|encoder, render_target, context| {
+                    // Clears the window before the GUI is drawn over it.
+                    context.scaling_renderer.render(encoder, render_target);
+                    gui.render(window, encoder, render_target, context, &view)?;
+                    Ok(())
+                })
+            } else {
+                Ok(())
+            };
 
             if render_result
                 .map_err(|e| error!("pixels.render() failed: {}", e))
@@ -84,29 +1080,370 @@ fn main() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
+            if let Some(latency) = latency.as_mut().filter(|_| id == window.id()) {
+                latency.presented();
+            }
+        }
+
+        let mut machine = machine.lock().unwrap();
+        if let (Event::MainEventsCleared, Some((scheduler, now))) = (&event, &mut virtual_clock) {
+            *now += Duration::from_secs_f32(1.0 / TIMER_HZ);
+            machine.advance(scheduler, *now);
+        }
+        if let Some(server) = debug_server.as_mut().filter(|_| splash_until.is_none()) {
+            server.poll(&mut machine);
+        }
+        let Machine {
+            cpu,
+            comparison,
+            paused,
+            fault,
+            trace,
+            breakpoints,
+            conditions,
+            watchpoints,
+            explain,
+            profiler,
+            recorder,
+            raw_dump,
+            chaos,
+            running,
+            speed: speed_multiplier,
+            step,
+            skipped,
+            executed,
+            faulted,
+            hit_breakpoint,
+            saved_flags,
+            exited,
+            ..
+        } = &mut *machine;
+        if let Event::LoopDestroyed = event {
+            if let Some(chaos) = &chaos {
+                eprintln!("chaos: injected {}", chaos);
+            }
+            if let Some(latency) = &latency {
+                eprint!("{}", latency);
+            }
+            if let (Some(profiler), Some(path)) = (&profiler, &profile) {
+                if let Err(e) = std::fs::write(path, profiler.to_collapsed()) {
+                    error!("failed to write {}: {}", path.display(), e);
+                }
+            }
+            if let Some(Err(e)) = raw_dump.take().map(RawDump::finish) {
+                error!("failed to finish raw dump: {}", e);
+            }
+            if let Some(recorder) = &recorder {
+                let exports = [
+                    (&record_svg, Recorder::to_svg as fn(&Recorder) -> String),
+                    (&record_cast, Recorder::to_cast),
+                ];
+                for (path, export) in exports {
+                    if let Some(path) = path {
+                        if let Err(e) = std::fs::write(path, export(recorder)) {
+                            error!("failed to write {}: {}", path.display(), e);
+                        }
+                    }
+                }
+            }
+            return;
         }
 
-        gui.handle_event(&window, &event);
+        match &mut debugger {
+            Some(debugger) => {
+                // The game window's input helper must not see the debugger
+                // window being resized.
+                if let Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::Resized(size),
+                } = &event
+                {
+                    if *window_id == debugger.window.id() {
+                        if size.width > 0 && size.height > 0 {
+                            debugger.pixels.resize_surface(size.width, size.height);
+                        }
+                        return;
+                    }
+                }
+                gui.handle_event(&debugger.window, &event);
+            }
+            None => gui.handle_event(&window, &event),
+        }
 
+        // Moving focus between the game and debugger windows unfocuses one
+        // just before focusing the other, so the pause doesn't stick.
+        if let Event::WindowEvent {
+            event: WindowEvent::Focused(focused),
+            ..
+        } = event
+        {
+            if pause_when_unfocused {
+                if !focused && paused.is_none() {
+                    *paused = Some(PauseReason::Unfocused);
+                } else if focused && *paused == Some(PauseReason::Unfocused) {
+                    *paused = None;
+                }
+            }
+        }
+
+        let mut paste = false;
+        let mut reset = false;
         if input.update(&event) {
             // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+            if bindings.pressed(&input, Hotkey::Quit) || input.quit() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
 
-            // Resize the window
+            input::update(&mut inputs, &input);
+
+            if bindings.pressed(&input, Hotkey::DumpAscii) {
+                print!("{}", cpu.get_display().to_block_art());
+            }
+
+            if bindings.pressed(&input, Hotkey::Help) {
+                gui.toggle_help();
+            }
+
+            paste |= bindings.pressed(&input, Hotkey::PasteRom);
+            reset |= bindings.pressed(&input, Hotkey::ResetRom);
+
+            if bindings.pressed(&input, Hotkey::Screenshot) {
+                let secs = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let path = PathBuf::from(format!("screenshot-{}.png", secs));
+                match screenshot::save(&path, cpu, &rom, gui.palette()) {
+                    Ok(()) => info!("Saved {}", path.display()),
+                    Err(e) => error!("failed to write {}: {}", path.display(), e),
+                }
+            }
+
+            if let Some(practice) = practice.as_mut().filter(|_| splash_until.is_none()) {
+                if bindings.pressed(&input, Hotkey::SaveSection) {
+                    practice.save(cpu);
+                }
+                if bindings.pressed(&input, Hotkey::RetrySection) {
+                    if let Some(state) = practice.retry() {
+                        *cpu = state;
+                    }
+                }
+                if bindings.pressed(&input, Hotkey::ResetRun) {
+                    practice.reset();
+                    *cpu = boot(&rom, quirks);
+                }
+            }
+
+            if bindings.pressed(&input, Hotkey::SlowMotion) {
+                gui.toggle_slow_motion();
+            }
+            *speed_multiplier = if bindings.held(&input, Hotkey::FastForward) {
+                fast_forward
+            } else {
+                gui.speed()
+            };
+
+            if bindings.pressed(&input, Hotkey::Pause) {
+                toggle_pause(paused);
+            }
+            if paused.is_some() && bindings.pressed(&input, Hotkey::FrameAdvance) {
+                *step = Some(Step::Frame);
+            }
+            if paused.is_some() && bindings.pressed(&input, Hotkey::Step) {
+                *step = Some(Step::Instruction);
+            }
+
+            // Resize the window; a zero size means it was minimized
             if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
+                let was_minimized = minimized;
+                minimized = size.width == 0 || size.height == 0;
+                if pause_when_minimized && minimized != was_minimized {
+                    if minimized && paused.is_none() {
+                        *paused = Some(PauseReason::Minimized);
+                    } else if !minimized && *paused == Some(PauseReason::Minimized) {
+                        *paused = None;
+                    }
+                }
+                if !minimized {
+                    pixels.resize_surface(size.width, size.height);
+                }
+            }
+        }
+
+        for action in gui.take_actions() {
+            match action {
+                Action::PasteRom => paste = true,
+                Action::ResetRom => reset = true,
+                Action::TogglePause => toggle_pause(paused),
+                Action::Step => {
+                    if paused.is_some() {
+                        *step = Some(Step::Instruction);
+                    }
+                }
+                Action::SelectFontSet(set) => {
+                    info!("Switching to the {} font", set.name());
+                    fonts.set((*set.data(), *set.big_data()));
+                    cpu.load_font(set.data(), set.big_data(), font_address);
+                    reset = true;
+                }
+                Action::TutorialNext => {
+                    if let Some(tutorial) = &mut tutorial {
+                        tutorial.advance(cpu);
+                    }
+                }
+                Action::WriteMemory { address, byte } => cpu.write_memory(address, byte),
+                Action::ClearHeatmap => cpu.clear_heatmap(),
+                Action::ToggleBreakpoint(address) => {
+                    match breakpoints.iter().position(|a| *a == address) {
+                        Some(i) => {
+                            breakpoints.remove(i);
+                        }
+                        None => breakpoints.push(address),
+                    }
+                }
+                Action::AddCondition(condition) => conditions.push(condition),
+                Action::RemoveCondition(i) => {
+                    if i < conditions.len() {
+                        conditions.remove(i);
+                    }
+                }
+                Action::AddWatchpoint(watchpoint) => watchpoints.push(watchpoint),
+                Action::RemoveWatchpoint(i) => {
+                    if i < watchpoints.len() {
+                        watchpoints.remove(i);
+                    }
+                }
+                Action::ExportSprite { address, rows } => {
+                    let start = address as usize;
+                    let bytes: Vec<String> = cpu.memory()[start..start + rows as usize]
+                        .iter()
+                        .map(|byte| format!("0x{:02X}", byte))
+                        .collect();
+                    println!(": sprite-{:03X}\n  {}", address, bytes.join(" "));
+                }
+            }
+        }
+
+        if paste && splash_until.is_none() {
+            match clipboard::read().and_then(|text| clipboard::parse_hex(&text)) {
+                Ok(pasted) => {
+                    info!("Loaded {} bytes from the clipboard", pasted.len());
+                    rom = pasted;
+                    metadata = None;
+                    rpl_path = None;
+                    rpl_flags.set(Default::default());
+                    source_map = None;
+                    tutorial = None;
+                    breakpoints.clear();
+                    trace.clear();
+                    *fault = None;
+                    *paused = None;
+                    *cpu = boot(&rom, quirks);
+                    *comparison = compare_quirks.map(|quirks| Comparison::new(boot(&rom, quirks)));
+                    if let Some(practice) = &mut practice {
+                        practice.reset();
+                    }
+                }
+                Err(e) => error!("Cannot paste ROM: {}", e),
+            }
+        }
+
+        if reset && splash_until.is_none() {
+            info!("Resetting the ROM");
+            cpu.reset();
+            cpu.load_rom(&rom);
+            trace.clear();
+            *fault = None;
+            if *paused == Some(PauseReason::Fault) {
+                *paused = None;
+            }
+            *comparison = compare_quirks.map(|quirks| Comparison::new(boot(&rom, quirks)));
+            if let Some(practice) = &mut practice {
+                practice.reset();
+            }
+            if let Some(tutorial) = &mut tutorial {
+                *tutorial = Tutorial::new();
+            }
+        }
+
+        let previous_keys = keys;
+        keys = input::held(&mut inputs) | gui.pointer_keys();
+        cpu.keys = keys;
+        if let Some(latency) = &mut latency {
+            if keys & !previous_keys != 0 {
+                latency.key_pressed(cpu.get_display().hash());
             }
         }
 
         let now = Instant::now();
-        if (now - last_render) > Duration::from_secs_f32(1. / 15.) {
-            last_render = now;
-            cpu.tick();
+        if let Some(deadline) = splash_until {
+            if now >= deadline {
+                splash_until = None;
+                *cpu = resume.take().unwrap_or_else(|| boot(&rom, quirks));
+                *comparison = compare_quirks.map(|quirks| Comparison::new(boot(&rom, quirks)));
+                *recorder = recording.then(Recorder::new);
+            }
+        }
+        *running = splash_until.is_none() && tutorial.is_none();
+        *explain = gui.explain_open();
+        if std::mem::take(faulted) {
+            gui.show_fault();
+        }
+        if std::mem::take(hit_breakpoint) {
+            gui.show_breakpoint();
+        }
+        if let Some(flags) = saved_flags.take() {
+            rpl_flags.set(flags);
+            if let Some(path) = &rpl_path {
+                if let Err(e) = rpl::save(path, &flags) {
+                    error!("failed to save RPL flags to {}: {}", path.display(), e);
+                }
+            }
+        }
+        if *exited {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+        let skipped = std::mem::take(skipped);
+        if !skipped.is_zero() {
+            info!(
+                "Skipped {:.1} s of emulated time after a stall",
+                skipped.as_secs_f32()
+            );
+            gui.show_time_skipped(skipped);
+        }
+        let executed = std::mem::take(executed);
+        speed.record(executed);
+        if let Some(practice) = &mut practice {
+            practice.record(executed);
+        }
+        if let Some(latency) = latency.as_mut().filter(|_| executed > 0) {
+            latency.executed(cpu.get_display().hash());
+        }
+        if let Some(spectators) = &mut spectators {
+            spectators.broadcast(cpu.get_display(), keys);
+        }
+        if let Some(buzzer) = &buzzer {
+            buzzer.set(cpu.is_beeping() && paused.is_none() && splash_until.is_none());
+            buzzer.set_tone(match cpu.audio_pattern {
+                Some(bits) => Tone::Pattern {
+                    bits,
+                    rate: cpu.playback_rate(),
+                },
+                None => Tone::Square,
+            });
         }
 
-        window.request_redraw();
+        if minimized {
+            // Nothing to draw; wake up just often enough to keep the buzzer
+            // and spectators in step with the emulation thread.
+            *control_flow = ControlFlow::WaitUntil(now + MINIMIZED_WAKEUP);
+        } else {
+            *control_flow = ControlFlow::Poll;
+            window.request_redraw();
+        }
+        if let Some(debugger) = &debugger {
+            debugger.window.request_redraw();
+        }
     })
 }
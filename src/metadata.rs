@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::json::{self, Value};
+use crate::octo::OctoOptions;
+
+/// The hex keypad as laid out on the COSMAC VIP.
+pub const KEYPAD: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// Descriptive information about a ROM, as published alongside programs in the
+/// CHIP-8 Archive and by Octo.
+#[derive(Debug, Default, Clone)]
+pub struct RomMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub platform: Option<String>,
+    pub options: Option<OctoOptions>,
+    controls: Vec<(u8, String)>,
+}
+
+impl RomMetadata {
+    /// Reads the sidecar next to `rom` (`game.json` or `game.ch8.json`), if any.
+    pub fn load_sidecar(rom: &Path) -> Option<Self> {
+        let path = sidecar_path(rom, "json")?;
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) => {
+                warn!("Could not read {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        match json::parse(&source) {
+            Ok(value) => Some(Self::from_json(&value)),
+            Err(err) => {
+                warn!("Ignoring malformed {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    fn from_json(value: &Value) -> Self {
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| value.get(key)?.as_str())
+                .map(str::to_string)
+        };
+        let authors = match value.get("authors").or_else(|| value.get("author")) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            Some(Value::String(author)) => vec![author.clone()],
+            _ => Vec::new(),
+        };
+        let mut controls: Vec<(u8, String)> = ["controls", "keys"]
+            .iter()
+            .filter_map(|key| value.get(key)?.as_object())
+            .flatten()
+            .filter_map(|(name, hint)| {
+                let hint = match hint {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    _ => return None,
+                };
+                // Accept both `"5": "up"` and Octo-style `"up": "5"`.
+                match (parse_key(name), parse_key(&hint)) {
+                    (Some(key), _) => Some((key, hint)),
+                    (None, Some(key)) => Some((key, name.clone())),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+        controls.sort_by_key(|(key, _)| *key);
+        Self {
+            title: text(&["title", "name"]),
+            authors,
+            description: text(&["description", "desc"]),
+            platform: text(&["platform"]),
+            options: OctoOptions::from_json(value.get("options").unwrap_or(value)),
+            controls,
+        }
+    }
+
+    /// What the ROM uses `key` for, if the sidecar says.
+    pub fn control(&self, key: u8) -> Option<&str> {
+        self.controls
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, hint)| hint.as_str())
+    }
+}
+
+/// The file with `extension` next to `rom`, either replacing its extension
+/// (`game.json`) or appended to it (`game.ch8.json`), if one exists.
+pub fn sidecar_path(rom: &Path, extension: &str) -> Option<PathBuf> {
+    let mut appended = rom.as_os_str().to_owned();
+    appended.push(".");
+    appended.push(extension);
+    [rom.with_extension(extension), PathBuf::from(appended)]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+fn parse_key(s: &str) -> Option<u8> {
+    let s = s.trim();
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    match u8::from_str_radix(s, 16) {
+        Ok(key) if key < 16 => Some(key),
+        _ => None,
+    }
+}
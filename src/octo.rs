@@ -0,0 +1,109 @@
+//! Octo's per-program "options": the quirks, speed and colors a ROM was
+//! written against, as saved in Octo cartridges and the CHIP-8 Archive.
+
+use crate::json::Value;
+use crate::palette::{Color, Palette};
+use crate::quirks::Quirks;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OctoOptions {
+    /// Instructions per 60 Hz frame.
+    pub tickrate: Option<u32>,
+    shift_quirks: bool,
+    load_store_quirks: bool,
+    jump_quirks: bool,
+    vblank_quirks: bool,
+    logic_quirks: bool,
+    clip_quirks: bool,
+    palette: Option<Palette>,
+}
+
+impl OctoOptions {
+    /// Reads an options object; returns `None` if it has no Octo settings.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        const KEYS: [&str; 11] = [
+            "tickrate",
+            "shiftQuirks",
+            "loadStoreQuirks",
+            "jumpQuirks",
+            "vBlankQuirks",
+            "logicQuirks",
+            "clipQuirks",
+            "backgroundColor",
+            "fillColor",
+            "fillColor2",
+            "blendColor",
+        ];
+        if !KEYS.iter().any(|key| value.get(key).is_some()) {
+            return None;
+        }
+        let flag = |key| value.get(key).and_then(Value::as_bool).unwrap_or(false);
+        let color = |key| value.get(key).and_then(Value::as_str).and_then(parse_color);
+        let palette = match (color("backgroundColor"), color("fillColor")) {
+            (Some(background), Some(fill)) => Some(Palette {
+                colors: [
+                    background,
+                    fill,
+                    color("fillColor2").unwrap_or(fill),
+                    color("blendColor").unwrap_or(fill),
+                ],
+            }),
+            _ => None,
+        };
+        Some(Self {
+            tickrate: value
+                .get("tickrate")
+                .and_then(Value::as_f64)
+                .filter(|rate| *rate >= 1.0)
+                .map(|rate| rate as u32),
+            shift_quirks: flag("shiftQuirks"),
+            load_store_quirks: flag("loadStoreQuirks"),
+            jump_quirks: flag("jumpQuirks"),
+            vblank_quirks: flag("vBlankQuirks"),
+            logic_quirks: flag("logicQuirks"),
+            clip_quirks: flag("clipQuirks"),
+            palette,
+        })
+    }
+
+    /// The equivalent quirks. Octo's shift and load/store flags name the
+    /// deviations from the COSMAC VIP, so for those an unset flag means VIP
+    /// behaviour; the others turn the VIP behaviour on.
+    pub fn quirks(&self) -> Quirks {
+        Quirks {
+            shift_uses_vy: !self.shift_quirks,
+            memory_increments_i: !self.load_store_quirks,
+            jump_uses_vx: self.jump_quirks,
+            index_overflow_sets_vf: false,
+            display_wait: self.vblank_quirks,
+            logic_resets_vf: self.logic_quirks,
+            clip_sprites: self.clip_quirks,
+            memory_increments_i_by_x: false,
+        }
+    }
+
+    pub fn palette(&self) -> Option<Palette> {
+        self.palette
+    }
+}
+
+/// Parses `#RRGGBB` (or `#RGB`) colors as written by Octo.
+fn parse_color(s: &str) -> Option<Color> {
+    let hex = s.trim().strip_prefix('#').filter(|hex| hex.is_ascii())?;
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        6 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+            0xFF,
+        ]),
+        3 => Some([
+            channel(&hex[0..1])? * 0x11,
+            channel(&hex[1..2])? * 0x11,
+            channel(&hex[2..3])? * 0x11,
+            0xFF,
+        ]),
+        _ => None,
+    }
+}
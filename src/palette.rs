@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+pub type Color = [u8; 4];
+
+/// Display colors, indexed by plane combination: background, first plane,
+/// second plane, and both planes. Single-plane ROMs only use the first two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Color; 4],
+}
+
+impl Palette {
+    pub fn background(&self) -> Color {
+        self.colors[0]
+    }
+
+    /// Color used to call out pixels, e.g. differences between two displays.
+    pub fn highlight(&self) -> Color {
+        self.colors[3]
+    }
+}
+
+/// Built-in palettes. The colorblind presets draw from the Okabe-Ito set,
+/// picking pairs that stay distinguishable under the given deficiency.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PalettePreset {
+    #[default]
+    Classic,
+    HighContrast,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl PalettePreset {
+    pub const ALL: [PalettePreset; 5] = [
+        PalettePreset::Classic,
+        PalettePreset::HighContrast,
+        PalettePreset::Deuteranopia,
+        PalettePreset::Protanopia,
+        PalettePreset::Tritanopia,
+    ];
+
+    pub const NAMES: &'static [&'static str] = &[
+        "classic",
+        "high-contrast",
+        "deuteranopia",
+        "protanopia",
+        "tritanopia",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn palette(&self) -> Palette {
+        const BLACK: Color = [0x00, 0x00, 0x00, 0xFF];
+        const WHITE: Color = [0xFF, 0xFF, 0xFF, 0xFF];
+        let colors = match self {
+            PalettePreset::Classic => [
+                BLACK,
+                WHITE,
+                [0xAA, 0xAA, 0xAA, 0xFF],
+                [0xFF, 0x30, 0x30, 0xFF],
+            ],
+            PalettePreset::HighContrast => [
+                BLACK,
+                [0xFF, 0xFF, 0x00, 0xFF],
+                [0x00, 0xFF, 0xFF, 0xFF],
+                WHITE,
+            ],
+            PalettePreset::Deuteranopia => [
+                BLACK,
+                [0xE6, 0x9F, 0x00, 0xFF],
+                [0x56, 0xB4, 0xE9, 0xFF],
+                [0xF0, 0xE4, 0x42, 0xFF],
+            ],
+            PalettePreset::Protanopia => [
+                BLACK,
+                [0x56, 0xB4, 0xE9, 0xFF],
+                [0xF0, 0xE4, 0x42, 0xFF],
+                WHITE,
+            ],
+            PalettePreset::Tritanopia => [
+                BLACK,
+                [0xCC, 0x79, 0xA7, 0xFF],
+                [0x00, 0x9E, 0x73, 0xFF],
+                WHITE,
+            ],
+        };
+        Palette { colors }
+    }
+}
+
+impl FromStr for PalettePreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown palette: {}", s))
+    }
+}
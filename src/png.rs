@@ -0,0 +1,115 @@
+//! A minimal PNG writer: 8-bit RGBA, stored (uncompressed) deflate blocks.
+//! The output is fully determined by the pixels, so files can be compared
+//! byte for byte.
+
+/// Encodes `rgba` (4 bytes per pixel, row by row) as a PNG.
+pub fn encode(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    encode_with_text(width, height, rgba, &[])
+}
+
+/// Like [`encode`], adding a `tEXt` chunk for every keyword/text pair.
+pub fn encode_with_text(
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+    text: &[(&str, &str)],
+) -> Vec<u8> {
+    assert_eq!(rgba.len(), width * height * 4);
+
+    // Each scanline is prefixed with filter type 0 (none).
+    let mut raw = Vec::with_capacity((width * 4 + 1) * height);
+    for row in rgba.chunks_exact(width * 4) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    header.extend([8, 6, 0, 0, 0]); // 8-bit RGBA, deflate, no filter, no interlace
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    for (keyword, value) in text {
+        let mut data = keyword.as_bytes().to_vec();
+        data.push(0);
+        data.extend_from_slice(value.as_bytes());
+        chunk(&mut png, b"tEXt", &data);
+    }
+    chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Reads the keyword/text pairs from the `tEXt` chunks of a PNG.
+pub fn read_text(png: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut rest = png
+        .strip_prefix(b"\x89PNG\r\n\x1a\n")
+        .ok_or("not a PNG file")?;
+    let mut text = Vec::new();
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind = &rest[4..8];
+        let data = rest.get(8..8 + len).ok_or("truncated PNG chunk")?;
+        if kind == b"tEXt" {
+            if let Some(split) = data.iter().position(|&b| b == 0) {
+                // tEXt is Latin-1, which maps byte for byte onto chars.
+                let latin1 = |bytes: &[u8]| bytes.iter().map(|&b| b as char).collect();
+                text.push((latin1(&data[..split]), latin1(&data[split + 1..])));
+            }
+        }
+        rest = &rest[(12 + len).min(rest.len())..];
+    }
+    Ok(text)
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+        out.push(last);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use crate::cpu::Cpu;
+
+/// A savestate marking the start of a section of a run.
+struct Section {
+    state: Cpu,
+    instructions: u64,
+}
+
+/// Speedrun practice: a timer since reset and savestates at section starts
+/// to retry from. Time is emulated time, so it stands still while paused.
+pub struct Practice {
+    instructions_per_second: f32,
+    instructions: u64,
+    sections: Vec<Section>,
+}
+
+impl Practice {
+    pub fn new(instructions_per_second: f32) -> Self {
+        Self {
+            instructions_per_second,
+            instructions: 0,
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, executed: u32) {
+        self.instructions += executed as u64;
+    }
+
+    /// Starts over from the beginning of the ROM.
+    pub fn reset(&mut self) {
+        self.instructions = 0;
+        self.sections.clear();
+    }
+
+    /// Marks the start of a new section at the current state.
+    pub fn save(&mut self, cpu: &Cpu) {
+        self.sections.push(Section {
+            state: cpu.clone(),
+            instructions: self.instructions,
+        });
+    }
+
+    /// The state to retry the current section from, if one was marked.
+    pub fn retry(&mut self) -> Option<Cpu> {
+        let section = self.sections.last()?;
+        self.instructions = section.instructions;
+        Some(section.state.clone())
+    }
+
+    /// The current section, counting from 1.
+    pub fn section(&self) -> usize {
+        self.sections.len() + 1
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.time(self.instructions)
+    }
+
+    pub fn section_elapsed(&self) -> Duration {
+        let start = self
+            .sections
+            .last()
+            .map_or(0, |section| section.instructions);
+        self.time(self.instructions - start)
+    }
+
+    /// 60 Hz frames since reset.
+    pub fn frames(&self) -> u64 {
+        (self.elapsed().as_secs_f64() * 60.0) as u64
+    }
+
+    fn time(&self, instructions: u64) -> Duration {
+        Duration::from_secs_f64(instructions as f64 / self.instructions_per_second as f64)
+    }
+}
+
+/// Formats a duration as `m:ss.cc`.
+pub fn format_time(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
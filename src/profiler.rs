@@ -0,0 +1,84 @@
+//! Measures host time spent executing each instruction, grouped by opcode
+//! type and by ROM address.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::disasm;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Cost {
+    pub count: u64,
+    pub total: Duration,
+}
+
+impl Cost {
+    fn add(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+    }
+
+    /// Average time per execution.
+    pub fn mean(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Profiler {
+    /// Keyed by opcode pattern (`DXYN`) and address.
+    costs: HashMap<(&'static str, u16), Cost>,
+}
+
+impl Profiler {
+    /// Records that the instruction `opcode` at `address` took `elapsed`.
+    pub fn record(&mut self, address: u16, opcode: u16, elapsed: Duration) {
+        let pattern = disasm::lookup(opcode).map_or("????", |info| info.pattern);
+        self.costs
+            .entry((pattern, address))
+            .or_default()
+            .add(elapsed);
+    }
+
+    /// The `n` most expensive opcode types, by total time.
+    pub fn top_opcodes(&self, n: usize) -> Vec<(&'static str, Cost)> {
+        let mut by_pattern: HashMap<&'static str, Cost> = HashMap::new();
+        for ((pattern, _), cost) in &self.costs {
+            let total = by_pattern.entry(pattern).or_default();
+            total.count += cost.count;
+            total.total += cost.total;
+        }
+        top(by_pattern.into_iter().collect(), n)
+    }
+
+    /// The `n` most expensive instructions, by total time.
+    pub fn top_addresses(&self, n: usize) -> Vec<((&'static str, u16), Cost)> {
+        top(self.costs.iter().map(|(k, v)| (*k, *v)).collect(), n)
+    }
+
+    /// The profile in the collapsed-stack format read by inferno and
+    /// flamegraph.pl: one `chip8;PATTERN;ADDRESS NANOSECONDS` line per
+    /// instruction.
+    pub fn to_collapsed(&self) -> String {
+        let mut entries: Vec<_> = self.costs.iter().collect();
+        entries.sort_by_key(|((pattern, address), _)| (*pattern, *address));
+        let mut out = String::new();
+        for ((pattern, address), cost) in entries {
+            let _ = writeln!(
+                out,
+                "chip8;{};{:#05x} {}",
+                pattern,
+                address,
+                cost.total.as_nanos()
+            );
+        }
+        out
+    }
+}
+
+fn top<K>(mut entries: Vec<(K, Cost)>, n: usize) -> Vec<(K, Cost)> {
+    entries.sort_by_key(|(_, cost)| std::cmp::Reverse(cost.total));
+    entries.truncate(n);
+    entries
+}
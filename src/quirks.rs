@@ -0,0 +1,282 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cpu::{MemoryLayout, PC_START};
+
+/// Behaviours that differ between CHIP-8 interpreters.
+///
+/// Every flag defaults to `false`, which is the behaviour this emulator has
+/// always had.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8XY6/8XYE shift VY into VX instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// FX55/FX65 leave I pointing past the last register, as on the COSMAC VIP.
+    pub memory_increments_i: bool,
+    /// BNNN is read as BXNN and jumps to XNN + VX.
+    pub jump_uses_vx: bool,
+    /// FX1E sets VF when I goes past 0xFFF, as on the Amiga interpreter.
+    pub index_overflow_sets_vf: bool,
+    /// DXYN waits for the start of the next 60Hz frame, like the COSMAC VIP
+    /// waiting for vertical blank, so at most one sprite is drawn per frame.
+    pub display_wait: bool,
+    /// 8XY1/8XY2/8XY3 reset VF to 0, as on the COSMAC VIP.
+    pub logic_resets_vf: bool,
+    /// DXYN clips sprites at the screen edges instead of wrapping them
+    /// around to the other side.
+    pub clip_sprites: bool,
+    /// FX55/FX65 advance I by X, leaving it on the last register's byte, as
+    /// on CHIP-48. Takes precedence over `memory_increments_i`.
+    pub memory_increments_i_by_x: bool,
+}
+
+impl Quirks {
+    /// Every possible combination of quirks, starting with none.
+    pub fn combinations() -> impl Iterator<Item = Quirks> {
+        (0..=u8::MAX).map(Quirks::from_bits)
+    }
+
+    /// The quirks packed one bit each, in the order of [`Quirks::combinations`].
+    pub fn bits(&self) -> u8 {
+        self.flags()
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, (_, on))| bits | (*on as u8) << i)
+    }
+
+    pub fn from_bits(bits: u8) -> Quirks {
+        Quirks {
+            shift_uses_vy: bits & 1 != 0,
+            memory_increments_i: bits & 2 != 0,
+            jump_uses_vx: bits & 4 != 0,
+            index_overflow_sets_vf: bits & 8 != 0,
+            display_wait: bits & 16 != 0,
+            logic_resets_vf: bits & 32 != 0,
+            clip_sprites: bits & 64 != 0,
+            memory_increments_i_by_x: bits & 128 != 0,
+        }
+    }
+
+    /// How far FX55/FX65 move I after touching VX and the registers below.
+    pub fn memory_increment(&self, x: u8) -> u16 {
+        if self.memory_increments_i_by_x {
+            x as u16
+        } else if self.memory_increments_i {
+            x as u16 + 1
+        } else {
+            0
+        }
+    }
+
+    fn flags(&self) -> [(&'static str, bool); 8] {
+        [
+            ("shift", self.shift_uses_vy),
+            ("memory-increment", self.memory_increments_i),
+            ("jump", self.jump_uses_vx),
+            ("index-overflow", self.index_overflow_sets_vf),
+            ("display-wait", self.display_wait),
+            ("vf-reset", self.logic_resets_vf),
+            ("clip", self.clip_sprites),
+            ("memory-increment-x", self.memory_increments_i_by_x),
+        ]
+    }
+}
+
+/// A family of interpreters whose quirks are known, for picking them all at
+/// once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// The original COSMAC VIP interpreter.
+    Chip8,
+    /// SUPER-CHIP 1.1 on the HP 48.
+    Schip,
+    /// Octo's XO-CHIP.
+    XoChip,
+    /// MegaChip, SUPER-CHIP with a 256x192 color screen.
+    MegaChip,
+    /// The COSMAC VIP's two-page hi-res CHIP-8, with a 64x64 screen.
+    HiresChip8,
+    /// CHIP-48 on the HP 48, which SUPER-CHIP grew out of.
+    Chip48,
+    /// The ETI-660 learning computer, whose programs start at 0x600.
+    Eti660,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 7] = [
+        Platform::Chip8,
+        Platform::Schip,
+        Platform::XoChip,
+        Platform::MegaChip,
+        Platform::HiresChip8,
+        Platform::Chip48,
+        Platform::Eti660,
+    ];
+
+    pub const NAMES: &'static [&'static str] = &[
+        "chip8",
+        "schip",
+        "xochip",
+        "megachip",
+        "hires-chip8",
+        "chip48",
+        "eti660",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    /// Bytes of memory programs for this platform expect.
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Platform::Chip8
+            | Platform::Schip
+            | Platform::HiresChip8
+            | Platform::Chip48
+            | Platform::Eti660 => 4096,
+            Platform::XoChip | Platform::MegaChip => 0x10000,
+        }
+    }
+
+    /// Where programs for this platform are loaded and start running.
+    pub fn load_address(&self) -> usize {
+        match self {
+            Platform::Eti660 => 0x600,
+            _ => PC_START,
+        }
+    }
+
+    /// Memory as programs for this platform expect it laid out.
+    pub fn memory_layout(&self) -> MemoryLayout {
+        MemoryLayout {
+            size: self.memory_size(),
+            program_start: self.load_address(),
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Chip8 | Platform::HiresChip8 | Platform::Eti660 => Quirks {
+                shift_uses_vy: true,
+                memory_increments_i: true,
+                display_wait: true,
+                logic_resets_vf: true,
+                clip_sprites: true,
+                ..Quirks::default()
+            },
+            Platform::Schip | Platform::MegaChip => Quirks {
+                jump_uses_vx: true,
+                clip_sprites: true,
+                ..Quirks::default()
+            },
+            // The HP 48 port shifts VX in place and jumps to XNN + VX, and
+            // like the SUPER-CHIP after it has no vblank to wait for. Unlike
+            // SUPER-CHIP it still moves I on FX55/FX65, one byte short.
+            Platform::Chip48 => Quirks {
+                jump_uses_vx: true,
+                clip_sprites: true,
+                memory_increments_i_by_x: true,
+                ..Quirks::default()
+            },
+            Platform::XoChip => Quirks {
+                shift_uses_vy: true,
+                memory_increments_i: true,
+                ..Quirks::default()
+            },
+        }
+    }
+}
+
+/// Where the platform or quirks of a run were picked from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    CommandLine,
+    /// The ROM's TOML config.
+    Config,
+    /// The Octo options in the ROM's JSON sidecar.
+    OctoOptions,
+    RomDatabase,
+    #[default]
+    Default,
+}
+
+impl Origin {
+    /// Translation key of the origin shown in CPU Info.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Origin::CommandLine => "origin-command-line",
+            Origin::Config => "origin-config",
+            Origin::OctoOptions => "origin-octo-options",
+            Origin::RomDatabase => "origin-rom-database",
+            Origin::Default => "origin-default",
+        }
+    }
+}
+
+/// The platform and quirks a run uses, and where each came from, so users
+/// can see why a ROM behaves the way it does.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Setup {
+    pub platform: Option<Platform>,
+    pub platform_origin: Origin,
+    pub quirks: Quirks,
+    pub quirks_origin: Origin,
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "chip8" => Ok(Platform::Chip8),
+            "schip" => Ok(Platform::Schip),
+            "xochip" => Ok(Platform::XoChip),
+            "megachip" => Ok(Platform::MegaChip),
+            "hires-chip8" => Ok(Platform::HiresChip8),
+            "chip48" => Ok(Platform::Chip48),
+            "eti660" => Ok(Platform::Eti660),
+            _ => Err(format!("unknown platform: {}", s)),
+        }
+    }
+}
+
+impl FromStr for Quirks {
+    type Err = String;
+
+    /// Parses a comma-separated list of quirks to enable, e.g. `shift,jump`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quirks = Quirks::default();
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "shift" => quirks.shift_uses_vy = true,
+                "memory-increment" => quirks.memory_increments_i = true,
+                "jump" => quirks.jump_uses_vx = true,
+                "index-overflow" => quirks.index_overflow_sets_vf = true,
+                "display-wait" => quirks.display_wait = true,
+                "vf-reset" => quirks.logic_resets_vf = true,
+                "clip" => quirks.clip_sprites = true,
+                "memory-increment-x" => quirks.memory_increments_i_by_x = true,
+                _ => return Err(format!("unknown quirk: {}", name)),
+            }
+        }
+        Ok(quirks)
+    }
+}
+
+impl fmt::Display for Quirks {
+    /// Formats the enabled quirks the same way they are parsed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled: Vec<&str> = self
+            .flags()
+            .iter()
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect();
+        if enabled.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&enabled.join(","))
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::cpu::{Cpu, CpuError};
+use crate::quirks::Quirks;
+
+/// How one quirk combination fared against the run with no quirks.
+pub enum Verdict {
+    Identical,
+    Diverged { frame: u64 },
+    Crashed { frame: u64 },
+}
+
+pub struct Report {
+    frames: u64,
+    results: Vec<(Quirks, Verdict)>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self
+            .results
+            .iter()
+            .map(|(quirks, _)| quirks.to_string())
+            .collect();
+        let width = names.iter().map(String::len).max().unwrap_or(0);
+        writeln!(
+            f,
+            "{:<width$} result after {} frames",
+            "quirks", self.frames
+        )?;
+        for ((quirks, verdict), name) in self.results.iter().zip(&names) {
+            let verdict = match verdict {
+                Verdict::Identical if *quirks == Quirks::default() => "baseline".to_string(),
+                Verdict::Identical => "same display as no quirks".to_string(),
+                Verdict::Diverged { frame } => format!("display diverges at frame {}", frame),
+                Verdict::Crashed { frame } => format!("crashed at frame {}", frame),
+            };
+            writeln!(f, "{:<width$} {}", name, verdict)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the CPU `boot` sets up for each quirk combination for `frames` 60 Hz
+/// frames, and compares the display each frame with the run that has no
+/// quirks enabled.
+pub fn scan(frames: u64, boot: impl Fn(Quirks) -> Cpu) -> Report {
+    // A crashing combination is part of the report, not a reason to stop.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut baseline = Vec::new();
+    let baseline_crash = run(boot(Quirks::default()), frames, |frame, display| {
+        baseline.push((frame, display));
+        true
+    });
+
+    let mut results = vec![(Quirks::default(), baseline_crash)];
+    for quirks in Quirks::combinations().filter(|quirks| *quirks != Quirks::default()) {
+        let mut diverged = None;
+        let mut observed = baseline.iter();
+        let verdict = run(boot(quirks), frames, |frame, display| {
+            if observed.next() != Some(&(frame, display)) {
+                diverged = Some(frame);
+            }
+            diverged.is_none()
+        });
+        let verdict = match (verdict, diverged) {
+            (Verdict::Identical, Some(frame)) => Verdict::Diverged { frame },
+            (verdict, _) => verdict,
+        };
+        results.push((quirks, verdict));
+    }
+
+    panic::set_hook(hook);
+    Report { frames, results }
+}
+
+/// Runs `cpu` until `frames` frames have passed, handing the frame and the
+/// display hash to `observe` on the instruction that starts each frame,
+/// until it returns `false`.
+fn run(mut cpu: Cpu, frames: u64, mut observe: impl FnMut(u64, u64) -> bool) -> Verdict {
+    let start = cpu.frame();
+    let mut frame = 0;
+    let finished = panic::catch_unwind(AssertUnwindSafe(|| -> Result<_, CpuError> {
+        while frame < frames {
+            cpu.tick()?;
+            if cpu.frame() - start != frame {
+                frame = cpu.frame() - start;
+                if !observe(frame, cpu.get_display().hash()) {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }));
+    match finished {
+        Ok(Ok(())) => Verdict::Identical,
+        Ok(Err(_)) | Err(_) => Verdict::Crashed { frame },
+    }
+}
@@ -0,0 +1,42 @@
+//! A compact, deterministic dump of every emulated frame, for byte-comparing
+//! runs across emulator versions.
+//!
+//! The file starts with the magic `CH8R` and a format version byte (2). A
+//! record follows for each 60 Hz frame, taken on the instruction that starts
+//! it: the display width and height as little-endian `u16`s, since
+//! SUPER-CHIP programs can switch resolution mid-run, and then the display
+//! packed 1 bit per pixel, MSB first, row by row.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::display::Display;
+
+const MAGIC: &[u8; 4] = b"CH8R";
+const VERSION: u8 = 2;
+
+pub struct RawDump {
+    out: BufWriter<File>,
+}
+
+impl RawDump {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        Ok(Self { out })
+    }
+
+    pub fn append(&mut self, display: &Display) -> io::Result<()> {
+        self.out
+            .write_all(&(display.width() as u16).to_le_bytes())?;
+        self.out
+            .write_all(&(display.height() as u16).to_le_bytes())?;
+        self.out.write_all(&display.packed())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::display::Display;
+
+/// Records every distinct display state of a run along with when it appeared.
+pub struct Recorder {
+    start: Instant,
+    frames: Vec<(Duration, Display)>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Captures `display` if it differs from the last recorded frame.
+    pub fn record(&mut self, display: &Display) {
+        if self.frames.last().map(|(_, last)| last) != Some(display) {
+            self.frames.push((self.start.elapsed(), display.clone()));
+        }
+    }
+
+    /// The largest resolution any recorded frame used. Frames in a lower
+    /// resolution are scaled up to it.
+    fn size(&self) -> (usize, usize) {
+        self.frames
+            .iter()
+            .map(|(_, display)| (display.width(), display.height()))
+            .max()
+            .unwrap_or((Display::WIDTH, Display::HEIGHT))
+    }
+
+    /// Converts the recording into a looping animated SVG, one `<rect>` per
+    /// pixel that is ever lit, with discrete keyframed opacity.
+    pub fn to_svg(&self) -> String {
+        let total = self.start.elapsed().as_secs_f64().max(0.001);
+        let (width, height) = self.size();
+        // Keep the picture the same size whatever the resolution.
+        let pixel_size = 10 * Display::WIDTH / width;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{sw}" height="{sh}" shape-rendering="crispEdges">"#,
+            w = width,
+            h = height,
+            sw = width * pixel_size,
+            sh = height * pixel_size,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" fill="black"/>"#,
+            width, height
+        );
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut times = vec!["0".to_string()];
+                let mut values = vec!["0"];
+                let mut current = false;
+                for (at, display) in &self.frames {
+                    let on = display.scaled_color(x, y, width) != 0;
+                    if on == current {
+                        continue;
+                    }
+                    current = on;
+                    let t = (at.as_secs_f64() / total).min(1.0);
+                    if t == 0.0 {
+                        values[0] = if on { "1" } else { "0" };
+                    } else {
+                        times.push(format!("{:.4}", t));
+                        values.push(if on { "1" } else { "0" });
+                    }
+                }
+                if !values.contains(&"1") {
+                    continue;
+                }
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="1" height="1" fill="white" opacity="{}"><animate attributeName="opacity" dur="{:.3}s" repeatCount="indefinite" calcMode="discrete" keyTimes="{}" values="{}"/></rect>"#,
+                    x,
+                    y,
+                    values[0],
+                    total,
+                    times.join(";"),
+                    values.join(";")
+                );
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Converts the recording into an asciinema v2 cast that redraws the
+    /// display as block art whenever it changes.
+    pub fn to_cast(&self) -> String {
+        let (width, height) = self.size();
+        let mut cast = String::new();
+        let _ = writeln!(
+            cast,
+            r#"{{"version": 2, "width": {}, "height": {}}}"#,
+            width,
+            height / 2
+        );
+        for (at, display) in &self.frames {
+            let screen = format!("\x1b[H\x1b[2J{}", display.to_block_art()).replace('\n', "\r\n");
+            let _ = writeln!(
+                cast,
+                r#"[{:.6}, "o", "{}"]"#,
+                at.as_secs_f64(),
+                escape_json(&screen)
+            );
+        }
+        cast
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
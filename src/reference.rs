@@ -0,0 +1,155 @@
+//! A deliberately simple CHIP-8 interpreter used as an oracle for the real
+//! core. It favours obviousness over speed and only supports the base
+//! instruction set with default quirks.
+
+use crate::cpu::{DEFAULT_IPS, PC_START, TIMER_HZ};
+use crate::display::Display;
+use crate::font::{FontSet, BIG_FONT};
+
+pub struct Reference {
+    pub memory: [u8; 4096],
+    pub display: Display,
+    pub pc: u16,
+    pub index: u16,
+    pub registers: [u8; 16],
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// Instructions into the current 60Hz frame.
+    frame_phase: f32,
+}
+
+impl Reference {
+    pub fn new(rom: &[u8]) -> Self {
+        let mut memory = [0; 4096];
+        let font = FontSet::default().data();
+        memory[..font.len()].copy_from_slice(font);
+        memory[font.len()..font.len() + BIG_FONT.len()].copy_from_slice(&BIG_FONT);
+        let len = rom.len().min(memory.len() - PC_START);
+        memory[PC_START..PC_START + len].copy_from_slice(&rom[..len]);
+        Self {
+            memory,
+            display: Display::new(),
+            pc: PC_START as u16,
+            index: 0,
+            registers: [0; 16],
+            stack: Vec::new(),
+            delay_timer: 0,
+            sound_timer: 0,
+            frame_phase: 0.0,
+        }
+    }
+
+    pub fn opcode(&self) -> u16 {
+        let pc = self.pc as usize;
+        u16::from_be_bytes([self.memory[pc], self.memory[pc + 1]])
+    }
+
+    /// Executes one instruction, or returns the opcode if it isn't supported.
+    pub fn step(&mut self) -> Result<(), u16> {
+        // Timers count down every DEFAULT_IPS / TIMER_HZ instructions.
+        self.frame_phase += 1.0;
+        while self.frame_phase >= DEFAULT_IPS / TIMER_HZ {
+            self.frame_phase -= DEFAULT_IPS / TIMER_HZ;
+            self.delay_timer = self.delay_timer.saturating_sub(1);
+            self.sound_timer = self.sound_timer.saturating_sub(1);
+        }
+
+        let opcode = self.opcode();
+        let x = ((opcode >> 8) & 0xF) as usize;
+        let y = ((opcode >> 4) & 0xF) as usize;
+        let n = opcode & 0xF;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+        let vx = self.registers[x];
+        let vy = self.registers[y];
+        let mut next = self.pc + 2;
+
+        match (opcode >> 12, n) {
+            (0x0, _) if opcode == 0x00E0 => self.display = Display::new(),
+            (0x0, _) if opcode == 0x00EE => next = self.stack.pop().ok_or(opcode)?,
+            (0x1, _) => next = nnn,
+            (0x2, _) => {
+                self.stack.push(next);
+                next = nnn;
+            }
+            (0x3, _) if vx == nn => next += 2,
+            (0x3, _) => {}
+            (0x4, _) if vx != nn => next += 2,
+            (0x4, _) => {}
+            (0x5, 0x0) if vx == vy => next += 2,
+            (0x5, 0x0) => {}
+            (0x6, _) => self.registers[x] = nn,
+            (0x7, _) => self.registers[x] = vx.wrapping_add(nn),
+            (0x8, 0x0) => self.registers[x] = vy,
+            (0x8, 0x1) => self.registers[x] = vx | vy,
+            (0x8, 0x2) => self.registers[x] = vx & vy,
+            (0x8, 0x3) => self.registers[x] = vx ^ vy,
+            (0x8, 0x4) => {
+                let sum = vx as u16 + vy as u16;
+                self.registers[x] = sum as u8;
+                self.registers[0xF] = (sum > 0xFF) as u8;
+            }
+            (0x8, 0x5) => {
+                self.registers[x] = vx.wrapping_sub(vy);
+                self.registers[0xF] = (vx >= vy) as u8;
+            }
+            (0x8, 0x6) => {
+                self.registers[x] = vx >> 1;
+                self.registers[0xF] = vx & 1;
+            }
+            (0x8, 0x7) => {
+                self.registers[x] = vy.wrapping_sub(vx);
+                self.registers[0xF] = (vy >= vx) as u8;
+            }
+            (0x8, 0xE) => {
+                self.registers[x] = vx << 1;
+                self.registers[0xF] = vx >> 7;
+            }
+            (0x9, 0x0) if vx != vy => next += 2,
+            (0x9, 0x0) => {}
+            (0xA, _) => self.index = nnn,
+            (0xB, _) => next = nnn + self.registers[0] as u16,
+            (0xD, _) => {
+                let mut collision = 0;
+                for row in 0..n as usize {
+                    let sprite = self.memory[self.index as usize + row];
+                    for col in 0..8 {
+                        if sprite & (0x80 >> col) != 0 {
+                            let px = (vx as usize + col) % Display::WIDTH;
+                            let py = (vy as usize + row) % Display::HEIGHT;
+                            let pixel = &mut self.display.planes[0][py][px];
+                            if *pixel {
+                                collision = 1;
+                            }
+                            *pixel = !*pixel;
+                        }
+                    }
+                }
+                self.registers[0xF] = collision;
+            }
+            (0xF, _) if nn == 0x15 => self.delay_timer = vx,
+            (0xF, _) if nn == 0x18 => self.sound_timer = vx,
+            (0xF, _) if nn == 0x1E => self.index += vx as u16,
+            (0xF, _) if nn == 0x29 => self.index = (vx as u16 & 0xF) * 5,
+            (0xF, _) if nn == 0x33 => {
+                let i = self.index as usize;
+                self.memory[i] = vx / 100;
+                self.memory[i + 1] = vx / 10 % 10;
+                self.memory[i + 2] = vx % 10;
+            }
+            (0xF, _) if nn == 0x55 => {
+                let i = self.index as usize;
+                self.memory[i..=i + x].copy_from_slice(&self.registers[..=x]);
+            }
+            (0xF, _) if nn == 0x65 => {
+                let i = self.index as usize;
+                self.registers[..=x].copy_from_slice(&self.memory[i..=i + x]);
+            }
+            _ => return Err(opcode),
+        }
+
+        self.pc = next;
+        Ok(())
+    }
+}
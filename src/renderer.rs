@@ -1,19 +1,99 @@
 use crate::display::Display;
+use crate::palette::{Color, Palette};
 
-pub struct DisplayRenderer;
+pub struct DisplayRenderer {
+    pub palette: Palette,
+}
 
 impl DisplayRenderer {
-    pub fn draw(&self, display: &Display, output_frame: &mut [u8]) {
-        for (i, pixel) in output_frame.chunks_exact_mut(4).enumerate() {
-            let x = (i % Display::WIDTH) as usize;
-            let y = (i / Display::WIDTH) as usize;
-            let is_on = display.pixels[y][x];
-            let color = if is_on {
-                [0xFF, 0xFF, 0xFF, 0xFF]
+    /// Brightness of lit pixels while the display is dimmed, out of 0xFF.
+    const DIMMED: u16 = 0x60;
+
+    /// Columns separating the two displays in comparison mode.
+    pub const COMPARE_GAP: usize = 2;
+
+    /// Size of the frame [`DisplayRenderer::draw`] fills for `display`.
+    pub fn frame_size(display: &Display) -> (usize, usize) {
+        (display.width(), display.height())
+    }
+
+    /// Size of the frame [`DisplayRenderer::draw_compare`] fills: both
+    /// displays side by side at the higher of their two resolutions.
+    pub fn compare_frame_size(left: &Display, right: &Display) -> (usize, usize) {
+        let (width, height) = Self::frame_size(if left.width() >= right.width() {
+            left
+        } else {
+            right
+        });
+        (width * 2 + Self::COMPARE_GAP, height)
+    }
+
+    pub fn draw(&self, display: &Display, output_frame: &mut [u8], dimmed: bool) {
+        self.draw_at(display, None, output_frame, display.width(), 0, dimmed);
+    }
+
+    /// Draws `left` and `right` next to each other, marking the pixels where
+    /// they differ in red. A low-resolution display next to a high-resolution
+    /// one is scaled up to match.
+    pub fn draw_compare(
+        &self,
+        left: &Display,
+        right: &Display,
+        output_frame: &mut [u8],
+        dimmed: bool,
+    ) {
+        for pixel in output_frame.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0x40, 0x40, 0x40, 0xFF]);
+        }
+        let (stride, _) = Self::compare_frame_size(left, right);
+        let right_offset = (stride + Self::COMPARE_GAP) / 2;
+        self.draw_at(left, Some(right), output_frame, stride, 0, dimmed);
+        self.draw_at(
+            right,
+            Some(left),
+            output_frame,
+            stride,
+            right_offset,
+            dimmed,
+        );
+    }
+
+    fn draw_at(
+        &self,
+        display: &Display,
+        other: Option<&Display>,
+        output_frame: &mut [u8],
+        stride: usize,
+        x_offset: usize,
+        dimmed: bool,
+    ) {
+        let shade = |color: Color| {
+            if dimmed {
+                let [r, g, b, a] = color;
+                let dim = |c: u8| (c as u16 * Self::DIMMED / 0xFF) as u8;
+                [dim(r), dim(g), dim(b), a]
             } else {
-                [0x00, 0x00, 0x00, 0x00]
-            };
-            pixel.copy_from_slice(&color);
+                color
+            }
+        };
+        // Indexed by `Display::color`.
+        let colors: Vec<Color> = display
+            .colors(&self.palette)
+            .into_iter()
+            .map(shade)
+            .collect();
+        let highlight = shade(self.palette.highlight());
+        // Both displays are drawn at the higher of their resolutions.
+        let width = other.map_or(display.width(), |other| display.width().max(other.width()));
+        let height = display.height() * width / display.width();
+        for y in 0..height {
+            for x in 0..width {
+                let color = display.scaled_color(x, y, width);
+                let differs = other.is_some_and(|other| other.scaled_color(x, y, width) != color);
+                let color = if differs { highlight } else { colors[color] };
+                let i = (y * stride + x_offset + x) * 4;
+                output_frame[i..i + 4].copy_from_slice(&color);
+            }
         }
     }
 }
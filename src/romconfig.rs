@@ -0,0 +1,89 @@
+//! Per-ROM settings kept in a TOML file next to the ROM (`game.ch8.toml` or
+//! `game.toml`), so a folder of ROMs carries how each one should be run.
+//! Keys are named after the command-line options they stand in for:
+//!
+//! ```toml
+//! platform = "schip"
+//! quirks = ["shift", "jump"]
+//! tickrate = 30
+//! palette = "high-contrast"
+//! font-set = "vip"
+//! keypad-layout = "Key1,Key2,Key3,Key4,Q,W,E,R,A,S,D,F,Z,X,C,V"
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use log::warn;
+
+use crate::font::FontSet;
+use crate::keyboard::KeypadLayout;
+use crate::metadata;
+use crate::palette::PalettePreset;
+use crate::quirks::{Platform, Quirks};
+use crate::toml::{self, Value};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RomConfig {
+    pub platform: Option<Platform>,
+    pub quirks: Option<Quirks>,
+    /// Instructions per 60 Hz frame, as in Octo's options.
+    pub tickrate: Option<u32>,
+    pub palette: Option<PalettePreset>,
+    pub font_set: Option<FontSet>,
+    pub keypad_layout: Option<KeypadLayout>,
+}
+
+impl RomConfig {
+    /// Reads the config next to `rom`, if any. Settings that can't be read
+    /// are warned about and left unset.
+    pub fn load_sidecar(rom: &Path) -> Option<Self> {
+        let path = metadata::sidecar_path(rom, "toml")?;
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) => {
+                warn!("Could not read {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        let entries = match toml::parse(&source) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Ignoring malformed {}: {}", path.display(), err);
+                return None;
+            }
+        };
+
+        let mut config = Self::default();
+        for (key, value) in &entries {
+            if let Err(err) = config.set(key, value) {
+                warn!("{}: ignoring {}: {}", path.display(), key, err);
+            }
+        }
+        Some(config)
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        let list = || value.as_list().ok_or("expected a string or a list");
+        match key {
+            "platform" => self.platform = Some(parse(value)?),
+            "quirks" => self.quirks = Some(list()?.parse()?),
+            "tickrate" => {
+                self.tickrate = match value.as_f64() {
+                    Some(rate) if rate >= 1.0 && rate <= u32::MAX as f64 => Some(rate as u32),
+                    _ => return Err("expected a positive number".to_string()),
+                }
+            }
+            "palette" => self.palette = Some(parse(value)?),
+            "font-set" => self.font_set = Some(parse(value)?),
+            "keypad-layout" => self.keypad_layout = Some(list()?.parse()?),
+            _ => return Err("unknown setting".to_string()),
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: FromStr<Err = String>>(value: &Value) -> Result<T, String> {
+    value.as_str().ok_or("expected a string")?.parse()
+}
@@ -0,0 +1,81 @@
+//! Recognising known ROMs by hash, so the platform they were written for can
+//! be picked without the user having to know it. Entries follow the
+//! community CHIP-8 database, which keys programs on the SHA-1 of the ROM.
+
+use crate::quirks::Platform;
+
+pub struct Entry {
+    /// Lowercase hex SHA-1 of the ROM file.
+    pub sha1: &'static str,
+    pub title: &'static str,
+    pub platform: Platform,
+}
+
+const ENTRIES: &[Entry] = &[Entry {
+    sha1: "1ba58656810b67fd131eb9af3e3987863bf26c90",
+    title: "IBM Logo",
+    platform: Platform::Chip8,
+}];
+
+/// The entry for `rom`, if it's one the database knows.
+pub fn lookup(rom: &[u8]) -> Option<&'static Entry> {
+    let digest: String = sha1(rom).iter().map(|b| format!("{:02x}", b)).collect();
+    ENTRIES.iter().find(|entry| entry.sha1 == digest)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
@@ -0,0 +1,46 @@
+//! SUPER-CHIP's RPL user flags, which FX75 and FX85 save and load. On the
+//! HP 48 they outlived the program, so games kept settings and high scores
+//! in them; here they are kept in a small file next to the ROM
+//! (`game.ch8.rpl`).
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::cpu::RPL_FLAGS;
+
+pub type Flags = [u8; RPL_FLAGS];
+
+/// Where the flags of the ROM at `rom` are kept.
+pub fn path(rom: &Path) -> PathBuf {
+    let mut appended = rom.as_os_str().to_owned();
+    appended.push(".rpl");
+    PathBuf::from(appended)
+}
+
+/// Reads the flags saved at `path`. A missing or unreadable file gives
+/// cleared flags, as on a fresh calculator.
+pub fn load(path: &Path) -> Flags {
+    match fs::read(path) {
+        Ok(bytes) => bytes.as_slice().try_into().unwrap_or_else(|_| {
+            warn!(
+                "{}: expected {} bytes of RPL flags, got {}",
+                path.display(),
+                RPL_FLAGS,
+                bytes.len()
+            );
+            Flags::default()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Flags::default(),
+        Err(e) => {
+            warn!("{}: {}", path.display(), e);
+            Flags::default()
+        }
+    }
+}
+
+pub fn save(path: &Path, flags: &Flags) -> io::Result<()> {
+    fs::write(path, flags)
+}
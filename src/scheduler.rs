@@ -0,0 +1,203 @@
+//! When instructions run and when the 60 Hz timers count down. Nothing here
+//! reads the clock: the app passes in `Instant::now()`, while anything that
+//! wants repeatable timing can pass made-up instants instead.
+
+use std::time::{Duration, Instant};
+
+use crate::cpu::{DEFAULT_IPS, TIMER_HZ};
+
+/// The most emulated time made up for after a stall, e.g. the machine being
+/// locked by a long redraw, so the emulator doesn't race through a backlog
+/// afterwards.
+pub const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+/// Divides executed instructions into 60 Hz frames. The timers count down,
+/// and draws held back for the display-wait quirk run, as each frame starts.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameClock {
+    instructions_per_frame: f32,
+    /// Instructions into the current frame.
+    phase: f32,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self {
+            instructions_per_frame: DEFAULT_IPS / TIMER_HZ,
+            phase: 0.0,
+        }
+    }
+}
+
+impl FrameClock {
+    pub fn set_instructions_per_second(&mut self, ips: f32) {
+        self.instructions_per_frame = ips / TIMER_HZ;
+    }
+
+    pub fn instructions_per_second(&self) -> f32 {
+        self.instructions_per_frame * TIMER_HZ
+    }
+
+    /// Starts over at the beginning of a frame.
+    pub fn restart(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /// Counts one instruction, returning how many frames start with it:
+    /// usually none, sometimes one, more only above 60 frames per
+    /// instruction.
+    pub fn step(&mut self) -> u32 {
+        self.phase += 1.0;
+        let mut frames = 0;
+        while self.phase >= self.instructions_per_frame {
+            self.phase -= self.instructions_per_frame;
+            frames += 1;
+        }
+        frames
+    }
+
+    /// How many instructions run until the next frame starts, counting the
+    /// one it starts on.
+    pub fn instructions_to_next_frame(&self) -> u32 {
+        (self.instructions_per_frame - self.phase).ceil().max(1.0) as u32
+    }
+}
+
+/// Paces instructions against the passage of time: tells how many are due
+/// at each moment it's asked about.
+pub struct Scheduler {
+    instructions_per_second: f32,
+    last: Instant,
+    /// Fractions of an instruction owed, carried between calls so odd
+    /// intervals don't round the speed down.
+    owed: f32,
+}
+
+impl Scheduler {
+    pub fn new(instructions_per_second: f32, now: Instant) -> Self {
+        Self {
+            instructions_per_second,
+            last: now,
+            owed: 0.0,
+        }
+    }
+
+    /// The instructions due between the last call and `now` at `speed`
+    /// times the normal rate, and how much time past [`MAX_CATCH_UP`] was
+    /// let go rather than made up for.
+    pub fn advance(&mut self, now: Instant, speed: f32) -> (u32, Duration) {
+        let elapsed = now.saturating_duration_since(self.last);
+        self.last = now;
+        let skipped = elapsed.saturating_sub(MAX_CATCH_UP);
+        self.owed += (elapsed - skipped).as_secs_f32() * self.instructions_per_second * speed;
+        let due = self.owed as u32;
+        self.owed -= due as f32;
+        (due, skipped)
+    }
+
+    /// Lets the time up to `now` pass without anything coming due, e.g.
+    /// while paused.
+    pub fn idle(&mut self, now: Instant) {
+        self.last = now;
+        self.owed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::Cpu;
+
+    const IPS: f32 = 600.0;
+
+    #[test]
+    fn advance_runs_at_the_normal_rate() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(IPS, start);
+        let mut due = 0;
+        for ms in 1..=1000 {
+            let (instructions, skipped) = scheduler.advance(start + Duration::from_millis(ms), 1.0);
+            assert_eq!(skipped, Duration::ZERO);
+            due += instructions;
+        }
+        assert!((599..=600).contains(&due), "{} due", due);
+    }
+
+    #[test]
+    fn advance_scales_with_speed() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(IPS, start);
+        let (due, _) = scheduler.advance(start + Duration::from_millis(100), 4.0);
+        assert!((239..=240).contains(&due), "{} due", due);
+    }
+
+    #[test]
+    fn advance_lets_a_long_stall_go() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(IPS, start);
+        let (due, skipped) = scheduler.advance(start + Duration::from_secs(1), 1.0);
+        assert_eq!(skipped, Duration::from_secs(1) - MAX_CATCH_UP);
+        assert!((149..=150).contains(&due), "{} due", due);
+    }
+
+    #[test]
+    fn idle_lets_time_pass() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(IPS, start);
+        scheduler.idle(start + Duration::from_secs(5));
+        let (due, skipped) = scheduler.advance(start + Duration::from_secs(5), 1.0);
+        assert_eq!((due, skipped), (0, Duration::ZERO));
+    }
+
+    #[test]
+    fn frames_start_every_instructions_per_frame() {
+        let mut clock = FrameClock::default();
+        clock.set_instructions_per_second(IPS);
+        let frames: Vec<u32> = (0..30).map(|_| clock.step()).collect();
+        let starts: Vec<usize> = (0..30).filter(|&i| frames[i] == 1).collect();
+        assert_eq!(starts, [9, 19, 29]);
+        assert_eq!(frames.iter().sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn frames_run_ahead_of_slow_instructions() {
+        let mut clock = FrameClock::default();
+        clock.set_instructions_per_second(30.0);
+        assert_eq!(clock.step(), 2);
+    }
+
+    #[test]
+    fn delay_timer_counts_down_once_a_frame() {
+        let mut cpu = Cpu::new();
+        cpu.set_instructions_per_second(IPS);
+        // V0 = 10, DT = V0, then loop forever.
+        cpu.load_rom(&[0x60, 0x0A, 0xF0, 0x15, 0x12, 0x04]);
+        cpu.tick().unwrap();
+        cpu.tick().unwrap();
+        assert_eq!(cpu.delay_timer, 10);
+        let timers: Vec<u8> = (2..30)
+            .map(|_| {
+                cpu.tick().unwrap();
+                cpu.delay_timer
+            })
+            .collect();
+        // Frames start on the 10th, 20th and 30th instructions.
+        assert_eq!(timers[..7], [10; 7]);
+        assert_eq!(timers[7..17], [9; 10]);
+        assert_eq!(timers[17..27], [8; 10]);
+        assert_eq!(timers[27], 7);
+    }
+
+    #[test]
+    fn instructions_to_next_frame_counts_the_boundary() {
+        let mut clock = FrameClock::default();
+        clock.set_instructions_per_second(IPS);
+        assert_eq!(clock.instructions_to_next_frame(), 10);
+        (0..4).for_each(|_| {
+            clock.step();
+        });
+        assert_eq!(clock.instructions_to_next_frame(), 6);
+        clock.restart();
+        assert_eq!(clock.instructions_to_next_frame(), 10);
+    }
+}
@@ -0,0 +1,70 @@
+//! PNG screenshots that double as savestates: alongside the image they
+//! carry the ROM and the serialized machine state in `tEXt` chunks, so a
+//! screenshot attached to a bug report can be opened to resume from
+//! exactly that moment.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::clipboard;
+use crate::cpu::Cpu;
+use crate::palette::Palette;
+use crate::png;
+use crate::renderer::DisplayRenderer;
+
+const ROM_KEY: &str = "chip8-rom";
+const STATE_KEY: &str = "chip8-state";
+
+/// Writes the display, ROM and machine state to a PNG at `path`.
+pub fn save(path: &Path, cpu: &Cpu, rom: &[u8], palette: Palette) -> Result<(), String> {
+    let (width, height) = DisplayRenderer::frame_size(cpu.get_display());
+    let mut frame = vec![0; width * height * 4];
+    DisplayRenderer { palette }.draw(cpu.get_display(), &mut frame, false);
+    let hash = format!("{:016x}", cpu.get_display().hash());
+    let png = png::encode_with_text(
+        width,
+        height,
+        &frame,
+        &[
+            ("Software", "chip8-rust"),
+            ("chip8-display-hash", &hash),
+            (ROM_KEY, &hex(rom)),
+            (STATE_KEY, &hex(&cpu.save_state())),
+        ],
+    );
+    fs::write(path, png).map_err(|e| e.to_string())
+}
+
+/// Reads the ROM and machine state back from a screenshot.
+pub fn load(path: &Path) -> Result<(Vec<u8>, Cpu), String> {
+    let png = fs::read(path).map_err(|e| e.to_string())?;
+    let text = png::read_text(&png)?;
+    let field = |key: &str| {
+        text.iter()
+            .find(|(keyword, _)| keyword == key)
+            .map(|(_, value)| value.as_str())
+            .ok_or_else(|| format!("no {} in screenshot", key))
+    };
+    let rom = clipboard::parse_hex(field(ROM_KEY)?)?;
+    let cpu = Cpu::load_state(&clipboard::parse_hex(field(STATE_KEY)?)?)?;
+    Ok((rom, cpu))
+}
+
+/// Whether `path` should be opened as a screenshot rather than a ROM.
+pub fn is_screenshot(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("png")
+}
+
+/// Hex with a line break every 32 bytes, so the chunk stays readable in
+/// tools that dump PNG text.
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2 + bytes.len() / 32);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 && i % 32 == 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{:02x}", byte);
+    }
+    out
+}
@@ -0,0 +1,24 @@
+/// Maps program addresses back to the source lines they were assembled from.
+pub struct SourceMap {
+    pub lines: Vec<String>,
+    /// `(address, line)` pairs in ascending address order.
+    pub addresses: Vec<(u16, usize)>,
+}
+
+impl SourceMap {
+    /// The zero-based source line of the instruction at `address`.
+    pub fn line_at(&self, address: u16) -> Option<usize> {
+        self.addresses
+            .iter()
+            .find(|(a, _)| *a == address)
+            .map(|(_, line)| *line)
+    }
+
+    /// The address of the instruction on `line`, if it produced any code.
+    pub fn address_of(&self, line: usize) -> Option<u16> {
+        self.addresses
+            .iter()
+            .find(|(_, l)| *l == line)
+            .map(|(address, _)| *address)
+    }
+}
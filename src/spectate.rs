@@ -0,0 +1,106 @@
+//! Mirrors the display to any number of viewers over TCP.
+//!
+//! Every time the display changes, each connected viewer receives one frame:
+//!
+//! ```text
+//! u16  length of the rest of the frame (big endian)
+//! u8   width
+//! u8   height
+//! u16  pressed keys, bit N for key N (big endian)
+//! ...  display rows packed MSB first, run-length encoded as
+//!      (count, byte) pairs with count in 1..=255
+//! ```
+//!
+//! Viewers that fall behind are disconnected rather than slowing down the
+//! emulator.
+
+use std::io::{self, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use log::{info, warn};
+
+use crate::display::Display;
+
+pub struct Spectators {
+    viewers: Arc<Mutex<Vec<TcpStream>>>,
+    joined: Arc<AtomicBool>,
+    last_hash: Option<u64>,
+}
+
+impl Spectators {
+    /// Starts accepting viewers on `addr` in the background.
+    pub fn listen(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Spectators can connect to {}", listener.local_addr()?);
+        let viewers = Arc::new(Mutex::new(Vec::new()));
+        let joined = Arc::new(AtomicBool::new(false));
+        let accepted = Arc::clone(&viewers);
+        let announce = Arc::clone(&joined);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream.and_then(|stream| {
+                    stream.set_nodelay(true)?;
+                    stream.set_nonblocking(true)?;
+                    Ok(stream)
+                }) {
+                    Ok(stream) => {
+                        info!("Spectator connected from {:?}", stream.peer_addr());
+                        accepted.lock().unwrap().push(stream);
+                        announce.store(true, Ordering::Relaxed);
+                    }
+                    Err(e) => warn!("Failed to accept spectator: {}", e),
+                }
+            }
+        });
+        Ok(Self {
+            viewers,
+            joined,
+            last_hash: None,
+        })
+    }
+
+    /// Sends the display to every viewer if it changed since the last call
+    /// or someone new joined.
+    pub fn broadcast(&mut self, display: &Display, keys: u16) {
+        let hash = display.hash();
+        let joined = self.joined.swap(false, Ordering::Relaxed);
+        if self.last_hash == Some(hash) && !joined {
+            return;
+        }
+        self.last_hash = Some(hash);
+
+        let frame = encode(display, keys);
+        self.viewers
+            .lock()
+            .unwrap()
+            .retain_mut(|viewer| match viewer.write_all(&frame) {
+                Ok(()) => true,
+                Err(e) => {
+                    if e.kind() == ErrorKind::WouldBlock {
+                        warn!("Dropping spectator {:?}: too slow", viewer.peer_addr());
+                    }
+                    false
+                }
+            });
+    }
+}
+
+fn encode(display: &Display, keys: u16) -> Vec<u8> {
+    let mut body = vec![display.width() as u8, display.height() as u8];
+    body.extend(keys.to_be_bytes());
+    let mut bytes = display.packed().into_iter().peekable();
+    while let Some(byte) = bytes.next() {
+        let mut count = 1u8;
+        while count < u8::MAX && bytes.next_if_eq(&byte).is_some() {
+            count += 1;
+        }
+        body.extend([count, byte]);
+    }
+
+    let mut frame = (body.len() as u16).to_be_bytes().to_vec();
+    frame.extend(body);
+    frame
+}
@@ -0,0 +1,34 @@
+use std::time::{Duration, Instant};
+
+/// Measures how many instructions actually ran over the last second.
+pub struct SpeedMeter {
+    window_start: Instant,
+    count: u32,
+    rate: u32,
+}
+
+impl SpeedMeter {
+    pub fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            count: 0,
+            rate: 0,
+        }
+    }
+
+    /// Records `instructions` executed just now.
+    pub fn record(&mut self, instructions: u32) {
+        self.count += instructions;
+        let now = Instant::now();
+        if now - self.window_start >= Duration::from_secs(1) {
+            self.rate = self.count;
+            self.count = 0;
+            self.window_start = now;
+        }
+    }
+
+    /// Instructions per second over the last full second.
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+}
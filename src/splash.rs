@@ -0,0 +1,86 @@
+use crate::cpu::{Cpu, CpuEvent, PC_START};
+use crate::display::Display;
+
+/// How long the splash stays on screen before the user ROM is loaded.
+pub const DURATION_SECS: f32 = 1.0;
+
+const TOP: usize = 9;
+const LINE_HEIGHT: usize = 9;
+const GLYPH_ADVANCE: usize = 5;
+const GLYPH_HEIGHT: u16 = 5;
+
+/// Letters not covered by the built-in hex font.
+const GLYPHS: [(char, [u8; 5]); 6] = [
+    ('H', [0x90, 0x90, 0xF0, 0x90, 0x90]),
+    ('I', [0xE0, 0x40, 0x40, 0x40, 0xE0]),
+    ('P', [0xF0, 0x90, 0xF0, 0x80, 0x80]),
+    ('V', [0x90, 0x90, 0x90, 0x90, 0x60]),
+    ('-', [0x00, 0x00, 0xF0, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+];
+
+enum Sprite {
+    Font(u16),
+    Data(usize),
+}
+
+/// Builds a small program that prints the emulator name and version, then
+/// loops forever.
+pub fn rom() -> Vec<u8> {
+    let lines = [
+        String::from("CHIP-8"),
+        format!("V{}", env!("CARGO_PKG_VERSION")),
+    ];
+
+    let mut draws = Vec::new();
+    let mut data: Vec<[u8; 5]> = Vec::new();
+    for (row, line) in lines.iter().enumerate() {
+        let width = line.chars().count() * GLYPH_ADVANCE - 1;
+        let mut x = Display::WIDTH.saturating_sub(width) / 2;
+        let y = TOP + row * LINE_HEIGHT;
+        for c in line.chars() {
+            let sprite = if let Some(digit) = c.to_digit(16) {
+                Some(Sprite::Font(digit as u16 * GLYPH_HEIGHT))
+            } else if let Some((_, glyph)) = GLYPHS.iter().find(|(g, _)| *g == c) {
+                data.push(*glyph);
+                Some(Sprite::Data(data.len() - 1))
+            } else {
+                None
+            };
+            if let Some(sprite) = sprite {
+                draws.push((x as u8, y as u8, sprite));
+            }
+            x += GLYPH_ADVANCE;
+        }
+    }
+
+    let code_len = 2 + draws.len() * 8 + 2;
+    let data_start = (PC_START + code_len) as u16;
+    let mut rom = Vec::with_capacity(code_len + data.len() * 5);
+    let mut emit = |opcode: u16| rom.extend_from_slice(&opcode.to_be_bytes());
+
+    emit(0x00E0);
+    for (x, y, sprite) in draws {
+        let addr = match sprite {
+            Sprite::Font(addr) => addr,
+            Sprite::Data(index) => data_start + index as u16 * GLYPH_HEIGHT,
+        };
+        emit(0x6000 | x as u16);
+        emit(0x6100 | y as u16);
+        emit(0xA000 | addr);
+        emit(0xD010 | GLYPH_HEIGHT);
+    }
+    emit(0x1000 | (data_start - 2));
+
+    for glyph in data {
+        rom.extend_from_slice(&glyph);
+    }
+    rom
+}
+
+/// Loads the splash program into `cpu` and runs it until it settles on its
+/// final jump-to-self.
+pub fn show(cpu: &mut Cpu) {
+    cpu.load_rom(&rom());
+    while cpu.tick().expect("splash ROM faulted") != Some(CpuEvent::Halted) {}
+}
@@ -0,0 +1,229 @@
+//! A small TOML reader, enough for per-ROM config sidecars: top-level
+//! `key = value` pairs with strings, numbers, booleans and arrays of those.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// A string, or an array of strings joined with commas, as the
+    /// command-line options take lists.
+    pub fn as_list(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .map(|items| items.join(",")),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a document into its key/value pairs, in order. Errors name the
+/// line they were found on.
+pub fn parse(source: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut parser = Parser {
+        chars: source.chars().peekable(),
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|e| format!("line {}: {}", parser.line, e))
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skips spaces and tabs, and with `newlines` also line breaks and
+    /// comments.
+    fn skip_whitespace(&mut self, newlines: bool) {
+        loop {
+            match self.chars.peek() {
+                Some(' ' | '\t') => {}
+                Some('\r' | '\n') if newlines => {}
+                Some('#') if newlines => {
+                    while self.chars.peek().is_some_and(|c| *c != '\n') {
+                        self.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected {:?}, found {:?}", expected, c)),
+            None => Err(format!("expected {:?}, found end of input", expected)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<(String, Value)>, String> {
+        let mut entries: Vec<(String, Value)> = Vec::new();
+        loop {
+            self.skip_whitespace(true);
+            if self.chars.peek().is_none() {
+                return Ok(entries);
+            }
+            if self.chars.peek() == Some(&'[') {
+                return Err("tables aren't supported".to_string());
+            }
+            let key = self.key()?;
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(format!("duplicate key {}", key));
+            }
+            self.skip_whitespace(false);
+            self.expect('=')?;
+            self.skip_whitespace(false);
+            let value = self.value()?;
+            entries.push((key, value));
+            self.skip_whitespace(false);
+            match self.chars.peek() {
+                None | Some('\r' | '\n' | '#') => {}
+                Some(c) => return Err(format!("unexpected character {:?}", c)),
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        if self.chars.peek() == Some(&'"') {
+            return self.string();
+        }
+        let mut key = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        {
+            key.push(c);
+        }
+        if key.is_empty() {
+            return Err("expected a key".to_string());
+        }
+        Ok(key)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek() {
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some(c) if c.is_ascii_alphanumeric() || matches!(c, '-' | '+') => self.bare(),
+            Some(c) => Err(format!("unexpected character {:?}", c)),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace(true);
+            if self.chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace(true);
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err("expected ',' or ']' in array".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some(c @ ('"' | '\\')) => s.push(c),
+                    Some(c) => return Err(format!("invalid escape \\{}", c)),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(s),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// Booleans and numbers, which TOML writes without quotes.
+    fn bare(&mut self) -> Result<Value, String> {
+        let mut text = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'))
+        {
+            text.push(c);
+        }
+        let digits = text.replace('_', "");
+        let value = match text.as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => match digits.strip_prefix("0x") {
+                Some(hex) => i64::from_str_radix(hex, 16).ok().map(Value::Integer),
+                None => digits
+                    .parse()
+                    .map(Value::Integer)
+                    .or_else(|_| digits.parse().map(Value::Float))
+                    .ok(),
+            },
+        };
+        value.ok_or_else(|| format!("invalid value {}", text))
+    }
+}
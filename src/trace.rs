@@ -0,0 +1,69 @@
+//! The most recent instructions executed, so a fault can be traced back to
+//! what led up to it.
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+use crate::cpu::CpuError;
+
+/// How many instructions are kept.
+const TRACE_LEN: usize = 32;
+
+#[derive(Default, Clone)]
+pub struct Trace {
+    entries: VecDeque<(u16, u16)>,
+}
+
+impl Trace {
+    /// Records that `opcode` at `address` was executed.
+    pub fn record(&mut self, address: u16, opcode: u16) {
+        if self.entries.len() == TRACE_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((address, opcode));
+    }
+
+    /// Address and opcode of each instruction, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &(u16, u16)> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// An instruction that failed or made the emulator panic.
+#[derive(Clone)]
+pub struct Fault {
+    pub pc: u16,
+    pub message: String,
+}
+
+impl Fault {
+    pub fn new(pc: u16, payload: Box<dyn Any + Send>) -> Self {
+        Self {
+            pc,
+            message: panic_message(payload),
+        }
+    }
+}
+
+impl From<CpuError> for Fault {
+    fn from(error: CpuError) -> Self {
+        Self {
+            pc: error.pc(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The message a panic was raised with.
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or_else(|| "unknown panic".to_string(), |s| s.to_string()),
+    }
+}
@@ -0,0 +1,83 @@
+use crate::cpu::{Cpu, PC_START};
+
+/// A tiny program that draws a digit and a smiley, then loops forever.
+pub const ROM: &[u8] = &[
+    0x00, 0xE0, // CLS
+    0x6A, 0x0C, // LD VA, 12
+    0x6B, 0x08, // LD VB, 8
+    0x6C, 0x05, // LD VC, 5
+    0xFC, 0x29, // LD F, VC
+    0xDA, 0xB5, // DRW VA, VB, 5
+    0x7A, 0x06, // ADD VA, 6
+    0xA2, 0x14, // LD I, 0x214
+    0xDA, 0xB4, // DRW VA, VB, 4
+    0x12, 0x12, // JP 0x212
+    0x66, 0x00, 0x81, 0x7E, // smiley sprite
+];
+
+/// Where the sprite data starts; everything before it is code.
+pub const DATA_START: u16 = 0x214;
+
+/// The three stages every instruction goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Fetch,
+    Decode,
+    Execute,
+}
+
+impl Phase {
+    /// Translation key of the phase's description.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Phase::Fetch => "tutorial-fetch",
+            Phase::Decode => "tutorial-decode",
+            Phase::Execute => "tutorial-execute",
+        }
+    }
+}
+
+pub struct Tutorial {
+    phase: Phase,
+    instruction: u16,
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self {
+            phase: Phase::Fetch,
+            instruction: PC_START as u16,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Address of the instruction currently being walked through.
+    pub fn instruction(&self) -> u16 {
+        self.instruction
+    }
+
+    /// Translation key explaining what the current instruction is for.
+    pub fn note_key(&self) -> String {
+        let step = (self.instruction as usize).wrapping_sub(PC_START) / 2;
+        format!("tutorial-note-{}", step)
+    }
+
+    /// Moves to the next phase, executing the instruction when leaving the
+    /// decode phase.
+    pub fn advance(&mut self, cpu: &mut Cpu) {
+        self.phase = match self.phase {
+            Phase::Fetch => Phase::Decode,
+            Phase::Decode => {
+                cpu.tick().expect("tutorial ROM faulted");
+                Phase::Execute
+            }
+            Phase::Execute => {
+                self.instruction = cpu.pc;
+                Phase::Fetch
+            }
+        };
+    }
+}
@@ -0,0 +1,105 @@
+//! Watchpoints: pause when the program reads or writes a range of memory,
+//! e.g. a game's score variable. Each instruction's reads and writes are
+//! matched against them as the CPU logs them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::heatmap::Access;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    start: u16,
+    /// The last address watched, inclusive.
+    end: u16,
+    read: bool,
+    write: bool,
+}
+
+impl Watchpoint {
+    /// The first of `accesses`, as logged by [`Cpu::accesses`], that is of
+    /// a watched kind and falls in the range.
+    ///
+    /// [`Cpu::accesses`]: crate::cpu::Cpu::accesses
+    pub fn triggered(&self, accesses: &[(Access, usize)]) -> Option<Access> {
+        let range = self.start as usize..=self.end as usize;
+        accesses
+            .iter()
+            .find(|(access, address)| {
+                let watched = match access {
+                    Access::Read => self.read,
+                    Access::Write => self.write,
+                    Access::Execute => false,
+                };
+                watched && range.contains(address)
+            })
+            .map(|(access, _)| *access)
+    }
+
+    /// Whether the whole range lies below `size`.
+    pub fn fits(&self, size: usize) -> bool {
+        (self.end as usize) < size
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#05x}", self.start)?;
+        if self.end != self.start {
+            write!(f, "-{:#05x}", self.end)?;
+        }
+        let access = match (self.read, self.write) {
+            (true, false) => "r",
+            (false, true) => "w",
+            _ => "rw",
+        };
+        write!(f, ":{}", access)
+    }
+}
+
+/// `START[-END][:r|w|rw]`, with addresses in decimal or 0x hex. Without an
+/// access kind both reads and writes are watched.
+impl FromStr for Watchpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (range, access) = s.trim().split_once(':').unwrap_or((s.trim(), "rw"));
+        let (read, write) = match access.trim() {
+            "r" => (true, false),
+            "w" => (false, true),
+            "rw" | "wr" => (true, true),
+            other => return Err(format!("expected r, w or rw, got {}", other)),
+        };
+        let address = |s: &str| {
+            let s = s.trim();
+            match s.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16),
+                None => s.parse(),
+            }
+            .map_err(|_| format!("not an address: {}", s))
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (address(start)?, address(end)?),
+            None => (address(range)?, address(range)?),
+        };
+        if end < start {
+            return Err(format!("range ends before it starts: {}", range));
+        }
+        Ok(Self {
+            start,
+            end,
+            read,
+            write,
+        })
+    }
+}
+
+/// The access that stopped execution at a watchpoint.
+#[derive(Debug, Clone, Copy)]
+pub struct WatchHit {
+    pub watchpoint: Watchpoint,
+    pub access: Access,
+    /// The instruction responsible.
+    pub pc: u16,
+    pub opcode: u16,
+}
@@ -0,0 +1,172 @@
+//! Reusable imgui widgets for the emulator's views.
+//!
+//! Each widget draws into whatever window the caller has open and keeps no
+//! state of its own, so tools that embed the emulator can place a display,
+//! keypad, register view or disassembly next to their own UI.
+
+use imgui::{StyleColor, Ui};
+
+use crate::cpu::Cpu;
+use crate::disasm;
+use crate::display::Display;
+use crate::i18n::Translations;
+use crate::metadata::KEYPAD;
+use crate::palette::{Color, Palette};
+use crate::quirks::Setup;
+
+/// Draws `display` with `scale` screen pixels per CHIP-8 pixel. A display in
+/// a higher resolution is as wide, with smaller pixels.
+pub fn display(ui: &Ui, display: &Display, palette: Palette, scale: f32) {
+    let to_f32 = |color: Color| color.map(|c| c as f32 / 255.0);
+    let origin = ui.cursor_screen_pos();
+    let scale = scale * Display::WIDTH as f32 / display.width() as f32;
+    let size = [
+        display.width() as f32 * scale,
+        display.height() as f32 * scale,
+    ];
+    let colors = display.colors(&palette);
+    let draw_list = ui.get_window_draw_list();
+    draw_list
+        .add_rect(
+            origin,
+            [origin[0] + size[0], origin[1] + size[1]],
+            to_f32(palette.background()),
+        )
+        .filled(true)
+        .build();
+    for y in 0..display.height() {
+        for x in 0..display.width() {
+            let color = display.color(x, y);
+            if color == 0 {
+                continue;
+            }
+            let min = [origin[0] + x as f32 * scale, origin[1] + y as f32 * scale];
+            let max = [min[0] + scale, min[1] + scale];
+            draw_list
+                .add_rect(min, max, to_f32(colors[color]))
+                .filled(true)
+                .build();
+        }
+    }
+    ui.dummy(size);
+}
+
+/// Draws a hex keypad laid out like the COSMAC VIP's, highlighting the keys
+/// in `held`. `hint` labels keys with what the ROM uses them for. Returns
+/// the key being held down with the mouse, if any.
+pub fn keypad<'a>(ui: &Ui, held: u16, hint: impl Fn(u8) -> Option<&'a str>) -> Option<u8> {
+    let mut pressed = None;
+    for row in KEYPAD {
+        for (column, key) in row.into_iter().enumerate() {
+            if column > 0 {
+                ui.same_line();
+            }
+            let label = match hint(key) {
+                Some(hint) => format!("{:X}\n{}##key{:X}", key, hint, key),
+                None => format!("{:X}##key{:X}", key, key),
+            };
+            let token = (held & (1 << key) != 0)
+                .then(|| ui.push_style_color(StyleColor::Button, [0.9, 0.6, 0.1, 1.0]));
+            ui.button_with_size(label, [56.0, 40.0]);
+            if ui.is_item_active() {
+                pressed = Some(key);
+            }
+            if let Some(token) = token {
+                token.pop();
+            }
+        }
+    }
+    pressed
+}
+
+/// The program counter, index register, stack and display hash.
+pub fn registers(ui: &Ui, tr: &Translations, cpu: &Cpu) {
+    ui.text(format!("PC: {}", cpu.pc));
+    ui.text(format!("I: {}", cpu.index));
+    ui.text(tr.format(
+        "cpu-stack",
+        &[
+            ("depth", &cpu.stack_pointer),
+            ("size", &cpu.stack.len()),
+            ("max", &cpu.max_stack_depth),
+        ],
+    ));
+    ui.text(tr.format(
+        "cpu-display-hash",
+        &[("hash", &format!("{:016x}", cpu.get_display().hash()))],
+    ));
+}
+
+/// The platform and quirks in effect, and what picked them.
+pub fn setup(ui: &Ui, tr: &Translations, setup: &Setup) {
+    let platform = setup
+        .platform
+        .map_or(tr.get("cpu-no-platform"), |platform| platform.name());
+    ui.text(tr.format(
+        "cpu-platform",
+        &[
+            ("platform", &platform),
+            ("origin", &tr.get(setup.platform_origin.key())),
+        ],
+    ));
+    ui.text(tr.format(
+        "cpu-quirks",
+        &[
+            ("quirks", &setup.quirks),
+            ("origin", &tr.get(setup.quirks_origin.key())),
+        ],
+    ));
+}
+
+/// Disassembles `rows` instructions around the program counter, with the
+/// opcode reference in a tooltip. Returns the address of the line that was
+/// clicked, if any.
+pub fn disassembly(
+    ui: &Ui,
+    tr: &Translations,
+    cpu: &Cpu,
+    breakpoints: &[u16],
+    rows: usize,
+    selected: Option<usize>,
+) -> Option<usize> {
+    let mut clicked = None;
+    let memory = cpu.memory();
+    let start = cpu.pc.saturating_sub(16) as usize;
+    for addr in (start..memory.len() - 1).step_by(2).take(rows) {
+        let opcode = u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+        let marker = if addr == cpu.pc as usize { ">" } else { " " };
+        let breakpoint = if breakpoints.contains(&(addr as u16)) {
+            "*"
+        } else {
+            " "
+        };
+        let label = format!(
+            "{}{} {:#05x}: {:04X}  {}##{}",
+            marker,
+            breakpoint,
+            addr,
+            opcode,
+            disasm::disassemble(opcode),
+            addr
+        );
+        if imgui::Selectable::new(label)
+            .selected(selected == Some(addr))
+            .build(ui)
+        {
+            clicked = Some(addr);
+        }
+        if ui.is_item_hovered() {
+            if let Some(info) = disasm::lookup(opcode) {
+                ui.tooltip(|| {
+                    ui.text(info.pattern);
+                    ui.text(info.description);
+                    ui.text(tr.format("disasm-affects", &[("registers", &info.affects)]));
+                    if let Some(quirks) = info.quirks {
+                        ui.text(tr.format("disasm-quirks", &[("notes", &quirks)]));
+                    }
+                });
+            }
+        }
+    }
+    clicked
+}